@@ -0,0 +1,48 @@
+//! The time of reading a dataset of each primitive type, which takes the fast
+//! path of decoding without the dispatch of each element but for uint64.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use hdf5::{File, H5Type};
+use nu_plugin_from_hdf5::{from_hdf5::read_path, hdf5_ext::ToImage};
+use nu_protocol::Span;
+
+/// The number of elements of each dataset.
+const LEN: usize = 1 << 20;
+
+/// The image of a file kept in memory with the dataset `x` of `LEN` elements.
+fn image<T: H5Type + Copy>(val: T) -> Vec<u8> {
+    let file = File::with_options()
+        .with_fapl(|p| p.core_filebacked(false))
+        .create("decode.h5")
+        .unwrap();
+    file.new_dataset_builder()
+        .with_data(&vec![val; LEN])
+        .create("x")
+        .unwrap();
+    file.to_image().unwrap()
+}
+
+fn decode(c: &mut Criterion) {
+    let images = [
+        ("int8", image(1i8)),
+        ("int16", image(1i16)),
+        ("int32", image(1i32)),
+        ("int64", image(1i64)),
+        ("uint8", image(1u8)),
+        ("uint16", image(1u16)),
+        ("uint32", image(1u32)),
+        ("uint64", image(1u64)),
+        ("float32", image(1f32)),
+        ("float64", image(1f64)),
+    ];
+    let mut group = c.benchmark_group("decode");
+    for (name, bytes) in &images {
+        group.bench_function(*name, |b| {
+            b.iter(|| read_path(bytes, "x", None, Span::unknown()).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
@@ -0,0 +1,7 @@
+fn main() {
+    println!("cargo::rustc-check-cfg=cfg(have_direct)");
+    // Set by hdf5-sys from the configuration of the linked HDF5.
+    if std::env::var_os("DEP_HDF5_HAVE_DIRECT").is_some() {
+        println!("cargo::rustc-cfg=have_direct");
+    }
+}
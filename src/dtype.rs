@@ -0,0 +1,323 @@
+use hdf5::{
+    from_id,
+    globals::{H5T_STD_REF_DSETREG, H5T_STD_REF_OBJ},
+    h5call, h5lock,
+    types::{EnumMember, EnumType, FloatSize, IntSize, VarLenArray},
+    Datatype, Result,
+};
+use hdf5_sys::{
+    h5::H5free_memory,
+    h5i::hid_t,
+    h5r::{hdset_reg_ref_t, hobj_ref_t},
+    h5t::{
+        H5T_class_t, H5T_cset_t, H5T_order_t, H5T_sign_t, H5T_str_t, H5Tequal, H5Tget_array_dims2,
+        H5Tget_array_ndims, H5Tget_class, H5Tget_cset, H5Tget_ebias, H5Tget_fields,
+        H5Tget_member_name, H5Tget_member_offset, H5Tget_member_type, H5Tget_member_value,
+        H5Tget_nmembers, H5Tget_order, H5Tget_sign, H5Tget_size, H5Tget_strpad, H5Tget_super,
+        H5Tget_tag, H5Tis_variable_str,
+    },
+};
+use std::{
+    ffi::{c_char, CStr},
+    fmt::{self, Display},
+    ptr::addr_of_mut,
+};
+
+/// Like `TypeDescriptor`, but also models the classes the `hdf5` crate
+/// doesn't, e.g. references.
+#[derive(Clone, Debug)]
+pub enum Dtype {
+    Integer(IntSize),
+    Unsigned(IntSize),
+    Float(FloatSize),
+    /// An IEEE half-precision float, which `FloatSize` has no size for.
+    Half,
+    /// A `{FALSE, TRUE}` enum over an integer of any size.
+    Boolean(IntSize),
+    Enum(EnumType),
+    Compound(CompoundDtype),
+    FixedArray(Box<Dtype>, usize),
+    FixedAscii(usize, StrPad),
+    FixedUnicode(usize, StrPad),
+    VarLenArray(Box<Dtype>),
+    VarLenAscii,
+    VarLenUnicode,
+    ObjectReference,
+    RegionReference,
+    /// Bits of the given number of bytes, without a meaning as a number.
+    Bitfield(usize),
+    Opaque(OpaqueDtype),
+    /// Seconds since the Unix epoch, as `H5T_UNIX_D32` or `H5T_UNIX_D64`
+    /// store them. HDF5 can't convert these, so they stay in the byte order
+    /// of the file.
+    Time {
+        size: usize,
+        big_endian: bool,
+    },
+}
+
+/// What fills a fixed-length string after its end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StrPad {
+    /// Nulls, after at least one.
+    NullTerm,
+    /// Nulls, with none if the string fills the size.
+    NullPad,
+    /// Spaces, as Fortran pads strings.
+    SpacePad,
+}
+
+#[derive(Clone, Debug)]
+pub struct OpaqueDtype {
+    pub size: usize,
+    pub tag: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct CompoundDtype {
+    pub fields: Vec<CompoundField>,
+    pub size: usize,
+}
+
+impl CompoundDtype {
+    /// The real and imaginary fields of a complex number, as NumPy and h5py
+    /// write it: two floats named `r` and `i`, or `real` and `imag`.
+    pub fn complex_parts(&self) -> Option<(&CompoundField, &CompoundField)> {
+        let [a, b] = self.fields.as_slice() else {
+            return None;
+        };
+        let is_float = |ty: &Dtype| matches!(ty, Dtype::Float(_) | Dtype::Half);
+        if !is_float(&a.ty) || !is_float(&b.ty) {
+            return None;
+        }
+        let is = |field: &CompoundField, names: [&str; 2]| {
+            names
+                .iter()
+                .any(|name| field.name.eq_ignore_ascii_case(name))
+        };
+        let real = ["r", "real"];
+        let imag = ["i", "imag"];
+        if is(a, real) && is(b, imag) {
+            Some((a, b))
+        } else if is(a, imag) && is(b, real) {
+            Some((b, a))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CompoundField {
+    pub name: String,
+    pub ty: Dtype,
+    pub offset: usize,
+}
+
+impl Dtype {
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Integer(size) | Self::Unsigned(size) => *size as _,
+            Self::Float(size) => *size as _,
+            Self::Half => 2,
+            Self::Boolean(size) => *size as _,
+            Self::Enum(ty) => ty.size as _,
+            Self::Compound(comp) => comp.size,
+            Self::FixedArray(ty, len) => ty.size() * len,
+            Self::FixedAscii(len, _) | Self::FixedUnicode(len, _) => *len,
+            Self::VarLenArray(_) => std::mem::size_of::<VarLenArray<u8>>(),
+            Self::VarLenAscii | Self::VarLenUnicode => std::mem::size_of::<*const u8>(),
+            Self::ObjectReference => std::mem::size_of::<hobj_ref_t>(),
+            Self::RegionReference => std::mem::size_of::<hdset_reg_ref_t>(),
+            Self::Bitfield(size) => *size,
+            Self::Opaque(opaque) => opaque.size,
+            Self::Time { size, .. } => *size,
+        }
+    }
+
+    /// Describes a datatype. The offsets and sizes are taken as is, so pass
+    /// the memory datatype the data is read with.
+    pub fn from_datatype(dt: &Datatype) -> Result<Self> {
+        let id = dt.id();
+        let size = h5lock!(H5Tget_size(id));
+        match h5lock!(H5Tget_class(id)) {
+            H5T_class_t::H5T_INTEGER => {
+                let size = IntSize::from_int(size).ok_or("Invalid size of integer datatype")?;
+                match h5lock!(H5Tget_sign(id)) {
+                    H5T_sign_t::H5T_SGN_NONE => Ok(Self::Unsigned(size)),
+                    H5T_sign_t::H5T_SGN_2 => Ok(Self::Integer(size)),
+                    _ => Err("Invalid sign of integer datatype".into()),
+                }
+            }
+            H5T_class_t::H5T_FLOAT if size == 2 => {
+                let (mut spos, mut epos, mut esize, mut mpos, mut msize) = (0, 0, 0, 0, 0);
+                h5call!(H5Tget_fields(
+                    id, &mut spos, &mut epos, &mut esize, &mut mpos, &mut msize
+                ))?;
+                // Not e.g. bfloat16, which has another layout.
+                if (spos, epos, esize, mpos, msize) == (15, 10, 5, 0, 10)
+                    && h5lock!(H5Tget_ebias(id)) == 15
+                {
+                    Ok(Self::Half)
+                } else {
+                    Err("Unsupported layout of 16-bit float datatype".into())
+                }
+            }
+            H5T_class_t::H5T_FLOAT => {
+                let size = FloatSize::from_int(size).ok_or("Invalid size of float datatype")?;
+                Ok(Self::Float(size))
+            }
+            H5T_class_t::H5T_ENUM => {
+                let (size, signed) = match Self::from_datatype(&super_type(id)?)? {
+                    Self::Integer(size) => (size, true),
+                    Self::Unsigned(size) => (size, false),
+                    _ => return Err("Invalid base type for enum datatype".into()),
+                };
+                let mut members = vec![];
+                for idx in 0..h5call!(H5Tget_nmembers(id))? as _ {
+                    let mut value: u64 = 0;
+                    h5call!(H5Tget_member_value(id, idx, addr_of_mut!(value).cast()))?;
+                    members.push(EnumMember {
+                        name: member_name(id, idx)?,
+                        value,
+                    });
+                }
+                let bool_members = [
+                    EnumMember {
+                        name: "FALSE".to_owned(),
+                        value: 0,
+                    },
+                    EnumMember {
+                        name: "TRUE".to_owned(),
+                        value: 1,
+                    },
+                ];
+                if members == bool_members {
+                    Ok(Self::Boolean(size))
+                } else {
+                    Ok(Self::Enum(EnumType {
+                        size,
+                        signed,
+                        members,
+                    }))
+                }
+            }
+            H5T_class_t::H5T_COMPOUND => {
+                let mut fields = vec![];
+                for idx in 0..h5call!(H5Tget_nmembers(id))? as _ {
+                    let ty = unsafe { from_id::<Datatype>(h5call!(H5Tget_member_type(id, idx))?) }?;
+                    fields.push(CompoundField {
+                        name: member_name(id, idx)?,
+                        ty: Self::from_datatype(&ty)?,
+                        offset: h5lock!(H5Tget_member_offset(id, idx)),
+                    });
+                }
+                Ok(Self::Compound(CompoundDtype { fields, size }))
+            }
+            H5T_class_t::H5T_ARRAY => {
+                let base = Self::from_datatype(&super_type(id)?)?;
+                let ndims = h5call!(H5Tget_array_ndims(id))?;
+                let mut dims = vec![0; ndims as usize];
+                h5call!(H5Tget_array_dims2(id, dims.as_mut_ptr()))?;
+                // An array of arrays for each dimension after the first.
+                Ok(dims
+                    .into_iter()
+                    .rev()
+                    .fold(base, |ty, len| Self::FixedArray(Box::new(ty), len as _)))
+            }
+            H5T_class_t::H5T_STRING => {
+                let is_variable = h5call!(H5Tis_variable_str(id))? > 0;
+                let pad = match h5lock!(H5Tget_strpad(id)) {
+                    H5T_str_t::H5T_STR_NULLPAD => StrPad::NullPad,
+                    H5T_str_t::H5T_STR_SPACEPAD => StrPad::SpacePad,
+                    _ => StrPad::NullTerm,
+                };
+                match (is_variable, h5lock!(H5Tget_cset(id))) {
+                    (false, H5T_cset_t::H5T_CSET_ASCII) => Ok(Self::FixedAscii(size, pad)),
+                    (false, H5T_cset_t::H5T_CSET_UTF8) => Ok(Self::FixedUnicode(size, pad)),
+                    (true, H5T_cset_t::H5T_CSET_ASCII) => Ok(Self::VarLenAscii),
+                    (true, H5T_cset_t::H5T_CSET_UTF8) => Ok(Self::VarLenUnicode),
+                    _ => Err("Invalid encoding for string datatype".into()),
+                }
+            }
+            H5T_class_t::H5T_VLEN => Ok(Self::VarLenArray(Box::new(Self::from_datatype(
+                &super_type(id)?,
+            )?))),
+            H5T_class_t::H5T_REFERENCE => {
+                if h5call!(H5Tequal(id, *H5T_STD_REF_OBJ))? > 0 {
+                    Ok(Self::ObjectReference)
+                } else if h5call!(H5Tequal(id, *H5T_STD_REF_DSETREG))? > 0 {
+                    Ok(Self::RegionReference)
+                } else {
+                    Err("Unsupported reference datatype".into())
+                }
+            }
+            H5T_class_t::H5T_BITFIELD => Ok(Self::Bitfield(size)),
+            H5T_class_t::H5T_OPAQUE => {
+                let tag = take_string(h5lock!(H5Tget_tag(id))).unwrap_or_default();
+                Ok(Self::Opaque(OpaqueDtype { size, tag }))
+            }
+            H5T_class_t::H5T_TIME => Ok(Self::Time {
+                size,
+                big_endian: h5lock!(H5Tget_order(id)) == H5T_order_t::H5T_ORDER_BE,
+            }),
+            _ => Err("Unsupported datatype class".into()),
+        }
+    }
+}
+
+impl Display for Dtype {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Integer(IntSize::U1) => write!(f, "int8"),
+            Self::Integer(IntSize::U2) => write!(f, "int16"),
+            Self::Integer(IntSize::U4) => write!(f, "int32"),
+            Self::Integer(IntSize::U8) => write!(f, "int64"),
+            Self::Unsigned(IntSize::U1) => write!(f, "uint8"),
+            Self::Unsigned(IntSize::U2) => write!(f, "uint16"),
+            Self::Unsigned(IntSize::U4) => write!(f, "uint32"),
+            Self::Unsigned(IntSize::U8) => write!(f, "uint64"),
+            Self::Float(FloatSize::U4) => write!(f, "float32"),
+            Self::Float(FloatSize::U8) => write!(f, "float64"),
+            Self::Half => write!(f, "float16"),
+            Self::Boolean(IntSize::U1) => write!(f, "bool"),
+            Self::Boolean(size) => write!(f, "bool ({} bytes)", *size as usize),
+            Self::Enum(ty) => write!(f, "enum ({})", ty.base_type()),
+            Self::Compound(comp) => write!(f, "compound ({} fields)", comp.fields.len()),
+            Self::FixedArray(ty, len) => write!(f, "[{}; {}]", ty, len),
+            Self::FixedAscii(len, _) => write!(f, "string (len {})", len),
+            Self::FixedUnicode(len, _) => write!(f, "unicode (len {})", len),
+            Self::VarLenArray(ty) => write!(f, "[{}] (var len)", ty),
+            Self::VarLenAscii => write!(f, "string (var len)"),
+            Self::VarLenUnicode => write!(f, "unicode (var len)"),
+            Self::ObjectReference => write!(f, "object reference"),
+            Self::RegionReference => write!(f, "region reference"),
+            Self::Bitfield(size) => write!(f, "bitfield ({} bytes)", size),
+            Self::Opaque(opaque) if opaque.tag.is_empty() => {
+                write!(f, "opaque (len {})", opaque.size)
+            }
+            Self::Opaque(opaque) => write!(f, "opaque (len {}, tag {})", opaque.size, opaque.tag),
+            Self::Time { size, .. } => write!(f, "time ({} bytes)", size),
+        }
+    }
+}
+
+fn super_type(id: hid_t) -> Result<Datatype> {
+    unsafe { from_id(h5call!(H5Tget_super(id))?) }
+}
+
+fn member_name(id: hid_t, idx: u32) -> Result<String> {
+    take_string(h5lock!(H5Tget_member_name(id, idx))).ok_or_else(|| "Invalid member name".into())
+}
+
+/// Copies and frees a string allocated by the library.
+fn take_string(ptr: *mut c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    let res = unsafe { CStr::from_ptr(ptr) }
+        .to_string_lossy()
+        .into_owned();
+    h5lock!(H5free_memory(ptr.cast()));
+    Some(res)
+}
@@ -1,9 +1,47 @@
-use crate::hdf5_ext::{FileImage, ReadRawBytes};
+use crate::{
+    dtype::{CompoundDtype, CompoundField, Dtype, StrPad},
+    hdf5_dtype::describe,
+    hdf5_ext::{
+        open_image_copy, open_swmr, AllocTime, CommittedType, Dereference, ExternalLink, FileImage,
+        FillValue, NativeType, ReadBlocks, ReadRange, ReadRawBytes, ReadRegion, ReadSelection,
+        Refresh, SelectMembers, Selection, SelectionBounds, VirtualPrefix,
+    },
+    hdf5_open::{Hdf5Dataset, Images},
+    sha256::Sha256,
+};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeDelta};
 use hdf5::{
-    types::{FloatSize, IntSize, TypeDescriptor, VarLenArray, VarLenAscii, VarLenUnicode},
-    Dataset, Group, Result,
+    filters::Filter,
+    h5lock,
+    plist::{
+        dataset_create::Layout,
+        file_access::{ChunkCache, FileAccess},
+    },
+    types::{FloatSize, IntSize, VarLenAscii, VarLenUnicode},
+    Container, Dataset, Dataspace, Datatype, File, Group, LinkType, Location, LocationToken,
+    LocationType, Result,
+};
+use hdf5_sys::{
+    h5r::{hdset_reg_ref_t, hobj_ref_t},
+    h5t::{hvl_t, H5T_class_t, H5Tdetect_class, H5Tget_class},
+};
+use nu_glob::{MatchOptions, Pattern};
+use nu_plugin::{EngineInterface, EvaluatedCall};
+use nu_protocol::{
+    ByteStream, ByteStreamType, Category, DataSource, Example, LabeledError, ListStream,
+    PipelineData, PipelineMetadata, Record, ShellError, Signals, Signature, Span, Spanned,
+    SyntaxShape, Type, Value,
+};
+use rayon::prelude::*;
+use std::{
+    ops::Deref,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
-use nu_protocol::{Category, LabeledError, PipelineData, Record, Signature, Span, Type, Value};
 
 macro_rules! native {
     ($native_ty: ty, $slice: expr) => {
@@ -11,6 +49,17 @@ macro_rules! native {
     };
 }
 
+/// Copies a struct out of the buffer, which may be unaligned. The copy doesn't
+/// own the heap data it points to, which still belongs to the buffer.
+macro_rules! native_copy {
+    ($native_ty: ty, $slice: expr) => {{
+        assert_eq!($slice.len(), std::mem::size_of::<$native_ty>());
+        std::mem::ManuallyDrop::new(unsafe {
+            std::ptr::read_unaligned(native!($native_ty, $slice))
+        })
+    }};
+}
+
 macro_rules! native_value {
     ($native_ty: ty, $variant: ident, $slice: expr, $span: expr) => {{
         assert_eq!($slice.len(), std::mem::size_of::<$native_ty>());
@@ -21,28 +70,1259 @@ macro_rules! native_value {
     }};
 }
 
-fn to_value(slice: &[u8], dtype: &TypeDescriptor, span: Span) -> Result<Value> {
+/// Converts all elements of a buffer of a primitive type in one typed loop.
+macro_rules! native_values {
+    ($native_ty: ty, $variant: ident, $data: expr, $span: expr) => {
+        $data
+            .chunks_exact(std::mem::size_of::<$native_ty>())
+            .map(|slice| Value::$variant {
+                val: <$native_ty>::from_ne_bytes(slice.try_into().unwrap()) as _,
+                internal_span: $span,
+            })
+            .collect()
+    };
+}
+
+#[derive(Clone, Default)]
+struct Options {
+    tabular: bool,
+    group_as_table: bool,
+    pandas: bool,
+    pad_ragged: bool,
+    meta: bool,
+    max_depth: Option<usize>,
+    max_children: Option<usize>,
+    offset: Option<usize>,
+    length: Option<usize>,
+    flatten_paths: bool,
+    flatten_separator: String,
+    /// Data of any rank as a 1-D list, and scalars as lists of one.
+    flatten: bool,
+    scalars_as_lists: bool,
+    /// The replacement of characters of names out of cell paths.
+    sanitize_names: Option<String>,
+    name_map: bool,
+    attributes: bool,
+    checksum: bool,
+    with_checksum: bool,
+    table_2d: bool,
+    column_names: Vec<String>,
+    columns: Option<Vec<String>>,
+    no_attr_data: bool,
+    transpose_compound: bool,
+    selection: Selection,
+    select: Option<Vec<Index>>,
+    lenient: bool,
+    strict: bool,
+    fill_as_null: bool,
+    nan_as_null: bool,
+    parallel: bool,
+    images: bool,
+    h5tb: bool,
+    /// Datasets as handles read when accessed.
+    lazy: bool,
+    /// Checked between objects, when run by the engine.
+    signals: Option<Signals>,
+    raw_enums: bool,
+    read_regions: bool,
+    int_as_time: Option<(TimeUnit, String)>,
+    dim_scales: bool,
+    netcdf: bool,
+    order_by: Option<OrderBy>,
+    max_memory: Option<usize>,
+    on_duplicate: OnDuplicate,
+    order: Order,
+    over_memory: OverMemory,
+    desc: bool,
+    creation_order: bool,
+    opaque_tags: bool,
+    raw_compound: bool,
+    partial: bool,
+    root_key: Option<String>,
+    faithful: bool,
+    with_dtype: bool,
+    with_shape: bool,
+    metadata: bool,
+    u64_as_float: bool,
+    dates: bool,
+    units: bool,
+    stream_threshold: Option<usize>,
+    schema: bool,
+    complex_string: bool,
+    layout: bool,
+    follow_external: bool,
+    /// Soft links are followed unless `--no-follow-links`.
+    no_follow_links: bool,
+    external_prefix: Option<String>,
+    virtual_prefix: Option<String>,
+    bytes_as_binary: bool,
+    raw: bool,
+    glob: Option<Pattern>,
+    exclude: Option<Pattern>,
+    table: bool,
+    mmap: bool,
+    swmr: bool,
+    vfd: Option<String>,
+    alignment: Option<usize>,
+    extract: Option<String>,
+    progress: bool,
+    /// Set by the plugin config only.
+    chunk_cache: Option<usize>,
+    sieve_buffer: Option<usize>,
+}
+
+impl Options {
+    fn from_call(call: &EvaluatedCall) -> Result<Self, ShellError> {
+        let schema = call.has_flag("schema")?;
+        let layout = call.has_flag("layout")?;
+        let pad_ragged = !schema && call.has_flag("as-dataframe-friendly")?;
+        let tabular = !schema && (pad_ragged || call.has_flag("tabular")?);
+        let with_checksum = call.has_flag("with-checksum")?;
+        let follow_links = call.has_flag("follow-links")?;
+        let no_follow_links = call.has_flag("no-follow-links")?;
+        if follow_links && no_follow_links {
+            return Err(ShellError::IncompatibleParametersSingle {
+                msg: "--follow-links and --no-follow-links are mutually exclusive".to_string(),
+                span: call.head,
+            });
+        }
+        let checksum = match call.get_flag::<Spanned<String>>("checksum")? {
+            Some(algo) if algo.item != "sha256" => {
+                return Err(ShellError::IncorrectValue {
+                    msg: format!("unsupported checksum {}, only sha256 is", algo.item),
+                    val_span: algo.span,
+                    call_span: call.head,
+                })
+            }
+            Some(_) => true,
+            None => with_checksum,
+        };
+        Ok(Self {
+            tabular,
+            group_as_table: !schema && call.has_flag("group-as-table")?,
+            pandas: !schema && call.has_flag("pandas")?,
+            pad_ragged,
+            meta: schema || layout || call.has_flag("meta")?,
+            max_depth: call.get_flag("max-depth")?,
+            max_children: call.get_flag("max-children")?,
+            offset: call.get_flag("offset")?,
+            length: call.get_flag("length")?,
+            flatten_paths: call.has_flag("flatten-paths")?,
+            flatten_separator: call
+                .get_flag("flatten-separator")?
+                .unwrap_or_else(|| "/".to_string()),
+            flatten: call.has_flag("flatten")?,
+            scalars_as_lists: call.has_flag("scalars-as-lists")?,
+            sanitize_names: if call.has_flag("sanitize-names")? {
+                Some(
+                    call.get_flag("replacement")?
+                        .unwrap_or_else(|| "_".to_string()),
+                )
+            } else {
+                None
+            },
+            name_map: call.has_flag("name-map")?,
+            // Attributes would keep groups from being tables.
+            attributes: !tabular && !schema && !call.has_flag("no-attributes")?,
+            checksum,
+            with_checksum,
+            table_2d: call.has_flag("2d-as-table")?,
+            table: call.has_flag("table")?,
+            mmap: call.has_flag("mmap")?,
+            swmr: call.has_flag("swmr")?,
+            vfd: call.get_flag("vfd")?,
+            alignment: call.get_flag("alignment")?,
+            column_names: call
+                .get_flag::<String>("column-names")?
+                .map(|names| names.split(',').map(|s| s.trim().to_string()).collect())
+                .unwrap_or_default(),
+            columns: call.get_flag("columns")?,
+            no_attr_data: call.has_flag("no-read-attributes-data")?,
+            transpose_compound: call.has_flag("transpose-compound")?,
+            selection: Selection {
+                start: call
+                    .get_flag::<Value>("start")?
+                    .map(|start| parse_dims(&start, 0, "starts"))
+                    .transpose()?,
+                count: call
+                    .get_flag::<Value>("count")?
+                    .map(|count| parse_dims(&count, 0, "counts"))
+                    .transpose()?,
+                stride: call
+                    .get_flag::<Value>("stride")?
+                    .map(|stride| parse_dims(&stride, 1, "strides"))
+                    .transpose()?,
+                head: call.get_flag("head")?,
+                skip: call.get_flag("skip")?,
+            },
+            select: call
+                .get_flag::<Spanned<String>>("select")?
+                .map(|select| parse_select(&select))
+                .transpose()?,
+            lenient: call.has_flag("lenient")?,
+            strict: call.has_flag("strict")?,
+            fill_as_null: call.has_flag("fill-as-null")?,
+            nan_as_null: call.has_flag("nan-as-null")?,
+            parallel: call.has_flag("parallel")?,
+            images: call.has_flag("images")?,
+            h5tb: call.has_flag("h5tb")?,
+            lazy: call.has_flag("lazy")?,
+            signals: None,
+            raw_enums: call.has_flag("raw-enums")?,
+            read_regions: call.has_flag("read-regions")?,
+            int_as_time: match call.get_flag::<Spanned<String>>("int-as-time")? {
+                Some(unit) => {
+                    let Some(path) = call.get_flag::<String>("time-path")? else {
+                        return Err(ShellError::MissingParameter {
+                            param_name: "time-path".to_string(),
+                            span: unit.span,
+                        });
+                    };
+                    let path = format!("/{}", path.trim_start_matches('/'));
+                    Some((TimeUnit::parse(&unit)?, path))
+                }
+                None => None,
+            },
+            dim_scales: call.has_flag("dim-scales")?,
+            netcdf: call.has_flag("netcdf")?,
+            order_by: call
+                .get_flag::<Spanned<String>>("order-by")?
+                .map(|key| OrderBy::parse(&key))
+                .transpose()?,
+            desc: call.has_flag("desc")?,
+            max_memory: call
+                .get_flag::<Spanned<i64>>("max-memory")?
+                .map(|max| {
+                    usize::try_from(max.item).map_err(|_| ShellError::IncorrectValue {
+                        msg: "--max-memory can't be negative".to_string(),
+                        val_span: max.span,
+                        call_span: call.head,
+                    })
+                })
+                .transpose()?,
+            on_duplicate: call
+                .get_flag::<Spanned<String>>("on-duplicate")?
+                .map(|policy| OnDuplicate::parse(&policy))
+                .transpose()?
+                .unwrap_or_default(),
+            order: call
+                .get_flag::<Spanned<String>>("order")?
+                .map(|order| Order::parse(&order))
+                .transpose()?
+                .unwrap_or_default(),
+            over_memory: call
+                .get_flag::<Spanned<String>>("over-memory")?
+                .map(|policy| OverMemory::parse(&policy))
+                .transpose()?
+                .unwrap_or_default(),
+            creation_order: call.has_flag("creation-order")?,
+            opaque_tags: call.has_flag("opaque-tags")?,
+            raw_compound: call.has_flag("raw-compound")?,
+            partial: call.has_flag("partial")?,
+            root_key: call.get_flag("root-key")?,
+            faithful: call.has_flag("faithful")?,
+            with_dtype: call.has_flag("with-dtype")?,
+            with_shape: call.has_flag("with-shape")?,
+            metadata: call.has_flag("metadata")?,
+            u64_as_float: call.has_flag("u64-as-float")?,
+            dates: call.has_flag("dates")?,
+            units: call.has_flag("units")?,
+            stream_threshold: call.get_flag("stream-threshold")?,
+            schema,
+            complex_string: call.has_flag("complex-string")?,
+            layout,
+            follow_external: !no_follow_links
+                && (follow_links || call.has_flag("follow-external")?),
+            no_follow_links,
+            external_prefix: call.get_flag("external-prefix")?,
+            virtual_prefix: call.get_flag("virtual-prefix")?,
+            bytes_as_binary: call.has_flag("bytes-as-binary")?,
+            raw: call.has_flag("raw")?,
+            glob: parse_glob(call, "glob")?,
+            exclude: parse_glob(call, "exclude")?,
+            extract: call.get_flag("extract")?,
+            progress: call.has_flag("progress")?,
+            chunk_cache: None,
+            sieve_buffer: None,
+        })
+    }
+
+    /// The file access properties of files opened by HDF5 from their paths.
+    fn file_access(&self) -> Result<FileAccess> {
+        let mut fapl = FileAccess::build();
+        if let Some(nbytes) = self.chunk_cache {
+            let ChunkCache { nslots, w0, .. } = ChunkCache::default();
+            fapl.chunk_cache(nslots, nbytes, w0);
+        }
+        if let Some(size) = self.sieve_buffer {
+            fapl.sieve_buf_size(size);
+        }
+        match self.vfd.as_deref() {
+            None => {}
+            Some("sec2") => {
+                fapl.sec2();
+            }
+            Some("stdio") => {
+                fapl.stdio();
+            }
+            #[cfg(have_direct)]
+            Some("direct") => {
+                use hdf5::plist::file_access::DirectDriver;
+                // Aligns the unbuffered reads as well.
+                let DirectDriver {
+                    alignment,
+                    block_size,
+                    cbuf_size,
+                } = DirectDriver::default();
+                fapl.direct_options(self.alignment.unwrap_or(alignment), block_size, cbuf_size);
+            }
+            #[cfg(not(have_direct))]
+            Some("direct") => {
+                return Err("the linked HDF5 is built without the direct VFD, so use --vfd sec2 or stdio instead".into())
+            }
+            Some(vfd) => {
+                return Err(format!("unknown VFD {vfd}, expected sec2, stdio or direct").into())
+            }
+        }
+        if let Some(alignment) = self.alignment {
+            fapl.alignment(1, alignment as _);
+        }
+        fapl.finish()
+    }
+
+    /// Picks the HDF5 image out of the input, which may be embedded in a larger blob.
+    fn image<'b>(&self, bytes: &'b [u8]) -> Result<&'b [u8]> {
+        let offset = self.offset.unwrap_or(0);
+        let length = self.length.unwrap_or(bytes.len().saturating_sub(offset));
+        offset
+            .checked_add(length)
+            .and_then(|end| bytes.get(offset..end))
+            .ok_or_else(|| {
+                format!(
+                    "offset {offset} and length {length} are out of the input of {} bytes",
+                    bytes.len()
+                )
+                .into()
+            })
+    }
+}
+
+#[derive(Clone, Copy)]
+enum TimeUnit {
+    Seconds,
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl TimeUnit {
+    fn parse(unit: &Spanned<String>) -> Result<Self, ShellError> {
+        match unit.item.as_str() {
+            "seconds" => Ok(Self::Seconds),
+            "millis" => Ok(Self::Millis),
+            "micros" => Ok(Self::Micros),
+            "nanos" => Ok(Self::Nanos),
+            _ => Err(ShellError::IncorrectValue {
+                msg: "expected one of seconds, millis, micros and nanos".to_string(),
+                val_span: unit.span,
+                call_span: unit.span,
+            }),
+        }
+    }
+
+    /// The time of an offset from the Unix epoch, if it is in range.
+    fn to_date(self, offset: i64) -> Option<DateTime<FixedOffset>> {
+        let date = match self {
+            Self::Seconds => DateTime::from_timestamp(offset, 0),
+            Self::Millis => DateTime::from_timestamp_millis(offset),
+            Self::Micros => DateTime::from_timestamp_micros(offset),
+            Self::Nanos => Some(DateTime::from_timestamp_nanos(offset)),
+        };
+        date.map(|date| date.fixed_offset())
+    }
+
+    /// The time of an offset from the epoch, if it is in range.
+    fn to_date_since(
+        self,
+        epoch: DateTime<FixedOffset>,
+        offset: i64,
+    ) -> Option<DateTime<FixedOffset>> {
+        let delta = match self {
+            Self::Seconds => TimeDelta::try_seconds(offset)?,
+            Self::Millis => TimeDelta::try_milliseconds(offset)?,
+            Self::Micros => TimeDelta::microseconds(offset),
+            Self::Nanos => TimeDelta::nanoseconds(offset),
+        };
+        epoch.checked_add_signed(delta)
+    }
+}
+
+/// Parses CF-style units like `seconds since 1970-01-01 00:00:00`, with the
+/// epoch in UTC unless it has an offset.
+fn parse_time_units(units: &str) -> Option<(TimeUnit, DateTime<FixedOffset>)> {
+    let (unit, epoch) = units.trim().split_once(" since ")?;
+    let unit = match unit.trim() {
+        "seconds" | "second" | "secs" | "sec" | "s" => TimeUnit::Seconds,
+        "milliseconds" | "millisecond" | "msecs" | "msec" | "ms" => TimeUnit::Millis,
+        "microseconds" | "microsecond" | "usecs" | "usec" | "us" => TimeUnit::Micros,
+        "nanoseconds" | "nanosecond" | "nsecs" | "nsec" | "ns" => TimeUnit::Nanos,
+        _ => return None,
+    };
+    let epoch = epoch.trim();
+    let epoch = DateTime::parse_from_rfc3339(epoch).ok().or_else(|| {
+        ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+            .iter()
+            .find_map(|fmt| NaiveDateTime::parse_from_str(epoch, fmt).ok())
+            .or_else(|| {
+                NaiveDate::parse_from_str(epoch, "%Y-%m-%d")
+                    .ok()
+                    .and_then(|date| date.and_hms_opt(0, 0, 0))
+            })
+            .map(|date| date.and_utc().fixed_offset())
+    })?;
+    Some((unit, epoch))
+}
+
+/// Replaces the integers, also in nested lists, with the times they are offsets
+/// to, or nothing if out of range.
+fn ints_to_dates(value: Value, to_date: &impl Fn(i64) -> Option<DateTime<FixedOffset>>) -> Value {
+    match value {
+        Value::Int { val, internal_span } => match to_date(val) {
+            Some(val) => Value::Date { val, internal_span },
+            None => Value::Nothing { internal_span },
+        },
+        Value::List {
+            vals,
+            internal_span,
+        } => Value::List {
+            vals: vals
+                .into_iter()
+                .map(|val| ints_to_dates(val, to_date))
+                .collect(),
+            internal_span,
+        },
+        value => value,
+    }
+}
+
+/// A unit of `--units` that Nushell has values of, with the nanoseconds or
+/// bytes it is.
+#[derive(Clone, Copy)]
+enum Unit {
+    Duration(f64),
+    Filesize(f64),
+}
+
+fn parse_unit(units: &str) -> Option<Unit> {
+    Some(match units.trim() {
+        "nanoseconds" | "nanosecond" | "nsecs" | "nsec" | "ns" => Unit::Duration(1.0),
+        "microseconds" | "microsecond" | "usecs" | "usec" | "us" => Unit::Duration(1e3),
+        "milliseconds" | "millisecond" | "msecs" | "msec" | "ms" => Unit::Duration(1e6),
+        "seconds" | "second" | "secs" | "sec" | "s" => Unit::Duration(1e9),
+        "minutes" | "minute" | "mins" | "min" => Unit::Duration(60e9),
+        "hours" | "hour" | "hrs" | "hr" | "h" => Unit::Duration(3600e9),
+        "days" | "day" | "d" => Unit::Duration(86400e9),
+        "bytes" | "byte" | "B" => Unit::Filesize(1.0),
+        "kB" | "KB" => Unit::Filesize(1e3),
+        "MB" => Unit::Filesize(1e6),
+        "GB" => Unit::Filesize(1e9),
+        "TB" => Unit::Filesize(1e12),
+        "KiB" => Unit::Filesize(1024.0),
+        "MiB" => Unit::Filesize(1024.0 * 1024.0),
+        "GiB" => Unit::Filesize(1024.0 * 1024.0 * 1024.0),
+        "TiB" => Unit::Filesize(1024.0 * 1024.0 * 1024.0 * 1024.0),
+        _ => return None,
+    })
+}
+
+/// Replaces the numbers, also in nested lists, with durations or file sizes
+/// of the unit.
+fn numbers_to_unit(value: Value, unit: Unit) -> Value {
+    let convert = |x: f64, internal_span| match unit {
+        Unit::Duration(ns) => Value::Duration {
+            val: (x * ns).round() as i64,
+            internal_span,
+        },
+        Unit::Filesize(bytes) => Value::Filesize {
+            val: (x * bytes).round() as i64,
+            internal_span,
+        },
+    };
+    match value {
+        Value::Int { val, internal_span } => convert(val as f64, internal_span),
+        Value::Float { val, internal_span } => convert(val, internal_span),
+        Value::List {
+            vals,
+            internal_span,
+        } => Value::List {
+            vals: vals
+                .into_iter()
+                .map(|val| numbers_to_unit(val, unit))
+                .collect(),
+            internal_span,
+        },
+        value => value,
+    }
+}
+
+/// What `--on-duplicate` does with keys of a group that are the same, e.g.
+/// after `--sanitize-names`.
+#[derive(Clone, Copy, Default)]
+enum OnDuplicate {
+    /// Keys after the first are suffixed with `_1`, `_2` and so on.
+    #[default]
+    Suffix,
+    Error,
+    /// The last of them is kept.
+    Last,
+}
+
+impl OnDuplicate {
+    fn parse(policy: &Spanned<String>) -> Result<Self, ShellError> {
+        match policy.item.as_str() {
+            "suffix" => Ok(Self::Suffix),
+            "error" => Ok(Self::Error),
+            "last" => Ok(Self::Last),
+            _ => Err(ShellError::IncorrectValue {
+                msg: "expected suffix, error or last".to_string(),
+                val_span: policy.span,
+                call_span: policy.span,
+            }),
+        }
+    }
+}
+
+/// How `--order` lays out the elements of multidimensional datasets.
+#[derive(Clone, Copy, Default)]
+enum Order {
+    /// Row-major, as HDF5 stores them.
+    #[default]
+    C,
+    /// Column-major, as written by Fortran and MATLAB, with the axes reversed.
+    F,
+    /// Column-major for datasets with a `MATLAB_class` attribute.
+    Auto,
+}
+
+impl Order {
+    fn parse(order: &Spanned<String>) -> Result<Self, ShellError> {
+        match order.item.as_str() {
+            "c" => Ok(Self::C),
+            "f" => Ok(Self::F),
+            "auto" => Ok(Self::Auto),
+            _ => Err(ShellError::IncorrectValue {
+                msg: "expected c, f or auto".to_string(),
+                val_span: order.span,
+                call_span: order.span,
+            }),
+        }
+    }
+
+    fn is_column_major(self, dataset: &Dataset) -> bool {
+        match self {
+            Self::C => false,
+            Self::F => true,
+            Self::Auto => dataset.attr("MATLAB_class").is_ok(),
+        }
+    }
+}
+
+/// Rearranges row-major elements of the shape into the row-major elements
+/// of the reversed shape, which is how column-major data is read.
+fn reverse_axes(data: &[u8], size: usize, shape: &[usize]) -> Vec<u8> {
+    let mut out = vec![0; data.len()];
+    // The strides of the reversed shape, by original axis.
+    let strides: Vec<usize> = shape
+        .iter()
+        .scan(1, |stride, &d| {
+            let s = *stride;
+            *stride *= d;
+            Some(s)
+        })
+        .collect();
+    let mut index = vec![0; shape.len()];
+    for elem in data.chunks_exact(size) {
+        let dest: usize = index.iter().zip(&strides).map(|(i, s)| i * s).sum();
+        out[dest * size..][..size].copy_from_slice(elem);
+        for (i, &d) in index.iter_mut().zip(shape).rev() {
+            *i += 1;
+            if *i < d {
+                break;
+            }
+            *i = 0;
+        }
+    }
+    out
+}
+
+/// Decodes the data of a dataset, as column-major by `--order`.
+fn decode_dataset(
+    dataset: &Dataset,
+    data: &[u8],
+    dtype: &Dtype,
+    shape: &[usize],
+    fill: Option<&[u8]>,
+    ctx: &Context,
+) -> Result<Value> {
+    if shape.len() > 1 && ctx.options.order.is_column_major(dataset) {
+        let data = reverse_axes(data, dtype.size(), shape);
+        let shape: Vec<usize> = shape.iter().rev().copied().collect();
+        return decode(&data, dtype, &shape, fill, ctx);
+    }
+    decode(data, dtype, shape, fill, ctx)
+}
+
+fn dedup_keys(
+    cols: Vec<String>,
+    vals: Vec<Value>,
+    policy: OnDuplicate,
+) -> Result<(Vec<String>, Vec<Value>)> {
+    let mut keys: Vec<String> = vec![];
+    let mut kept: Vec<Value> = vec![];
+    for (col, val) in cols.into_iter().zip(vals) {
+        let Some(i) = keys.iter().position(|key| *key == col) else {
+            keys.push(col);
+            kept.push(val);
+            continue;
+        };
+        match policy {
+            OnDuplicate::Error => return Err(format!("duplicate key {col}").into()),
+            OnDuplicate::Last => kept[i] = val,
+            OnDuplicate::Suffix => {
+                let key = (1..)
+                    .map(|n| format!("{col}_{n}"))
+                    .find(|key| !keys.contains(key))
+                    .unwrap();
+                keys.push(key);
+                kept.push(val);
+            }
+        }
+    }
+    Ok((keys, kept))
+}
+
+/// What `--max-memory` does with a dataset over the budget.
+#[derive(Clone, Copy, Default)]
+enum OverMemory {
+    #[default]
+    Error,
+    Skip,
+    Truncate,
+    Stream,
+}
+
+impl OverMemory {
+    fn parse(policy: &Spanned<String>) -> Result<Self, ShellError> {
+        match policy.item.as_str() {
+            "error" => Ok(Self::Error),
+            "skip" => Ok(Self::Skip),
+            "truncate" => Ok(Self::Truncate),
+            "stream" => Ok(Self::Stream),
+            _ => Err(ShellError::IncorrectValue {
+                msg: "expected error, skip, truncate or stream".to_string(),
+                val_span: policy.span,
+                call_span: policy.span,
+            }),
+        }
+    }
+}
+
+/// A rough size of an element converted into values.
+fn value_size(dtype: &Dtype) -> usize {
+    let value = std::mem::size_of::<Value>();
+    match dtype {
+        Dtype::Compound(comp) => {
+            value
+                + comp
+                    .fields
+                    .iter()
+                    .map(|field| field.name.len() + value_size(&field.ty))
+                    .sum::<usize>()
+        }
+        Dtype::FixedArray(ty, len) => value + len * value_size(ty),
+        Dtype::FixedAscii(len, _) | Dtype::FixedUnicode(len, _) => value + len,
+        _ => value,
+    }
+}
+
+/// A rough size of a dataset converted into values, for `--max-memory`.
+fn estimated_size(dataset: &Dataset) -> Result<usize> {
+    let dtype = Dtype::from_datatype(&dataset.dtype()?.to_native()?)?;
+    Ok(dataset.size().saturating_mul(value_size(&dtype)))
+}
+
+#[derive(Clone, Copy)]
+enum OrderBy {
+    Name,
+    Size,
+}
+
+impl OrderBy {
+    fn parse(key: &Spanned<String>) -> Result<Self, ShellError> {
+        match key.item.as_str() {
+            "name" => Ok(Self::Name),
+            "size" => Ok(Self::Size),
+            _ => Err(ShellError::IncorrectValue {
+                msg: "expected name or size".to_string(),
+                val_span: key.span,
+                call_span: key.span,
+            }),
+        }
+    }
+}
+
+/// Sorts datasets by `--order-by`, in descending order with `--desc`.
+fn order_datasets(mut datasets: Vec<Dataset>, options: &Options) -> Result<Vec<Dataset>> {
+    match options.order_by {
+        Some(OrderBy::Name) => datasets.sort_by_key(|ds| ds.name()),
+        Some(OrderBy::Size) => {
+            // The size in memory, from the dataspace and the datatype.
+            let mut sized: Vec<(usize, Dataset)> = datasets
+                .into_iter()
+                .map(|ds| -> Result<_> { Ok((ds.size() * ds.dtype()?.size(), ds)) })
+                .try_collect()?;
+            sized.sort_by_key(|(size, _)| *size);
+            datasets = sized.into_iter().map(|(_, ds)| ds).collect();
+        }
+        None => return Ok(datasets),
+    }
+    if options.desc {
+        datasets.reverse();
+    }
+    Ok(datasets)
+}
+
+fn parse_glob(call: &EvaluatedCall, name: &str) -> Result<Option<Pattern>, ShellError> {
+    call.get_flag::<Spanned<String>>(name)?
+        .map(|glob| {
+            Pattern::new(&glob.item).map_err(|e| ShellError::IncorrectValue {
+                msg: format!("invalid glob: {e}"),
+                val_span: glob.span,
+                call_span: call.head,
+            })
+        })
+        .transpose()
+}
+
+/// A value for every dimension, or a single one for all of them.
+fn parse_dims(value: &Value, min: i64, name: &str) -> Result<Vec<usize>, ShellError> {
+    let dims = match value {
+        Value::List { vals, .. } => vals.iter().map(|v| v.as_int()).try_collect()?,
+        v => vec![v.as_int()?],
+    };
+    if dims.is_empty() || dims.iter().any(|&k| k < min) {
+        return Err(ShellError::IncorrectValue {
+            msg: format!("{name} must be at least {min}"),
+            val_span: value.span(),
+            call_span: value.span(),
+        });
+    }
+    Ok(dims.into_iter().map(|k| k as _).collect())
+}
+
+/// A dimension of `--select`, as in NumPy: an index, or a slice whose bounds
+/// may count from the end and whose step may be negative.
+#[derive(Clone, Copy)]
+enum Index {
+    At(i64),
+    Slice(Option<i64>, Option<i64>, i64),
+}
+
+fn parse_select(select: &Spanned<String>) -> Result<Vec<Index>, ShellError> {
+    let err = |msg: String| ShellError::IncorrectValue {
+        msg,
+        val_span: select.span,
+        call_span: select.span,
+    };
+    let bound = |s: &str| {
+        let s = s.trim();
+        if s.is_empty() {
+            Ok(None)
+        } else {
+            s.parse()
+                .map(Some)
+                .map_err(|_| err(format!("invalid index {s}")))
+        }
+    };
+    select
+        .item
+        .split(',')
+        .map(
+            |part| match part.split(':').collect::<Vec<_>>().as_slice() {
+                [index] => bound(index)?
+                    .map(Index::At)
+                    .ok_or_else(|| err("empty index".to_string())),
+                [start, stop] => Ok(Index::Slice(bound(start)?, bound(stop)?, 1)),
+                [start, stop, step] => match bound(step)?.unwrap_or(1) {
+                    0 => Err(err("step must not be 0".to_string())),
+                    step => Ok(Index::Slice(bound(start)?, bound(stop)?, step)),
+                },
+                _ => Err(err(format!("invalid slice {}", part.trim()))),
+            },
+        )
+        .collect()
+}
+
+/// The hyperslab of `--select` in a dataset of the shape, which is read
+/// forwards, with the dimensions to reverse and those to drop after reading.
+fn resolve_select(select: &[Index], shape: &[usize]) -> Result<(Selection, Vec<bool>, Vec<bool>)> {
+    if select.len() > shape.len() {
+        return Err(format!(
+            "{} indices given for a dataset of {} dimensions",
+            select.len(),
+            shape.len()
+        )
+        .into());
+    }
+    let (mut start, mut count, mut stride) = (vec![], vec![], vec![]);
+    let (mut reversed, mut dropped) = (vec![], vec![]);
+    for (dim, &n) in shape.iter().enumerate() {
+        let n = n as i64;
+        let from_end = |i: i64| if i < 0 { i + n } else { i };
+        let (first, len, step, rev, drop) = match select.get(dim) {
+            Some(&Index::At(i)) => {
+                if !(0..n).contains(&from_end(i)) {
+                    return Err(format!("index {i} is out of dimension {dim} of size {n}").into());
+                }
+                (from_end(i), 1, 1, false, true)
+            }
+            Some(&Index::Slice(lo, hi, step)) if step < 0 => {
+                // Read the same elements forwards, from the last of them.
+                let clamp = |i: i64| from_end(i).clamp(-1, n - 1);
+                let (lo, hi, step) = (lo.map_or(n - 1, clamp), hi.map_or(-1, clamp), -step);
+                let len = if lo > hi {
+                    (lo - hi + step - 1) / step
+                } else {
+                    0
+                };
+                let first = if len > 0 { lo - (len - 1) * step } else { 0 };
+                (first, len, step, true, false)
+            }
+            index => {
+                let (lo, hi, step) = match index {
+                    Some(&Index::Slice(lo, hi, step)) => (lo, hi, step),
+                    _ => (None, None, 1),
+                };
+                let clamp = |i: i64| from_end(i).clamp(0, n);
+                let (lo, hi) = (lo.map_or(0, clamp), hi.map_or(n, clamp));
+                let len = if hi > lo {
+                    (hi - lo + step - 1) / step
+                } else {
+                    0
+                };
+                (lo, len, step, false, false)
+            }
+        };
+        start.push(first as usize);
+        count.push(len as usize);
+        stride.push(step as usize);
+        reversed.push(rev);
+        dropped.push(drop);
+    }
+    let selection = Selection {
+        start: Some(start),
+        count: Some(count),
+        stride: Some(stride),
+        head: None,
+        skip: None,
+    };
+    Ok((selection, reversed, dropped))
+}
+
+/// Reverses and drops the dimensions of nested lists read by `--select`.
+fn arrange(value: Value, reversed: &[bool], dropped: &[bool]) -> Value {
+    let (Some((&rev, reversed)), Some((&drop, dropped))) =
+        (reversed.split_first(), dropped.split_first())
+    else {
+        return value;
+    };
+    match value {
+        Value::List {
+            vals,
+            internal_span,
+        } => {
+            let mut vals: Vec<Value> = vals
+                .into_iter()
+                .map(|val| arrange(val, reversed, dropped))
+                .collect();
+            if rev {
+                vals.reverse();
+            }
+            match vals.pop() {
+                Some(val) if drop => val,
+                val => {
+                    vals.extend(val);
+                    Value::List {
+                        vals,
+                        internal_span,
+                    }
+                }
+            }
+        }
+        value => value,
+    }
+}
+
+struct Context<'a> {
+    file: &'a File,
+    options: &'a Options,
+    span: Span,
+    /// The groups being read, by file and address, to stop at link cycles.
+    ancestors: Mutex<Vec<(u64, LocationToken)>>,
+    progress: Option<Progress>,
+    /// The images of `--lazy` and the id of the file, to hand out datasets of.
+    lazy: Option<(&'a Images, u64)>,
+}
+
+/// The datasets converted so far with `--progress`, reported to stderr.
+struct Progress {
+    /// The datasets to convert, if they could be counted.
+    total: Option<usize>,
+    done: AtomicUsize,
+    bytes: AtomicU64,
+    start: Instant,
+}
+
+impl Progress {
+    fn new(total: Option<usize>) -> Self {
+        Self {
+            total,
+            done: AtomicUsize::new(0),
+            bytes: AtomicU64::new(0),
+            start: Instant::now(),
+        }
+    }
+
+    fn report(&self, dataset: &Dataset, elapsed: Duration) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        let size = dataset.storage_size();
+        let bytes = self.bytes.fetch_add(size, Ordering::Relaxed) + size;
+        let total = self
+            .total
+            .map_or("?".to_string(), |total| total.to_string());
+        eprintln!(
+            "from hdf5: [{done}/{total}] {}, {size} bytes in {elapsed:.2?}; {bytes} bytes in {:.2?} so far",
+            dataset.name(),
+            self.start.elapsed(),
+        );
+    }
+}
+
+/// The number of datasets under a group, stopping at link cycles.
+fn count_datasets(group: &Group, ancestors: &mut Vec<(u64, LocationToken)>) -> Result<usize> {
+    let info = group.loc_info()?;
+    let id = (info.fileno, info.token);
+    if ancestors.contains(&id) {
+        return Ok(0);
+    }
+    ancestors.push(id);
+    let mut count = group.datasets()?.len();
+    for g in group.groups()? {
+        count += count_datasets(&g, ancestors)?;
+    }
+    ancestors.pop();
+    Ok(count)
+}
+
+impl Context<'_> {
+    /// The datatype to read with, which is the file datatype of compounds
+    /// with `--raw-compound`, so that they are decoded with the file layout.
+    /// Compounds have only the members of `--columns`.
+    fn mem_dtype(&self, container: &Container) -> Result<Datatype> {
+        let dtype = container.dtype()?;
+        let is_compound = h5lock!(H5Tget_class(dtype.id())) == H5T_class_t::H5T_COMPOUND;
+        let dtype = if self.options.raw_compound && is_compound {
+            dtype
+        } else {
+            dtype.to_native()?
+        };
+        match &self.options.columns {
+            Some(columns) if is_compound => dtype.select_members(columns),
+            _ => Ok(dtype),
+        }
+    }
+
+    /// Replaces a failure with an `{error}` placeholder with `--lenient`, so
+    /// that the rest of the file still converts. The error already names the
+    /// object that failed.
+    fn lenient(&self, res: Result<Value>) -> Result<Value> {
+        match res {
+            Err(e) if self.options.lenient => Ok(Value::Record {
+                val: Record::from_raw_cols_vals(
+                    vec!["error".to_string()],
+                    vec![Value::String {
+                        val: e.to_string(),
+                        internal_span: self.span,
+                    }],
+                    self.span,
+                    self.span,
+                )
+                .unwrap()
+                .into(),
+                internal_span: self.span,
+            }),
+            res => res,
+        }
+    }
+
+    /// Stops between objects once the user interrupts.
+    fn check_interrupt(&self) -> Result<()> {
+        match &self.options.signals {
+            Some(signals) if signals.interrupted() => Err("interrupted".into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the column is an entry about a group itself, rather than a member.
+    fn is_special(&self, col: &str) -> bool {
+        col.rsplit('/')
+            .next()
+            .and_then(|name| name.rsplit(self.options.flatten_separator.as_str()).next())
+            .is_some_and(|name| name.starts_with('@'))
+    }
+
+    fn matches(&self, glob: &Pattern, path: &str) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+        glob.matches_with(path, options)
+    }
+
+    /// The column name of an object, which is its full path when flattening.
+    fn key(&self, path: &str) -> String {
+        let name = strip_name(path.to_string());
+        let name = match &self.options.sanitize_names {
+            Some(replacement) => name
+                .split('/')
+                .map(|segment| sanitize(segment, replacement))
+                .collect::<Vec<_>>()
+                .join("/"),
+            None => name,
+        };
+        if self.options.flatten_paths && self.options.flatten_separator != "/" {
+            name.replace('/', &self.options.flatten_separator)
+        } else {
+            name
+        }
+    }
+}
+
+/// Replaces the characters of a name out of cell paths, such as dots and spaces.
+fn sanitize(name: &str, replacement: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                c.to_string()
+            } else {
+                replacement.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Widens the bits of an IEEE half-precision float, also subnormals,
+/// infinities and NaN with its sign and payload.
+fn half_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exp = (bits >> 10) & 0x1f;
+    let frac = (bits & 0x3ff) as f64;
+    match exp {
+        0 => sign * frac * 2f64.powi(-24),
+        0x1f if frac == 0.0 => sign * f64::INFINITY,
+        // The payload at the top of the wider fraction, quiet or signaling
+        // as it was.
+        0x1f => {
+            f64::from_bits((bits as u64 & 0x8000) << 48 | 0x7ff << 52 | (bits as u64 & 0x3ff) << 42)
+        }
+        _ => sign * (1.0 + frac / 1024.0) * 2f64.powi(exp as i32 - 15),
+    }
+}
+
+/// Renders a datatype for `--with-dtype`, spelling out the members of
+/// compounds and enums and the element types of arrays.
+fn render_dtype(dtype: &Dtype) -> String {
+    match dtype {
+        Dtype::Compound(comp) => {
+            let fields: Vec<String> = comp
+                .fields
+                .iter()
+                .map(|field| format!("{}: {}", field.name, render_dtype(&field.ty)))
+                .collect();
+            format!("compound {{{}}}", fields.join(", "))
+        }
+        Dtype::Enum(ty) => {
+            let members: Vec<&str> = ty.members.iter().map(|m| m.name.as_str()).collect();
+            format!("enum ({}) {{{}}}", ty.base_type(), members.join(", "))
+        }
+        Dtype::FixedArray(ty, len) => format!("[{}; {}]", render_dtype(ty), len),
+        Dtype::VarLenArray(ty) => format!("[{}] (var len)", render_dtype(ty)),
+        _ => dtype.to_string(),
+    }
+}
+
+/// The bytes of the elements of a variable-length array, which belong to the
+/// buffer it was read into. The length counts elements, not bytes, and the
+/// elements may be variable-length themselves, pointing to more heap data.
+fn vlen_elements<'a>(slice: &'a [u8], ty: &Dtype) -> &'a [u8] {
+    let hvl = native_copy!(hvl_t, slice);
+    if hvl.len == 0 || hvl.p.is_null() {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(hvl.p.cast(), hvl.len * ty.size()) }
+    }
+}
+
+/// Decodes a fixed-length string strictly: ASCII must be 7-bit and unicode
+/// must be valid UTF-8. With `--lenient` bad bytes are replaced instead,
+/// unless `--strict`.
+fn fixed_string(bytes: &[u8], ascii: bool, options: &Options) -> Result<String> {
+    if options.lenient && !options.strict {
+        return Ok(String::from_utf8_lossy(bytes).into_owned());
+    }
+    if ascii {
+        if let Some(offset) = bytes.iter().position(|&b| b >= 0x80) {
+            return Err(format!(
+                "invalid ASCII byte {:#04x} at offset {offset} of fixed string",
+                bytes[offset]
+            )
+            .into());
+        }
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(str) => Ok(str.to_string()),
+        Err(e) => Err(format!(
+            "invalid UTF-8 at offset {} of fixed string",
+            e.valid_up_to()
+        )
+        .into()),
+    }
+}
+
+fn to_value(slice: &[u8], dtype: &Dtype, ctx: &Context) -> Result<Value> {
+    let span = ctx.span;
     let val = match dtype {
-        TypeDescriptor::Integer(IntSize::U1) => native_value!(i8, Int, slice, span),
-        TypeDescriptor::Integer(IntSize::U2) => native_value!(i16, Int, slice, span),
-        TypeDescriptor::Integer(IntSize::U4) => native_value!(i32, Int, slice, span),
-        TypeDescriptor::Integer(IntSize::U8) => native_value!(i64, Int, slice, span),
-        TypeDescriptor::Unsigned(IntSize::U1) => native_value!(u8, Int, slice, span),
-        TypeDescriptor::Unsigned(IntSize::U2) => native_value!(u16, Int, slice, span),
-        TypeDescriptor::Unsigned(IntSize::U4) => native_value!(u32, Int, slice, span),
-        TypeDescriptor::Unsigned(IntSize::U8) => native_value!(u64, Int, slice, span),
-        TypeDescriptor::Float(FloatSize::U4) => native_value!(f32, Float, slice, span),
-        TypeDescriptor::Float(FloatSize::U8) => native_value!(f64, Float, slice, span),
-        TypeDescriptor::Boolean => native_value!(bool, Bool, slice, span),
-        TypeDescriptor::Enum(ty) => {
+        Dtype::Integer(IntSize::U1) => native_value!(i8, Int, slice, span),
+        Dtype::Integer(IntSize::U2) => native_value!(i16, Int, slice, span),
+        Dtype::Integer(IntSize::U4) => native_value!(i32, Int, slice, span),
+        Dtype::Integer(IntSize::U8) => native_value!(i64, Int, slice, span),
+        Dtype::Unsigned(IntSize::U1) => native_value!(u8, Int, slice, span),
+        Dtype::Unsigned(IntSize::U2) => native_value!(u16, Int, slice, span),
+        Dtype::Unsigned(IntSize::U4) => native_value!(u32, Int, slice, span),
+        Dtype::Unsigned(IntSize::U8) => {
+            assert_eq!(slice.len(), std::mem::size_of::<u64>());
+            let val = unsafe { std::ptr::read_unaligned(native!(u64, slice)) };
+            match i64::try_from(val) {
+                Ok(val) => Value::Int {
+                    val,
+                    internal_span: span,
+                },
+                Err(_) if ctx.options.strict => {
+                    return Err(format!("uint64 {val} is out of the range of int").into())
+                }
+                // Out of the range of Int, so kept exact as digits unless asked for.
+                Err(_) if ctx.options.u64_as_float => Value::Float {
+                    val: val as f64,
+                    internal_span: span,
+                },
+                Err(_) => Value::String {
+                    val: val.to_string(),
+                    internal_span: span,
+                },
+            }
+        }
+        // Widening keeps NaN, the infinities and the sign of zero, all of which
+        // a Float holds as is.
+        Dtype::Float(FloatSize::U4) => nan_as_null(native_value!(f32, Float, slice, span), ctx),
+        Dtype::Float(FloatSize::U8) => nan_as_null(native_value!(f64, Float, slice, span), ctx),
+        Dtype::Half => {
+            assert_eq!(slice.len(), 2);
+            let val = Value::Float {
+                val: half_to_f64(u16::from_ne_bytes([slice[0], slice[1]])),
+                internal_span: span,
+            };
+            nan_as_null(val, ctx)
+        }
+        Dtype::Boolean(size) => {
+            // Nonzero of any width and byte order is true.
+            assert_eq!(slice.len(), *size as usize);
+            Value::Bool {
+                val: slice.iter().any(|&b| b != 0),
+                internal_span: span,
+            }
+        }
+        Dtype::Enum(ty) => {
             let int_size = ty.size;
-            if ty.signed {
-                to_value(slice, &TypeDescriptor::Integer(int_size), span)?
+            // Member values are fetched into the low bytes of a `u64`, the same
+            // way as here.
+            let mut value = 0u64;
+            assert_eq!(slice.len(), int_size as usize);
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    slice.as_ptr(),
+                    std::ptr::addr_of_mut!(value).cast(),
+                    slice.len(),
+                )
+            };
+            let member = ty.members.iter().find(|m| m.value == value);
+            if let (Some(member), false) = (member, ctx.options.raw_enums) {
+                Value::String {
+                    val: member.name.clone(),
+                    internal_span: span,
+                }
+            } else if ty.signed {
+                to_value(slice, &Dtype::Integer(int_size), ctx)?
+            } else {
+                to_value(slice, &Dtype::Unsigned(int_size), ctx)?
+            }
+        }
+        Dtype::Compound(comp) if comp.complex_parts().is_some() => {
+            assert_eq!(slice.len(), comp.size);
+            let (re, im) = comp.complex_parts().unwrap();
+            // Nothing for a NaN part with `--nan-as-null`.
+            let part = |field: &CompoundField| -> Result<Option<f64>> {
+                let val = to_value(
+                    &slice[field.offset..field.offset + field.ty.size()],
+                    &field.ty,
+                    ctx,
+                )?;
+                Ok(val.as_float().ok())
+            };
+            let (Some(re), Some(im)) = (part(re)?, part(im)?) else {
+                return Ok(Value::Nothing {
+                    internal_span: span,
+                });
+            };
+            if ctx.options.complex_string {
+                let sign = if im.is_sign_negative() { '-' } else { '+' };
+                Value::String {
+                    val: format!("{re}{sign}{}i", im.abs()),
+                    internal_span: span,
+                }
             } else {
-                to_value(slice, &TypeDescriptor::Unsigned(int_size), span)?
+                Value::Record {
+                    val: Record::from_raw_cols_vals(
+                        vec!["re".to_string(), "im".to_string()],
+                        vec![
+                            Value::Float {
+                                val: re,
+                                internal_span: span,
+                            },
+                            Value::Float {
+                                val: im,
+                                internal_span: span,
+                            },
+                        ],
+                        span,
+                        span,
+                    )
+                    .unwrap()
+                    .into(),
+                    internal_span: span,
+                }
             }
         }
-        TypeDescriptor::Compound(comp) => {
+        Dtype::Compound(comp) => {
             assert_eq!(slice.len(), comp.size);
             let mut cols = vec![];
             let mut vals = vec![];
@@ -51,7 +1331,7 @@ fn to_value(slice: &[u8], dtype: &TypeDescriptor, span: Span) -> Result<Value> {
                 vals.push(to_value(
                     &slice[field.offset..field.offset + field.ty.size()],
                     &field.ty,
-                    span,
+                    ctx,
                 )?)
             }
             Value::Record {
@@ -61,138 +1341,4934 @@ fn to_value(slice: &[u8], dtype: &TypeDescriptor, span: Span) -> Result<Value> {
                 internal_span: span,
             }
         }
-        TypeDescriptor::FixedArray(ty, len) => {
+        Dtype::FixedArray(ty, len) => {
             assert_eq!(slice.len(), ty.size() * len);
             Value::List {
                 vals: slice
                     .chunks(ty.size())
-                    .map(|slice| to_value(slice, ty, span))
+                    .map(|slice| to_value(slice, ty, ctx))
                     .try_collect()?,
                 internal_span: span,
             }
         }
-        TypeDescriptor::FixedAscii(len) | TypeDescriptor::FixedUnicode(len) => {
+        Dtype::FixedAscii(len, _) | Dtype::FixedUnicode(len, _) if ctx.options.bytes_as_binary => {
             assert_eq!(slice.len(), *len);
-            Value::String {
-                val: String::from_utf8_lossy(slice).into_owned(),
+            Value::Binary {
+                val: slice.to_vec(),
                 internal_span: span,
             }
         }
-        TypeDescriptor::VarLenArray(ty) => {
-            let hvl = unsafe { native!(VarLenArray<u8>, slice).as_ref() }.unwrap();
-            Value::List {
-                vals: hvl
-                    .chunks(ty.size())
-                    .map(|slice| to_value(slice, ty, span))
-                    .try_collect()?,
+        Dtype::FixedAscii(len, pad) | Dtype::FixedUnicode(len, pad) => {
+            assert_eq!(slice.len(), *len);
+            let end = match pad {
+                StrPad::SpacePad => {
+                    slice.len() - slice.iter().rev().take_while(|&&b| b == b' ').count()
+                }
+                StrPad::NullTerm | StrPad::NullPad => {
+                    slice.iter().position(|&b| b == 0).unwrap_or(slice.len())
+                }
+            };
+            let ascii = matches!(dtype, Dtype::FixedAscii(..));
+            Value::String {
+                val: fixed_string(&slice[..end], ascii, ctx.options)?,
                 internal_span: span,
             }
         }
-        TypeDescriptor::VarLenAscii => {
-            let str = unsafe { native!(VarLenAscii, slice).as_ref() }.unwrap();
+        Dtype::VarLenArray(ty) => Value::List {
+            vals: vlen_elements(slice, ty)
+                .chunks(ty.size())
+                .map(|slice| to_value(slice, ty, ctx))
+                .try_collect()?,
+            internal_span: span,
+        },
+        Dtype::VarLenAscii => {
+            let str = native_copy!(VarLenAscii, slice);
             Value::String {
                 val: str.as_str().to_string(),
                 internal_span: span,
             }
         }
-        TypeDescriptor::VarLenUnicode => {
-            let str = unsafe { native!(VarLenUnicode, slice).as_ref() }.unwrap();
+        Dtype::VarLenUnicode => {
+            let str = native_copy!(VarLenUnicode, slice);
             Value::String {
                 val: str.as_str().to_string(),
                 internal_span: span,
             }
         }
-    };
-    Ok(val)
-}
-
-fn to_list(dataset: &Dataset, span: Span) -> Result<Value> {
-    let dtype = dataset.dtype()?.to_descriptor()?;
-    let data = dataset.read_raw_bytes(&dtype)?;
-    let vals: Vec<Value> = data
-        .chunks(dtype.size())
-        .map(|slice| to_value(slice, &dtype, span))
-        .try_collect()?;
-    assert_eq!(vals.len(), dataset.size());
-    Ok(Value::List {
-        vals,
-        internal_span: span,
-    })
-}
-
-fn to_record(group: &Group, span: Span) -> Result<Value> {
-    let mut cols = vec![];
-    let mut vals = vec![];
-    for ds in group.datasets()? {
-        cols.push(strip_name(ds.name()));
-        vals.push(to_list(&ds, span)?);
-    }
-    for g in group.groups()? {
-        cols.push(strip_name(g.name()));
-        vals.push(to_record(&g, span)?);
-    }
-    Ok(Value::Record {
-        val: Record::from_raw_cols_vals(cols, vals, span, span)
-            .unwrap()
+        Dtype::ObjectReference => {
+            assert_eq!(slice.len(), std::mem::size_of::<hobj_ref_t>());
+            let reference = unsafe { std::ptr::read_unaligned(native!(hobj_ref_t, slice)) };
+            // A dangling reference has no target.
+            match ctx.file.dereference(reference) {
+                Ok(val) => Value::String {
+                    val,
+                    internal_span: span,
+                },
+                Err(e) if ctx.options.strict => {
+                    return Err(format!("dangling object reference: {e}").into())
+                }
+                Err(_) => Value::Nothing {
+                    internal_span: span,
+                },
+            }
+        }
+        Dtype::RegionReference => {
+            let reference: hdset_reg_ref_t = slice.try_into().unwrap();
+            match ctx.file.dereference_region(reference) {
+                Ok((target, space)) => to_region(&target, &space, ctx)?,
+                Err(e) if ctx.options.strict => {
+                    return Err(format!("dangling region reference: {e}").into())
+                }
+                Err(_) => Value::Nothing {
+                    internal_span: span,
+                },
+            }
+        }
+        Dtype::Bitfield(size) if *size <= 8 => {
+            // In the native byte order, like enums.
+            let mut bits = 0u64;
+            assert_eq!(slice.len(), *size);
+            unsafe {
+                std::ptr::copy_nonoverlapping(
+                    slice.as_ptr(),
+                    std::ptr::addr_of_mut!(bits).cast(),
+                    slice.len(),
+                )
+            };
+            // The bits as they are, for ones out of the range of Int.
+            match i64::try_from(bits) {
+                Ok(val) => Value::Int {
+                    val,
+                    internal_span: span,
+                },
+                Err(_) => Value::Binary {
+                    val: slice.to_vec(),
+                    internal_span: span,
+                },
+            }
+        }
+        Dtype::Bitfield(size) => {
+            assert_eq!(slice.len(), *size);
+            Value::Binary {
+                val: slice.to_vec(),
+                internal_span: span,
+            }
+        }
+        Dtype::Opaque(opaque) => {
+            assert_eq!(slice.len(), opaque.size);
+            let data = Value::Binary {
+                val: slice.to_vec(),
+                internal_span: span,
+            };
+            if ctx.options.opaque_tags {
+                Value::Record {
+                    val: Record::from_raw_cols_vals(
+                        vec!["tag".to_string(), "data".to_string()],
+                        vec![
+                            Value::String {
+                                val: opaque.tag.clone(),
+                                internal_span: span,
+                            },
+                            data,
+                        ],
+                        span,
+                        span,
+                    )
+                    .unwrap()
+                    .into(),
+                    internal_span: span,
+                }
+            } else {
+                data
+            }
+        }
+        Dtype::Time { size, big_endian } => {
+            assert_eq!(slice.len(), *size);
+            let offset = match (*size, *big_endian) {
+                (4, false) => i32::from_le_bytes(slice.try_into().unwrap()) as i64,
+                (4, true) => i32::from_be_bytes(slice.try_into().unwrap()) as i64,
+                (8, false) => i64::from_le_bytes(slice.try_into().unwrap()),
+                (8, true) => i64::from_be_bytes(slice.try_into().unwrap()),
+                (size, _) => return Err(format!("unsupported time of {size} bytes").into()),
+            };
+            match TimeUnit::Seconds.to_date(offset) {
+                Some(val) => Value::Date {
+                    val,
+                    internal_span: span,
+                },
+                None => Value::Nothing {
+                    internal_span: span,
+                },
+            }
+        }
+    };
+    Ok(val)
+}
+
+/// A region reference as `{target, start, count, points}`, the bounding box of
+/// the selection and the number of elements selected, with the elements as
+/// `data` with `--read-regions`.
+fn to_region(target: &Dataset, space: &Dataspace, ctx: &Context) -> Result<Value> {
+    let span = ctx.span;
+    let (start, count) = space.selection_bounds()?;
+    let ints = |vals: Vec<usize>| Value::List {
+        vals: vals
+            .into_iter()
+            .map(|val| Value::Int {
+                val: val as _,
+                internal_span: span,
+            })
+            .collect(),
+        internal_span: span,
+    };
+    let mut cols = vec![
+        "target".to_string(),
+        "start".to_string(),
+        "count".to_string(),
+        "points".to_string(),
+    ];
+    let mut vals = vec![
+        Value::String {
+            val: target.name(),
+            internal_span: span,
+        },
+        ints(start),
+        ints(count),
+        Value::Int {
+            val: space.selection_size() as _,
+            internal_span: span,
+        },
+    ];
+    if ctx.options.read_regions {
+        let mem_dtype = ctx.mem_dtype(target)?;
+        let dtype = Dtype::from_datatype(&mem_dtype)?;
+        let data = target.read_raw_bytes_region(&mem_dtype, space)?;
+        cols.push("data".to_string());
+        vals.push(decode(&data, &dtype, &[space.selection_size()], None, ctx)?);
+    }
+    Ok(Value::Record {
+        val: Record::from_raw_cols_vals(cols, vals, span, span)
+            .unwrap()
+            .into(),
+        internal_span: span,
+    })
+}
+
+/// Feeds the decoded element to the hasher. Variable-length data is hashed by
+/// content with its length, references by their target path, and region
+/// references also by the bounds and size of the selection, so the hash
+/// doesn't depend on pointers or on the storage.
+fn hash_value(slice: &[u8], dtype: &Dtype, ctx: &Context, hasher: &mut Sha256) -> Result<()> {
+    match dtype {
+        Dtype::Compound(comp) => {
+            for field in comp.fields.iter() {
+                hash_value(
+                    &slice[field.offset..field.offset + field.ty.size()],
+                    &field.ty,
+                    ctx,
+                    hasher,
+                )?;
+            }
+        }
+        Dtype::FixedArray(ty, _) => {
+            for slice in slice.chunks(ty.size()) {
+                hash_value(slice, ty, ctx, hasher)?;
+            }
+        }
+        Dtype::VarLenArray(ty) => {
+            let data = vlen_elements(slice, ty);
+            hasher.update(&((data.len() / ty.size()) as u64).to_ne_bytes());
+            for slice in data.chunks(ty.size()) {
+                hash_value(slice, ty, ctx, hasher)?;
+            }
+        }
+        Dtype::VarLenAscii => {
+            let str = native_copy!(VarLenAscii, slice);
+            hasher.update(&(str.len() as u64).to_ne_bytes());
+            hasher.update(str.as_bytes());
+        }
+        Dtype::VarLenUnicode => {
+            let str = native_copy!(VarLenUnicode, slice);
+            hasher.update(&(str.len() as u64).to_ne_bytes());
+            hasher.update(str.as_bytes());
+        }
+        Dtype::ObjectReference => {
+            let reference = unsafe { std::ptr::read_unaligned(native!(hobj_ref_t, slice)) };
+            let path = ctx.file.dereference(reference).unwrap_or_default();
+            hasher.update(&(path.len() as u64).to_ne_bytes());
+            hasher.update(path.as_bytes());
+        }
+        Dtype::RegionReference => {
+            let reference: hdset_reg_ref_t = slice.try_into().unwrap();
+            // A dangling reference hashes as an empty path.
+            let (path, bounds) = match ctx.file.dereference_region(reference) {
+                Ok((target, space)) => {
+                    let (start, count) = space.selection_bounds()?;
+                    let mut bounds = vec![space.selection_size()];
+                    bounds.extend(start);
+                    bounds.extend(count);
+                    (target.name(), bounds)
+                }
+                Err(_) => (String::new(), vec![]),
+            };
+            hasher.update(&(path.len() as u64).to_ne_bytes());
+            hasher.update(path.as_bytes());
+            hasher.update(&(bounds.len() as u64).to_ne_bytes());
+            for bound in bounds {
+                hasher.update(&(bound as u64).to_ne_bytes());
+            }
+        }
+        _ => hasher.update(slice),
+    }
+    Ok(())
+}
+
+/// The SHA-256 of the elements of a dataset, read a block at a time.
+fn to_checksum(dataset: &Dataset, ctx: &Context) -> Result<Value> {
+    let mem_dtype = dataset.dtype()?.to_native()?;
+    let dtype = Dtype::from_datatype(&mem_dtype)?;
+    let mut hasher = Sha256::new();
+    dataset.read_blocks(&mem_dtype, STREAM_CHUNK, |data| {
+        ctx.check_interrupt()?;
+        for slice in data.chunks(dtype.size()) {
+            hash_value(slice, &dtype, ctx, &mut hasher)?;
+        }
+        Ok(())
+    })?;
+    Ok(Value::String {
+        val: hasher.finalize_hex(),
+        internal_span: ctx.span,
+    })
+}
+
+/// Collects a `{path, sha256}` row for every dataset below the group, with
+/// the data too if asked to.
+fn to_checksums(group: &Group, ctx: &Context, rows: &mut Vec<Value>) -> Result<()> {
+    let span = ctx.span;
+    for ds in group.datasets()? {
+        let mut cols = vec!["path".to_string(), "sha256".to_string()];
+        let mut vals = vec![
+            Value::String {
+                val: ds.name(),
+                internal_span: span,
+            },
+            failed_to_read(&ds.name(), to_checksum(&ds, ctx))?,
+        ];
+        if ctx.options.with_checksum {
+            cols.push("value".to_string());
+            vals.push(failed_to_read(&ds.name(), to_dataset_list(&ds, ctx))?);
+        }
+        rows.push(Value::Record {
+            val: Record::from_raw_cols_vals(cols, vals, span, span)
+                .unwrap()
+                .into(),
+            internal_span: span,
+        });
+    }
+    for g in group.groups()? {
+        to_checksums(&g, ctx, rows)?;
+    }
+    Ok(())
+}
+
+fn to_list<C>(container: &C, ctx: &Context) -> Result<Value>
+where
+    C: Deref<Target = Container> + ReadRawBytes,
+{
+    match dtypes(container, ctx)? {
+        Ok((mem_dtype, dtype)) => read_list(container, &mem_dtype, &dtype, None, ctx),
+        Err(val) => Ok(val),
+    }
+}
+
+/// The datatype to read with and its description, worked out once for each
+/// dataset or attribute. With `--partial`, a datatype that can't be decoded
+/// gives the raw bytes instead.
+fn dtypes<C>(container: &C, ctx: &Context) -> Result<Result<(Datatype, Dtype), Value>>
+where
+    C: Deref<Target = Container> + ReadRawBytes,
+{
+    let res = ctx
+        .mem_dtype(container)
+        .and_then(|mem_dtype| Ok((Dtype::from_datatype(&mem_dtype)?, mem_dtype)));
+    match res {
+        Ok((dtype, mem_dtype)) => Ok(Ok((mem_dtype, dtype))),
+        Err(e) if ctx.options.partial => Ok(Err(to_partial(container, e, ctx)?)),
+        Err(e) => Err(e),
+    }
+}
+
+fn read_list<C>(
+    container: &C,
+    mem_dtype: &Datatype,
+    dtype: &Dtype,
+    fill: Option<&[u8]>,
+    ctx: &Context,
+) -> Result<Value>
+where
+    C: Deref<Target = Container> + ReadRawBytes,
+{
+    if let Some(val) = to_empty(container, dtype, ctx)? {
+        return Ok(val);
+    }
+    let data = container.read_raw_bytes(mem_dtype)?;
+    decode(&data, dtype, &container.shape(), fill, ctx)
+}
+
+/// The value of data without elements, which isn't read: nothing for a null
+/// dataspace, and empty lists for a zero extent.
+fn to_empty(container: &Container, dtype: &Dtype, ctx: &Context) -> Result<Option<Value>> {
+    if container.space()?.is_null() {
+        Ok(Some(Value::Nothing {
+            internal_span: ctx.span,
+        }))
+    } else if container.size() == 0 {
+        Ok(Some(decode(&[], dtype, &container.shape(), None, ctx)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// With `--partial`, the raw bytes of data of a datatype that can't be
+/// decoded, as `{dtype, data}` with a note of the datatype.
+fn to_partial<C>(container: &C, e: hdf5::Error, ctx: &Context) -> Result<Value>
+where
+    C: Deref<Target = Container> + ReadRawBytes,
+{
+    let span = ctx.span;
+    // Read as is, without conversion.
+    let dtype = container.dtype()?;
+    let data = container.read_raw_bytes(&dtype)?;
+    let class = h5lock!(H5Tget_class(dtype.id()));
+    Ok(Value::Record {
+        val: Record::from_raw_cols_vals(
+            vec!["dtype".to_string(), "data".to_string()],
+            vec![
+                Value::String {
+                    val: format!("{class:?} ({} bytes): {e}", dtype.size()),
+                    internal_span: span,
+                },
+                Value::Binary {
+                    val: data.to_vec(),
+                    internal_span: span,
+                },
+            ],
+            span,
+            span,
+        )
+        .unwrap()
+        .into(),
+        internal_span: span,
+    })
+}
+
+/// Like `to_list`, but reads only the hyperslab of `--start`, `--count` and
+/// `--stride`.
+fn to_dataset_list(dataset: &Dataset, ctx: &Context) -> Result<Value> {
+    if !ctx.options.table {
+        return read_dataset_list(dataset, ctx);
+    }
+    if dataset.ndim() != 2 {
+        return Err(format!(
+            "--table needs a 2-D dataset, but it has {} dimensions",
+            dataset.ndim()
+        )
+        .into());
+    }
+    let names = table_column_names(dataset, ctx)?;
+    Ok(rows_to_records(
+        read_dataset_list(dataset, ctx)?,
+        &names,
+        ctx.span,
+    ))
+}
+
+/// The column names of `--table`, from the `column_names` attribute of the
+/// dataset, or else `--column-names`.
+fn table_column_names(dataset: &Dataset, ctx: &Context) -> Result<Vec<String>> {
+    if !dataset
+        .attr_names()?
+        .iter()
+        .any(|name| name == "column_names")
+    {
+        return Ok(ctx.options.column_names.clone());
+    }
+    match to_list(&dataset.attr("column_names")?, ctx)? {
+        Value::List { vals, .. } => vals
+            .iter()
+            .map(|v| v.coerce_string().map_err(|e| e.to_string().into()))
+            .try_collect(),
+        Value::String { val, .. } => Ok(val.split(',').map(|s| s.trim().to_string()).collect()),
+        v => Err(format!("column_names should be strings, got {}", v.get_type()).into()),
+    }
+}
+
+/// Turns the rows of a 2-D list into records, naming missing columns `col_N`.
+fn rows_to_records(value: Value, names: &[String], span: Span) -> Value {
+    match value {
+        Value::List {
+            vals,
+            internal_span,
+        } => Value::List {
+            vals: vals
+                .into_iter()
+                .map(|row| match row {
+                    Value::List { vals, .. } => {
+                        let cols = (0..vals.len())
+                            .map(|i| names.get(i).cloned().unwrap_or_else(|| format!("col_{i}")))
+                            .collect();
+                        Value::Record {
+                            val: Record::from_raw_cols_vals(cols, vals, span, span)
+                                .unwrap()
+                                .into(),
+                            internal_span: span,
+                        }
+                    }
+                    // Already a record of `--2d-as-table`.
+                    row => row,
+                })
+                .collect(),
+            internal_span,
+        },
+        value => value,
+    }
+}
+
+fn read_dataset_list(dataset: &Dataset, ctx: &Context) -> Result<Value> {
+    let (mem_dtype, dtype) = match dtypes(dataset, ctx)? {
+        Ok(dtypes) => dtypes,
+        Err(val) => return Ok(val),
+    };
+    // Flattened to 1-D with `--flatten`, as `decode` does.
+    let rank = match dataset.ndim() {
+        2.. if ctx.options.flatten => 1,
+        rank => rank,
+    };
+    if ctx.options.transpose_compound && !(matches!(dtype, Dtype::Compound(_)) && rank == 1) {
+        return Err(format!(
+            "--transpose-compound needs 1-D compound datasets, but {} is {dtype} of rank {}",
+            dataset.name(),
+            dataset.ndim()
+        )
+        .into());
+    }
+    let fill = if ctx.options.fill_as_null && is_scalar(&dtype) {
+        dataset.dcpl()?.user_fill_value(&mem_dtype)?
+    } else {
+        None
+    };
+    let raw = ctx.options.raw
+        && matches!(
+            dtype,
+            Dtype::Integer(_) | Dtype::Unsigned(_) | Dtype::Float(_) | Dtype::Half
+        );
+    if let (Some(select), true) = (&ctx.options.select, dataset.ndim() > 0) {
+        let (selection, reversed, dropped) = resolve_select(select, &dataset.shape())?;
+        let (data, shape) = dataset.read_raw_bytes_selection(&mem_dtype, &selection)?;
+        let val = decode(&data, &dtype, &shape, fill.as_deref(), ctx)?;
+        return Ok(arrange(val, &reversed, &dropped));
+    }
+    match &ctx.options.selection {
+        selection if !selection.is_empty() && dataset.ndim() > 0 => {
+            if !raw {
+                if let Some(val) = to_empty(dataset, &dtype, ctx)? {
+                    return Ok(val);
+                }
+            }
+            let (data, shape) = dataset.read_raw_bytes_selection(&mem_dtype, selection)?;
+            if raw {
+                return Ok(to_raw(data.to_vec(), &dtype, &shape, ctx));
+            }
+            decode_dataset(dataset, &data, &dtype, &shape, fill.as_deref(), ctx)
+        }
+        _ if raw => {
+            let data = if dataset.size() == 0 || dataset.space()?.is_null() {
+                vec![]
+            } else {
+                dataset.read_raw_bytes(&mem_dtype)?.to_vec()
+            };
+            Ok(to_raw(data, &dtype, &dataset.shape(), ctx))
+        }
+        _ => {
+            if let Some(val) = to_empty(dataset, &dtype, ctx)? {
+                return Ok(val);
+            }
+            let data = dataset.read_raw_bytes(&mem_dtype)?;
+            let shape = dataset.shape();
+            decode_dataset(dataset, &data, &dtype, &shape, fill.as_deref(), ctx)
+        }
+    }
+}
+
+/// With `--raw`, numeric data as the binary of its elements in the native
+/// byte order, which is described by `order`.
+fn to_raw(data: Vec<u8>, dtype: &Dtype, shape: &[usize], ctx: &Context) -> Value {
+    let span = ctx.span;
+    let cols = vec![
+        "data".to_string(),
+        "dtype".to_string(),
+        "shape".to_string(),
+        "order".to_string(),
+    ];
+    let order = if cfg!(target_endian = "little") {
+        "little"
+    } else {
+        "big"
+    };
+    let vals = vec![
+        Value::Binary {
+            val: data,
+            internal_span: span,
+        },
+        Value::String {
+            val: dtype.to_string(),
+            internal_span: span,
+        },
+        Value::List {
+            vals: shape
+                .iter()
+                .map(|&d| Value::Int {
+                    val: d as _,
+                    internal_span: span,
+                })
+                .collect(),
+            internal_span: span,
+        },
+        Value::String {
+            val: order.to_string(),
+            internal_span: span,
+        },
+    ];
+    Value::Record {
+        val: Record::from_raw_cols_vals(cols, vals, span, span)
+            .unwrap()
+            .into(),
+        internal_span: span,
+    }
+}
+
+/// Whether the elements of a datatype are equal exactly when their bytes are,
+/// so that they can be compared to the fill value of `--fill-as-null`.
+fn is_scalar(dtype: &Dtype) -> bool {
+    matches!(
+        dtype,
+        Dtype::Integer(_)
+            | Dtype::Unsigned(_)
+            | Dtype::Float(_)
+            | Dtype::Half
+            | Dtype::Boolean(_)
+            | Dtype::Enum(_)
+            | Dtype::FixedAscii(..)
+            | Dtype::FixedUnicode(..)
+            | Dtype::Bitfield(_)
+    )
+}
+
+/// Decodes the elements of data into nested lists of the shape. Elements the
+/// same as `fill` are nothing.
+fn decode(
+    data: &[u8],
+    dtype: &Dtype,
+    shape: &[usize],
+    fill: Option<&[u8]>,
+    ctx: &Context,
+) -> Result<Value> {
+    let flat = [shape.iter().product()];
+    let shape = match shape.len() {
+        0 if ctx.options.scalars_as_lists => &flat,
+        2.. if ctx.options.flatten => &flat,
+        _ => shape,
+    };
+    if let (Dtype::Compound(comp), 1) = (dtype, shape.len()) {
+        if ctx.options.transpose_compound {
+            return to_columns(data, comp, ctx);
+        }
+    }
+    if shape.is_empty() {
+        // A scalar, whose size is 1 as the empty product.
+        assert_eq!(data.len(), dtype.size());
+        return to_element(data, dtype, fill, ctx);
+    }
+    let convert = |slice| to_element(slice, dtype, fill, ctx);
+    let primitive = match fill {
+        None if !ctx.options.parallel && !ctx.options.nan_as_null => {
+            primitive_values(data, dtype, ctx.span)
+        }
+        _ => None,
+    };
+    let vals: Vec<Value> = if let Some(vals) = primitive {
+        vals
+    } else if ctx.options.parallel {
+        data.par_chunks(dtype.size())
+            .map(convert)
+            .collect::<Result<_>>()?
+    } else {
+        data.chunks(dtype.size()).map(convert).try_collect()?
+    };
+    assert_eq!(vals.len(), shape.iter().product::<usize>());
+    match (dtype, shape) {
+        (
+            Dtype::Integer(_) | Dtype::Unsigned(_) | Dtype::Float(_) | Dtype::Half,
+            &[nrows, ncols],
+        ) if ctx.options.table_2d => Ok(to_2d_table(vals, nrows, ncols, ctx)),
+        _ => Ok(reshape(vals, shape, ctx.span)),
+    }
+}
+
+/// The elements of primitive numbers without the dispatch of `to_value` for
+/// each of them, or none for other datatypes. Uint64 is left to `to_value`
+/// for the values out of the range of int.
+fn primitive_values(data: &[u8], dtype: &Dtype, span: Span) -> Option<Vec<Value>> {
+    let vals = match dtype {
+        Dtype::Integer(IntSize::U1) => native_values!(i8, Int, data, span),
+        Dtype::Integer(IntSize::U2) => native_values!(i16, Int, data, span),
+        Dtype::Integer(IntSize::U4) => native_values!(i32, Int, data, span),
+        Dtype::Integer(IntSize::U8) => native_values!(i64, Int, data, span),
+        Dtype::Unsigned(IntSize::U1) => native_values!(u8, Int, data, span),
+        Dtype::Unsigned(IntSize::U2) => native_values!(u16, Int, data, span),
+        Dtype::Unsigned(IntSize::U4) => native_values!(u32, Int, data, span),
+        Dtype::Float(FloatSize::U4) => native_values!(f32, Float, data, span),
+        Dtype::Float(FloatSize::U8) => native_values!(f64, Float, data, span),
+        _ => return None,
+    };
+    Some(vals)
+}
+
+/// A NaN float as nothing with `--nan-as-null`.
+fn nan_as_null(value: Value, ctx: &Context) -> Value {
+    match value {
+        Value::Float { val, internal_span } if val.is_nan() && ctx.options.nan_as_null => {
+            Value::Nothing { internal_span }
+        }
+        value => value,
+    }
+}
+
+fn to_element(slice: &[u8], dtype: &Dtype, fill: Option<&[u8]>, ctx: &Context) -> Result<Value> {
+    if fill == Some(slice) {
+        Ok(Value::Nothing {
+            internal_span: ctx.span,
+        })
+    } else {
+        to_value(slice, dtype, ctx)
+    }
+}
+
+/// Decodes a 1-D compound dataset field by field into a record of lists.
+fn to_columns(data: &[u8], comp: &CompoundDtype, ctx: &Context) -> Result<Value> {
+    let span = ctx.span;
+    let cols = comp.fields.iter().map(|field| field.name.clone()).collect();
+    let vals = comp
+        .fields
+        .iter()
+        .map(|field| -> Result<Value> {
+            Ok(Value::List {
+                vals: data
+                    .chunks(comp.size)
+                    .map(|slice| {
+                        to_value(
+                            &slice[field.offset..field.offset + field.ty.size()],
+                            &field.ty,
+                            ctx,
+                        )
+                    })
+                    .try_collect()?,
+                internal_span: span,
+            })
+        })
+        .try_collect()?;
+    Ok(Value::Record {
+        val: Record::from_raw_cols_vals(cols, vals, span, span)
+            .unwrap()
+            .into(),
+        internal_span: span,
+    })
+}
+
+/// Turns a numeric matrix into a table, a record per row, with the columns
+/// named by `--column-names` or `col_0`, `col_1`, ...
+fn to_2d_table(vals: Vec<Value>, nrows: usize, ncols: usize, ctx: &Context) -> Value {
+    let span = ctx.span;
+    let cols: Vec<String> = (0..ncols)
+        .map(|i| {
+            ctx.options
+                .column_names
+                .get(i)
+                .cloned()
+                .unwrap_or_else(|| format!("col_{i}"))
+        })
+        .collect();
+    let mut vals = vals.into_iter();
+    Value::List {
+        vals: (0..nrows)
+            .map(|_| Value::Record {
+                val: Record::from_raw_cols_vals(
+                    cols.clone(),
+                    vals.by_ref().take(ncols).collect(),
+                    span,
+                    span,
+                )
+                .unwrap()
+                .into(),
+                internal_span: span,
+            })
+            .collect(),
+        internal_span: span,
+    }
+}
+
+/// Folds the elements, in row-major order, into nested lists of the shape.
+fn reshape(vals: Vec<Value>, shape: &[usize], span: Span) -> Value {
+    match shape {
+        [_, rest @ ..] if !rest.is_empty() => {
+            let stride = rest.iter().product();
+            let mut vals = vals.into_iter();
+            Value::List {
+                vals: (0..shape[0])
+                    .map(|_| reshape(vals.by_ref().take(stride).collect(), rest, span))
+                    .collect(),
+                internal_span: span,
+            }
+        }
+        _ => Value::List {
+            vals,
+            internal_span: span,
+        },
+    }
+}
+
+/// The dtype and shape of a dataset or an attribute, without reading the data.
+fn to_shape_meta(container: &Container, ctx: &Context) -> Result<(Vec<String>, Vec<Value>)> {
+    let span = ctx.span;
+    let dtype = Dtype::from_datatype(&container.dtype()?.to_native()?)?;
+    let shape = container
+        .shape()
+        .into_iter()
+        .map(|d| Value::Int {
+            val: d as _,
+            internal_span: span,
+        })
+        .collect();
+    let cols = vec!["dtype".to_string(), "shape".to_string()];
+    let vals = vec![
+        Value::String {
+            val: dtype.to_string(),
+            internal_span: span,
+        },
+        Value::List {
+            vals: shape,
+            internal_span: span,
+        },
+    ];
+    Ok((cols, vals))
+}
+
+fn to_meta(dataset: &Dataset, ctx: &Context) -> Result<Value> {
+    let span = ctx.span;
+    let (mut cols, mut vals) = to_shape_meta(dataset, ctx)?;
+    // Nothing for an unlimited dimension, which may still be extended.
+    cols.push("maxshape".to_string());
+    vals.push(Value::List {
+        vals: dataset
+            .space()?
+            .maxdims()
+            .into_iter()
+            .map(|d| match d {
+                Some(d) => Value::Int {
+                    val: d as _,
+                    internal_span: span,
+                },
+                None => Value::Nothing {
+                    internal_span: span,
+                },
+            })
+            .collect(),
+        internal_span: span,
+    });
+    if ctx.options.schema {
+        cols.insert(0, "name".to_string());
+        vals.insert(
+            0,
+            Value::String {
+                val: strip_name(dataset.name()),
+                internal_span: span,
+            },
+        );
+        cols.push("storage_size".to_string());
+        vals.push(Value::Filesize {
+            val: dataset.storage_size() as _,
+            internal_span: span,
+        });
+        // The members of compounds too, for comparing with expected schemas.
+        cols.push("datatype".to_string());
+        vals.push(describe(
+            &Dtype::from_datatype(&dataset.dtype()?.to_native()?)?,
+            span,
+        ));
+    }
+    cols.push("alloc_time".to_string());
+    vals.push(Value::String {
+        val: dataset.dcpl()?.alloc_time_str()?.to_string(),
+        internal_span: span,
+    });
+    if ctx.options.layout {
+        cols.push("layout".to_string());
+        vals.push(Value::String {
+            val: match dataset.layout() {
+                Layout::Compact => "compact",
+                Layout::Contiguous => "contiguous",
+                Layout::Chunked => "chunked",
+                Layout::Virtual => "virtual",
+            }
+            .to_string(),
+            internal_span: span,
+        });
+        cols.push("chunk".to_string());
+        vals.push(match dataset.chunk() {
+            Some(chunk) => Value::List {
+                vals: chunk
+                    .into_iter()
+                    .map(|d| Value::Int {
+                        val: d as _,
+                        internal_span: span,
+                    })
+                    .collect(),
+                internal_span: span,
+            },
+            None => Value::Nothing {
+                internal_span: span,
+            },
+        });
+        // Joined, so that it can be matched like `where filters =~ gzip`.
+        cols.push("filters".to_string());
+        vals.push(Value::String {
+            val: dataset
+                .filters()
+                .iter()
+                .map(filter_name)
+                .collect::<Vec<_>>()
+                .join(", "),
+            internal_span: span,
+        });
+    }
+    if ctx.options.no_attr_data || ctx.options.schema {
+        cols.push("attrs".to_string());
+        vals.push(Value::List {
+            vals: dataset
+                .attr_names()?
+                .into_iter()
+                .map(|name| Value::String {
+                    val: name,
+                    internal_span: span,
+                })
+                .collect(),
+            internal_span: span,
+        });
+    }
+    Ok(Value::Record {
+        val: Record::from_raw_cols_vals(cols, vals, span, span)
+            .unwrap()
+            .into(),
+        internal_span: span,
+    })
+}
+
+pub(crate) fn filter_name(filter: &Filter) -> String {
+    match filter {
+        Filter::Deflate(_) => "gzip".to_string(),
+        Filter::Shuffle => "shuffle".to_string(),
+        Filter::Fletcher32 => "fletcher32".to_string(),
+        Filter::SZip(..) => "szip".to_string(),
+        Filter::NBit => "nbit".to_string(),
+        Filter::ScaleOffset(_) => "scaleoffset".to_string(),
+        // Registered filters that this build can't apply, named if well known.
+        Filter::User(id, _) => match id {
+            307 => "bzip2".to_string(),
+            32000 => "lzf".to_string(),
+            32001 => "blosc".to_string(),
+            32004 => "lz4".to_string(),
+            32008 => "bitshuffle".to_string(),
+            32015 => "zstd".to_string(),
+            id => format!("filter {id}"),
+        },
+    }
+}
+
+/// The attributes of an object as a record, if it has any.
+fn to_attributes(location: &Location, ctx: &Context) -> Result<Option<Value>> {
+    let span = ctx.span;
+    let cols = location.attr_names()?;
+    if cols.is_empty() {
+        return Ok(None);
+    }
+    let vals = cols
+        .iter()
+        .map(|name| {
+            let attr = location.attr(name)?;
+            if ctx.options.no_attr_data {
+                let (cols, vals) = to_shape_meta(&attr, ctx)?;
+                Ok(Value::Record {
+                    val: Record::from_raw_cols_vals(cols, vals, span, span)
+                        .unwrap()
+                        .into(),
+                    internal_span: span,
+                })
+            } else {
+                to_list(&attr, ctx)
+            }
+        })
+        .try_collect()?;
+    Ok(Some(Value::Record {
+        val: Record::from_raw_cols_vals(cols, vals, span, span)
+            .unwrap()
+            .into(),
+        internal_span: span,
+    }))
+}
+
+/// Wraps the data with the file datatype and the shape, which `to hdf5`
+/// writes back as they were.
+fn to_faithful(dataset: &Dataset, data: Value, ctx: &Context) -> Result<Value> {
+    let span = ctx.span;
+    let dtype = Dtype::from_datatype(&dataset.dtype()?)?;
+    let shape = dataset
+        .shape()
+        .into_iter()
+        .map(|d| Value::Int {
+            val: d as _,
+            internal_span: span,
+        })
+        .collect();
+    Ok(Value::Record {
+        val: Record::from_raw_cols_vals(
+            vec![
+                "_hdf5_dtype".to_string(),
+                "_shape".to_string(),
+                "data".to_string(),
+            ],
+            vec![
+                describe(&dtype, span),
+                Value::List {
+                    vals: shape,
+                    internal_span: span,
+                },
+                data,
+            ],
+            span,
+            span,
+        )
+        .unwrap()
+        .into(),
+        internal_span: span,
+    })
+}
+
+/// The units of an integer dataset of times, from its CF-style `units`
+/// attribute, if they can be parsed.
+fn time_units(dataset: &Dataset, ctx: &Context) -> Option<(TimeUnit, DateTime<FixedOffset>)> {
+    let dtype = Dtype::from_datatype(&dataset.dtype().ok()?.to_native().ok()?).ok()?;
+    if !matches!(dtype, Dtype::Integer(_) | Dtype::Unsigned(_)) {
+        return None;
+    }
+    let units = to_list(&dataset.attr("units").ok()?, ctx).ok()?;
+    parse_time_units(units.as_str().ok()?)
+}
+
+/// With `--units`, the data of a dataset of a `units` attribute in the unit.
+/// Times since an epoch are left to `--dates`.
+fn to_units(dataset: &Dataset, data: Value, ctx: &Context) -> Value {
+    let span = ctx.span;
+    let Some(units) = attr_string(dataset, "units", ctx) else {
+        return data;
+    };
+    if units.contains(" since ") {
+        return data;
+    }
+    match parse_unit(&units) {
+        Some(unit) => numbers_to_unit(data, unit),
+        None => Value::Record {
+            val: Record::from_raw_cols_vals(
+                vec!["value".to_string(), "unit".to_string()],
+                vec![
+                    data,
+                    Value::String {
+                        val: units,
+                        internal_span: span,
+                    },
+                ],
+                span,
+                span,
+            )
+            .unwrap()
+            .into(),
+            internal_span: span,
+        },
+    }
+}
+
+/// A string attribute, if the object has one by the name.
+fn attr_string(location: &Location, name: &str, ctx: &Context) -> Option<String> {
+    let val = to_list(&location.attr(name).ok()?, ctx).ok()?;
+    val.into_string().ok()
+}
+
+/// With `--pandas`, the DataFrame of a group of the fixed format of
+/// `DataFrame.to_hdf`, as a table led by the `index`. The columns of a block
+/// are the rows of its values, unless they are `transposed`.
+fn to_pandas(group: &Group, ctx: &Context) -> Result<Option<Value>> {
+    let span = ctx.span;
+    if attr_string(group, "pandas_type", ctx).as_deref() != Some("frame") {
+        return Ok(None);
+    }
+    let list = |name: &str| -> Result<Vec<Value>> {
+        match to_list(&group.dataset(name)?, ctx)? {
+            Value::List { vals, .. } => Ok(vals),
+            _ => Err(format!("{name} of the DataFrame is not an array").into()),
+        }
+    };
+    let text = |val: Value| val.coerce_into_string().map_err(|e| e.to_string());
+    let names = list("axis0")?;
+    let index = list("axis1")?;
+    let nblocks = to_list(&group.attr("nblocks")?, ctx)?
+        .as_int()
+        .map_err(|e| e.to_string())?;
+    let mut columns: Vec<(String, Vec<Value>)> = vec![];
+    for i in 0..nblocks {
+        let items = list(&format!("block{i}_items"))?;
+        let values = group.dataset(&format!("block{i}_values"))?;
+        let transposed = match values
+            .attr("transposed")
+            .and_then(|attr| to_list(&attr, ctx))
+        {
+            Ok(Value::Bool { val, .. }) => val,
+            Ok(Value::Int { val, .. }) => val != 0,
+            Ok(Value::String { val, .. }) => val.eq_ignore_ascii_case("true"),
+            _ => false,
+        };
+        let Value::List { vals: rows, .. } = to_list(&values, ctx)? else {
+            return Err(format!("block{i}_values of the DataFrame is not an array").into());
+        };
+        let rows: Vec<Vec<Value>> = rows
+            .into_iter()
+            .map(|row| match row {
+                Value::List { vals, .. } => vals,
+                val => vec![val],
+            })
+            .collect();
+        let block: Vec<Vec<Value>> = if transposed {
+            (0..items.len())
+                .map(|j| {
+                    rows.iter()
+                        .map(|row| {
+                            row.get(j).cloned().unwrap_or(Value::Nothing {
+                                internal_span: span,
+                            })
+                        })
+                        .collect()
+                })
+                .collect()
+        } else {
+            rows
+        };
+        for (item, column) in items.into_iter().zip(block) {
+            columns.push((text(item)?, column));
+        }
+    }
+    let mut cols = vec!["index".to_string()];
+    // The columns in the order of the frame.
+    let mut order = vec![];
+    for name in names {
+        let name = text(name)?;
+        if let Some(i) = columns.iter().position(|(item, _)| *item == name) {
+            cols.push(name);
+            order.push(i);
+        }
+    }
+    let rows = index
+        .into_iter()
+        .enumerate()
+        .map(|(r, index)| {
+            let mut vals = vec![index];
+            vals.extend(order.iter().map(|&i| {
+                columns[i].1.get(r).cloned().unwrap_or(Value::Nothing {
+                    internal_span: span,
+                })
+            }));
+            Value::Record {
+                val: Record::from_raw_cols_vals(cols.clone(), vals, span, span)
+                    .unwrap()
+                    .into(),
+                internal_span: span,
+            }
+        })
+        .collect();
+    Ok(Some(Value::List {
+        vals: rows,
+        internal_span: span,
+    }))
+}
+
+/// With `--images`, an image of the HDF5 Image spec as `{width, height,
+/// planes, interlace, data}`, and the paths of its palettes if any.
+fn to_image(dataset: &Dataset, data: Value, ctx: &Context) -> Result<Value> {
+    let span = ctx.span;
+    let interlace = attr_string(dataset, "INTERLACE_MODE", ctx);
+    let (height, width, planes) = match (dataset.shape().as_slice(), interlace.as_deref()) {
+        (&[height, width], _) => (height, width, 1),
+        (&[planes, height, width], Some("INTERLACE_PLANE")) => (height, width, planes),
+        (&[height, width, planes], _) => (height, width, planes),
+        (shape, _) => return Err(format!("an image of shape {shape:?}").into()),
+    };
+    let int = |val: usize| Value::Int {
+        val: val as _,
+        internal_span: span,
+    };
+    let mut cols = vec![
+        "width".to_string(),
+        "height".to_string(),
+        "planes".to_string(),
+        "interlace".to_string(),
+    ];
+    let mut vals = vec![
+        int(width),
+        int(height),
+        int(planes),
+        match interlace {
+            Some(val) => Value::String {
+                val,
+                internal_span: span,
+            },
+            None => Value::Nothing {
+                internal_span: span,
+            },
+        },
+    ];
+    if dataset.attr_names()?.iter().any(|name| name == "PALETTE") {
+        cols.push("palette".to_string());
+        vals.push(to_list(&dataset.attr("PALETTE")?, ctx)?);
+    }
+    cols.push("data".to_string());
+    vals.push(data);
+    Ok(Value::Record {
+        val: Record::from_raw_cols_vals(cols, vals, span, span)
+            .unwrap()
+            .into(),
+        internal_span: span,
+    })
+}
+
+/// A table of the HDF5 Table spec with its title and class, and the columns
+/// of its rows named by the fields of the spec rather than the members of the
+/// compound, which are often the same.
+fn to_h5tb(dataset: &Dataset, data: Value, ctx: &Context) -> Result<Value> {
+    let span = ctx.span;
+    let fields: Vec<String> = (0..)
+        .map_while(|i| attr_string(dataset, &format!("FIELD_{i}_NAME"), ctx))
+        .collect();
+    // Fields named the same are left as the members.
+    let unique = fields
+        .iter()
+        .enumerate()
+        .all(|(i, field)| !fields[..i].contains(field));
+    let rename = |record: Record| -> Value {
+        let (cols, vals) = record
+            .into_iter()
+            .enumerate()
+            .map(|(i, (col, val))| match fields.get(i) {
+                Some(field) if unique => (field.clone(), val),
+                _ => (col, val),
+            })
+            .unzip();
+        Value::Record {
+            val: Record::from_raw_cols_vals(cols, vals, span, span)
+                .unwrap()
+                .into(),
+            internal_span: span,
+        }
+    };
+    let data = match data {
+        Value::List { vals, .. } => Value::List {
+            vals: vals
+                .into_iter()
+                .map(|row| match row {
+                    Value::Record { val, .. } => rename(val.into_owned()),
+                    row => row,
+                })
+                .collect(),
+            internal_span: span,
+        },
+        // Transposed by `--transpose-compound`.
+        Value::Record { val, .. } => rename(val.into_owned()),
+        data => data,
+    };
+    let string = |name: &str| match attr_string(dataset, name, ctx) {
+        Some(val) => Value::String {
+            val,
+            internal_span: span,
+        },
+        None => Value::Nothing {
+            internal_span: span,
+        },
+    };
+    let cols = vec![
+        "title".to_string(),
+        "class".to_string(),
+        "fields".to_string(),
+        "data".to_string(),
+    ];
+    let vals = vec![
+        string("TITLE"),
+        string("CLASS"),
+        Value::List {
+            vals: fields
+                .iter()
+                .map(|field| Value::String {
+                    val: field.clone(),
+                    internal_span: span,
+                })
+                .collect(),
+            internal_span: span,
+        },
+        data,
+    ];
+    Ok(Value::Record {
+        val: Record::from_raw_cols_vals(cols, vals, span, span)
+            .unwrap()
+            .into(),
+        internal_span: span,
+    })
+}
+
+/// A NetCDF-4 variable as a table with a row per element, of its coordinates
+/// and its value, when its dimensions have scales. The coordinates of a
+/// dimension without a coordinate variable are the indices along it.
+fn to_netcdf_table(dataset: &Dataset, data: Value, ctx: &Context) -> Result<Value> {
+    let span = ctx.span;
+    if dataset.ndim() == 0
+        || !dataset
+            .attr_names()?
+            .iter()
+            .any(|name| name == "DIMENSION_LIST")
+    {
+        return Ok(data);
+    }
+    let dims = to_list(&dataset.attr("DIMENSION_LIST")?, ctx)?;
+    let mut names: Vec<String> = vec![];
+    let mut coords = vec![];
+    let basename = |path: String| path.rsplit('/').next().unwrap_or_default().to_string();
+    for (i, scales) in dims
+        .as_list()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .enumerate()
+    {
+        let scale = scales
+            .as_list()
+            .ok()
+            .and_then(|scales| scales.first())
+            .and_then(|path| path.as_str().ok())
+            .map(|path| ctx.file.dataset(path))
+            .transpose()?;
+        let name = scale
+            .as_ref()
+            .map_or(format!("dim{i}"), |scale| basename(scale.name()));
+        // NetCDF names dimensions without a variable so.
+        let values = match &scale {
+            Some(scale)
+                if !attr_string(scale, "NAME", ctx).is_some_and(|name| {
+                    name.starts_with("This is a netCDF dimension but not a netCDF variable")
+                }) =>
+            {
+                to_list(scale, ctx)?.into_list().ok()
+            }
+            _ => None,
+        };
+        names.push(if names.contains(&name) {
+            format!("{name}_{i}")
+        } else {
+            name
+        });
+        coords.push(values);
+    }
+    names.push(basename(dataset.name()));
+    let mut rows = vec![];
+    netcdf_rows(data, &names, &coords, &mut vec![], &mut rows, span);
+    Ok(Value::List {
+        vals: rows,
+        internal_span: span,
+    })
+}
+
+/// The rows of the elements of nested lists as deep as the coordinates.
+fn netcdf_rows(
+    data: Value,
+    names: &[String],
+    coords: &[Option<Vec<Value>>],
+    index: &mut Vec<usize>,
+    rows: &mut Vec<Value>,
+    span: Span,
+) {
+    match data {
+        Value::List { vals, .. } if index.len() < coords.len() => {
+            for (i, val) in vals.into_iter().enumerate() {
+                index.push(i);
+                netcdf_rows(val, names, coords, index, rows, span);
+                index.pop();
+            }
+        }
+        value => {
+            let mut vals: Vec<Value> = index
+                .iter()
+                .zip(coords)
+                .map(|(&i, coords)| {
+                    coords
+                        .as_ref()
+                        .and_then(|coords| coords.get(i))
+                        .cloned()
+                        .unwrap_or(Value::Int {
+                            val: i as _,
+                            internal_span: span,
+                        })
+                })
+                .collect();
+            vals.push(value);
+            rows.push(Value::Record {
+                val: Record::from_raw_cols_vals(names.to_vec(), vals, span, span)
+                    .unwrap()
+                    .into(),
+                internal_span: span,
+            });
+        }
+    }
+}
+
+/// Names the object in the error of reading it.
+fn failed_to_read<T>(path: &str, res: Result<T>) -> Result<T> {
+    res.map_err(|e| format!("failed to read {path}: {e}").into())
+}
+
+fn to_dataset(dataset: &Dataset, ctx: &Context) -> Result<Value> {
+    if let Some((images, id)) = ctx.lazy {
+        images.retain(id);
+        let handle = Hdf5Dataset {
+            id,
+            path: dataset.name(),
+        };
+        return Ok(Value::custom(Box::new(handle), ctx.span));
+    }
+    let reopened;
+    let dataset = match &ctx.options.virtual_prefix {
+        Some(prefix) if dataset.layout() == Layout::Virtual => {
+            reopened = dataset.with_virtual_prefix(prefix)?;
+            &reopened
+        }
+        _ => dataset,
+    };
+    let start = Instant::now();
+    let res = failed_to_read(&dataset.name(), over_memory(dataset, ctx));
+    if let Some(progress) = &ctx.progress {
+        progress.report(dataset, start.elapsed());
+    }
+    res
+}
+
+/// Reads a dataset over `--max-memory` by `--over-memory`. A dataset to
+/// stream that wasn't selected alone as a 1-D one is an error.
+fn over_memory(dataset: &Dataset, ctx: &Context) -> Result<Value> {
+    let span = ctx.span;
+    let (size, max) = match ctx.options.max_memory {
+        Some(max) if !ctx.options.meta => (estimated_size(dataset)?, max),
+        _ => return read_dataset(dataset, ctx),
+    };
+    if size <= max {
+        return read_dataset(dataset, ctx);
+    }
+    let record = |cols: Vec<&str>, vals| Value::Record {
+        val: Record::from_raw_cols_vals(
+            cols.into_iter().map(String::from).collect(),
+            vals,
+            span,
+            span,
+        )
+        .unwrap()
+        .into(),
+        internal_span: span,
+    };
+    match ctx.options.over_memory {
+        OverMemory::Error => {
+            Err(format!("about {size} bytes in memory, over --max-memory of {max} bytes").into())
+        }
+        OverMemory::Skip => Ok(record(
+            vec!["skipped"],
+            vec![Value::String {
+                val: format!("about {size} bytes in memory, over --max-memory"),
+                internal_span: span,
+            }],
+        )),
+        OverMemory::Stream => Err(format!(
+            "can't stream {}: --over-memory stream needs a single 1-D dataset selected, and no options changing its values",
+            dataset.name()
+        )
+        .into()),
+        OverMemory::Truncate => {
+            let (mem_dtype, dtype) = match dtypes(dataset, ctx)? {
+                Ok(dtypes) => dtypes,
+                Err(val) => return Ok(val),
+            };
+            if let Some(val) = to_empty(dataset, &dtype, ctx)? {
+                return Ok(val);
+            }
+            if dataset.ndim() == 0 {
+                return read_dataset(dataset, ctx);
+            }
+            // As many of the first dimension as fit, without overflow in between.
+            let len = dataset.shape()[0];
+            let selection = Selection {
+                head: Some((len as u128 * max as u128 / size as u128) as usize),
+                ..Default::default()
+            };
+            let (data, shape) = dataset.read_raw_bytes_selection(&mem_dtype, &selection)?;
+            Ok(record(
+                vec!["value", "truncated"],
+                vec![
+                    decode(&data, &dtype, &shape, None, ctx)?,
+                    Value::Bool {
+                        val: true,
+                        internal_span: span,
+                    },
+                ],
+            ))
+        }
+    }
+}
+
+fn read_dataset(dataset: &Dataset, ctx: &Context) -> Result<Value> {
+    let span = ctx.span;
+    if ctx.options.swmr {
+        dataset.refresh()?;
+    }
+    let val = if ctx.options.meta {
+        to_meta(dataset, ctx)?
+    } else {
+        match &ctx.options.int_as_time {
+            Some((unit, path)) if *path == dataset.name() => {
+                ints_to_dates(to_dataset_list(dataset, ctx)?, &|val| unit.to_date(val))
+            }
+            _ => match ctx.options.dates.then(|| time_units(dataset, ctx)) {
+                Some(Some((unit, epoch))) => {
+                    ints_to_dates(to_dataset_list(dataset, ctx)?, &|val| {
+                        unit.to_date_since(epoch, val)
+                    })
+                }
+                _ => to_dataset_list(dataset, ctx)?,
+            },
+        }
+    };
+    let val = if ctx.options.units && !ctx.options.meta {
+        to_units(dataset, val, ctx)
+    } else {
+        val
+    };
+    let val = if ctx.options.faithful && !ctx.options.meta {
+        to_faithful(dataset, val, ctx)?
+    } else {
+        val
+    };
+    let val = if ctx.options.images
+        && !ctx.options.meta
+        && attr_string(dataset, "CLASS", ctx).as_deref() == Some("IMAGE")
+    {
+        to_image(dataset, val, ctx)?
+    } else {
+        val
+    };
+    let val = if ctx.options.h5tb
+        && !ctx.options.meta
+        && attr_string(dataset, "CLASS", ctx).as_deref() == Some("TABLE")
+    {
+        to_h5tb(dataset, val, ctx)?
+    } else {
+        val
+    };
+    // A selection leaves the coordinates behind.
+    let val = if ctx.options.netcdf
+        && !ctx.options.meta
+        && ctx.options.selection.is_empty()
+        && ctx.options.select.is_none()
+    {
+        to_netcdf_table(dataset, val, ctx)?
+    } else {
+        val
+    };
+    let mut cols = vec![];
+    let mut vals = vec![];
+    let metadata = ctx.options.metadata && !ctx.options.meta;
+    if (ctx.options.with_dtype || metadata) && !ctx.options.meta {
+        cols.push("dtype".to_string());
+        vals.push(Value::String {
+            val: render_dtype(&Dtype::from_datatype(&dataset.dtype()?)?),
+            internal_span: span,
+        });
+    }
+    // Whether `--head` left out elements, so that a preview doesn't look whole.
+    if let (Some(head), false) = (ctx.options.selection.head, ctx.options.meta) {
+        let len = dataset.shape().first().copied().unwrap_or(0);
+        let skip = ctx.options.selection.skip.unwrap_or(0);
+        cols.push("truncated".to_string());
+        vals.push(Value::Bool {
+            val: len.saturating_sub(skip) > head,
+            internal_span: span,
+        });
+    }
+    if (ctx.options.with_shape || metadata) && !ctx.options.meta {
+        cols.push("shape".to_string());
+        vals.push(Value::List {
+            vals: dataset
+                .shape()
+                .into_iter()
+                .map(|d| Value::Int {
+                    val: d as _,
+                    internal_span: span,
+                })
+                .collect(),
+            internal_span: span,
+        });
+    }
+    if metadata {
+        cols.push("chunks".to_string());
+        vals.push(match dataset.chunk() {
+            Some(chunk) => Value::List {
+                vals: chunk
+                    .into_iter()
+                    .map(|d| Value::Int {
+                        val: d as _,
+                        internal_span: span,
+                    })
+                    .collect(),
+                internal_span: span,
+            },
+            None => Value::Nothing {
+                internal_span: span,
+            },
+        });
+        cols.push("filters".to_string());
+        vals.push(Value::List {
+            vals: dataset
+                .filters()
+                .iter()
+                .map(|filter| Value::String {
+                    val: filter_name(filter),
+                    internal_span: span,
+                })
+                .collect(),
+            internal_span: span,
+        });
+    }
+    if ctx.options.attributes {
+        if let Some(attrs) = to_attributes(dataset, ctx)? {
+            cols.push("@attributes".to_string());
+            vals.push(attrs);
+        }
+    }
+    if ctx.options.dim_scales {
+        // The scales of each dimension, and the dimensions a scale is of.
+        for (attr, col) in [
+            ("DIMENSION_LIST", "@dimensions"),
+            ("REFERENCE_LIST", "@referenced_by"),
+        ] {
+            if dataset.attr_names()?.iter().any(|name| name == attr) {
+                cols.push(col.to_string());
+                vals.push(to_list(&dataset.attr(attr)?, ctx)?);
+            }
+        }
+    }
+    if cols.is_empty() {
+        return Ok(val);
+    }
+    cols.insert(0, "value".to_string());
+    vals.insert(0, val);
+    Ok(Value::Record {
+        val: Record::from_raw_cols_vals(cols, vals, span, span)
+            .unwrap()
             .into(),
         internal_span: span,
     })
 }
 
-fn strip_name(name: String) -> String {
-    if let Some(s) = name.strip_prefix('/') {
-        s.to_string()
+/// The objects linked from a group, by the paths of the links.
+struct Members {
+    datasets: Vec<Dataset>,
+    groups: Vec<Group>,
+    /// Placeholders of links that aren't followed or have no target, and
+    /// descriptions of committed datatypes.
+    links: Vec<(String, Value)>,
+}
+
+fn members(group: &Group, ctx: &Context) -> Result<Members> {
+    let span = ctx.span;
+    // In the order of names, unless asked for the order of creation and the
+    // group tracks it.
+    let mut links = group.iter_visit_default(vec![], |_, name, info, links| {
+        links.push((name.to_string(), info.link_type, info.creation_order));
+        true
+    })?;
+    if ctx.options.creation_order && links.iter().all(|(_, _, order)| order.is_some()) {
+        links.sort_by_key(|(_, _, order)| *order);
+    }
+    let mut members = Members {
+        datasets: vec![],
+        groups: vec![],
+        links: vec![],
+    };
+    for (name, link_type, _) in links {
+        let path = format!("{}/{name}", group.name().trim_end_matches('/'));
+        if link_type == LinkType::Soft && ctx.options.no_follow_links {
+            members.links.push((
+                path,
+                Value::Record {
+                    val: Record::from_raw_cols_vals(
+                        vec!["soft_link".to_string()],
+                        vec![Value::String {
+                            val: group.soft_link(&name)?,
+                            internal_span: span,
+                        }],
+                        span,
+                        span,
+                    )
+                    .unwrap()
+                    .into(),
+                    internal_span: span,
+                },
+            ));
+            continue;
+        }
+        if link_type == LinkType::External && !ctx.options.follow_external {
+            let (file_name, obj_path) = group.external_link(&name)?;
+            members.links.push((
+                path,
+                Value::Record {
+                    val: Record::from_raw_cols_vals(
+                        vec!["external_file".to_string(), "external_path".to_string()],
+                        vec![
+                            Value::String {
+                                val: file_name,
+                                internal_span: span,
+                            },
+                            Value::String {
+                                val: obj_path,
+                                internal_span: span,
+                            },
+                        ],
+                        span,
+                        span,
+                    )
+                    .unwrap()
+                    .into(),
+                    internal_span: span,
+                },
+            ));
+            continue;
+        }
+        if let (LinkType::External, Some(prefix)) = (link_type, &ctx.options.external_prefix) {
+            match group.open_external(&name, prefix) {
+                Ok(object) => match object.loc_type()? {
+                    LocationType::Dataset => members.datasets.push(object.as_dataset()?),
+                    LocationType::Group => members.groups.push(object.as_group()?),
+                    _ => {}
+                },
+                // An external file that can't be opened.
+                Err(_) => members.links.push((
+                    path,
+                    Value::Nothing {
+                        internal_span: span,
+                    },
+                )),
+            }
+            continue;
+        }
+        // Opened by the link, so that the objects are named after it.
+        match group.loc_type_by_name(&name) {
+            Ok(LocationType::Dataset) => members.datasets.push(group.dataset(&name)?),
+            Ok(LocationType::Group) => members.groups.push(group.group(&name)?),
+            Ok(LocationType::NamedDatatype) => {
+                let dtype = Dtype::from_datatype(&group.committed_type(&name)?)?;
+                members.links.push((
+                    path,
+                    Value::Record {
+                        val: Record::from_raw_cols_vals(
+                            vec!["committed_dtype".to_string()],
+                            vec![Value::String {
+                                val: render_dtype(&dtype),
+                                internal_span: span,
+                            }],
+                            span,
+                            span,
+                        )
+                        .unwrap()
+                        .into(),
+                        internal_span: span,
+                    },
+                ));
+            }
+            Ok(_) => {}
+            // A dangling soft link, or an external file that can't be opened.
+            Err(_) if link_type != LinkType::Hard => members.links.push((
+                path,
+                Value::Nothing {
+                    internal_span: span,
+                },
+            )),
+            Err(e) => return Err(e),
+        }
+    }
+    if let Some(glob) = &ctx.options.glob {
+        members.datasets.retain(|ds| ctx.matches(glob, &ds.name()));
+        members.links.retain(|(path, _)| ctx.matches(glob, path));
+    }
+    // Excluded groups aren't opened further.
+    if let Some(exclude) = &ctx.options.exclude {
+        members
+            .datasets
+            .retain(|ds| !ctx.matches(exclude, &ds.name()));
+        members.groups.retain(|g| !ctx.matches(exclude, &g.name()));
+        members
+            .links
+            .retain(|(path, _)| !ctx.matches(exclude, path));
+    }
+    members.datasets = order_datasets(members.datasets, ctx.options)?;
+    Ok(members)
+}
+
+fn to_record(group: &Group, depth: usize, ctx: &Context) -> Result<Value> {
+    let info = group.loc_info()?;
+    ctx.ancestors
+        .lock()
+        .unwrap()
+        .push((info.fileno, info.token));
+    let res = read_record(group, depth, ctx);
+    ctx.ancestors.lock().unwrap().pop();
+    res
+}
+
+fn read_record(group: &Group, depth: usize, ctx: &Context) -> Result<Value> {
+    let span = ctx.span;
+    if ctx.options.pandas {
+        if let Some(frame) = failed_to_read(&group.name(), to_pandas(group, ctx))? {
+            return Ok(frame);
+        }
+    }
+    let max_children = ctx.options.max_children.unwrap_or(usize::MAX);
+    let Members {
+        datasets,
+        groups,
+        links,
+    } = failed_to_read(&group.name(), members(group, ctx))?;
+    let truncated = (datasets.len() + groups.len() + links.len()).saturating_sub(max_children);
+    let mut cols = vec![];
+    let mut vals = vec![];
+    // The sanitized keys of `--name-map`, with the paths they are of.
+    let mut renamed = vec![];
+    let mut key = |path: &str| {
+        let key = ctx.key(path);
+        if ctx.options.name_map && ctx.options.sanitize_names.is_some() {
+            let original = strip_name(path.to_string());
+            if key != original {
+                renamed.push((key.clone(), original));
+            }
+        }
+        key
+    };
+    for ds in datasets.iter().take(max_children) {
+        ctx.check_interrupt()?;
+        cols.push(key(&ds.name()));
+        vals.push(ctx.lenient(to_dataset(ds, ctx))?);
+    }
+    for g in groups.iter().take(max_children - cols.len()) {
+        ctx.check_interrupt()?;
+        let val = match ctx.options.max_depth {
+            Some(max_depth) if depth >= max_depth => Value::String {
+                val: format!("<truncated at depth {max_depth}>"),
+                internal_span: span,
+            },
+            _ if is_ancestor(g, ctx)? => Value::String {
+                val: format!("<link cycle at {}>", g.name()),
+                internal_span: span,
+            },
+            _ => ctx.lenient(to_record(g, depth + 1, ctx))?,
+        };
+        match val {
+            // Nothing in it matches the glob.
+            Value::Record { val, .. }
+                if ctx.options.glob.is_some() && val.columns().all(|col| ctx.is_special(col)) => {}
+            // The keys of a flattened subgroup are already full paths.
+            Value::Record { val, .. } if ctx.options.flatten_paths && !is_error(&val) => {
+                for (col, val) in val.into_owned() {
+                    cols.push(col);
+                    vals.push(val);
+                }
+            }
+            val => {
+                cols.push(key(&g.name()));
+                vals.push(val);
+            }
+        }
+    }
+    for (path, val) in links
+        .into_iter()
+        .take(max_children.saturating_sub(cols.len()))
+    {
+        cols.push(key(&path));
+        vals.push(val);
+    }
+    if !renamed.is_empty() {
+        let (names, originals) = renamed
+            .into_iter()
+            .map(|(name, original)| {
+                (
+                    name,
+                    Value::String {
+                        val: original,
+                        internal_span: span,
+                    },
+                )
+            })
+            .unzip();
+        cols.push(special_key(group, depth, "@names", ctx));
+        vals.push(Value::Record {
+            val: Record::from_raw_cols_vals(names, originals, span, span)
+                .unwrap()
+                .into(),
+            internal_span: span,
+        });
+    }
+    if truncated > 0 {
+        cols.push(special_key(group, depth, "@truncated", ctx));
+        vals.push(Value::Int {
+            val: truncated as _,
+            internal_span: span,
+        });
+    }
+    if ctx.options.attributes {
+        if let Some(attrs) = failed_to_read(&group.name(), to_attributes(group, ctx))? {
+            cols.push(special_key(group, depth, "@attributes", ctx));
+            vals.push(attrs);
+        }
+    }
+    let (cols, vals) = failed_to_read(
+        &group.name(),
+        dedup_keys(cols, vals, ctx.options.on_duplicate),
+    )?;
+    if ctx.options.tabular {
+        if let Some(table) = to_table(&cols, &vals, ctx) {
+            return Ok(table);
+        }
+    }
+    if ctx.options.group_as_table && !ctx.options.flatten_paths {
+        if let Some(table) = to_group_table(&cols, &vals, ctx) {
+            return Ok(table);
+        }
+    }
+    if ctx.options.flatten_paths && depth == 0 {
+        return Ok(to_path_table(cols, vals, span));
+    }
+    Ok(Value::Record {
+        val: Record::from_raw_cols_vals(cols, vals, span, span)
+            .unwrap()
+            .into(),
+        internal_span: span,
+    })
+}
+
+/// The flattened entries as a table of their `path` and `value`, for
+/// `--flatten-paths`.
+fn to_path_table(cols: Vec<String>, vals: Vec<Value>, span: Span) -> Value {
+    let rows = cols
+        .into_iter()
+        .zip(vals)
+        .map(|(path, value)| Value::Record {
+            val: Record::from_raw_cols_vals(
+                vec!["path".to_string(), "value".to_string()],
+                vec![
+                    Value::String {
+                        val: path,
+                        internal_span: span,
+                    },
+                    value,
+                ],
+                span,
+                span,
+            )
+            .unwrap()
+            .into(),
+            internal_span: span,
+        })
+        .collect();
+    Value::List {
+        vals: rows,
+        internal_span: span,
+    }
+}
+
+fn is_ancestor(group: &Group, ctx: &Context) -> Result<bool> {
+    let info = group.loc_info()?;
+    Ok(ctx
+        .ancestors
+        .lock()
+        .unwrap()
+        .contains(&(info.fileno, info.token)))
+}
+
+fn is_error(record: &Record) -> bool {
+    record.len() == 1 && record.contains("error")
+}
+
+/// The column name of an entry about the group itself, which is qualified
+/// by the path of the group when flattening.
+fn special_key(group: &Group, depth: usize, name: &str, ctx: &Context) -> String {
+    if ctx.options.flatten_paths && depth > 0 {
+        ctx.key(&format!("{}/{name}", group.name()))
+    } else {
+        name.to_string()
+    }
+}
+
+/// Turns a group of datasets into a table with a column per dataset. Columns
+/// of differing lengths are padded with nothing only if asked to.
+fn to_table(cols: &[String], vals: &[Value], ctx: &Context) -> Option<Value> {
+    let span = ctx.span;
+    let columns: Vec<&Vec<Value>> = vals
+        .iter()
+        .map(|v| match v {
+            Value::List { vals, .. } => Some(vals),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    let len = columns.iter().map(|c| c.len()).max()?;
+    if !ctx.options.pad_ragged && columns.iter().any(|c| c.len() != len) {
+        return None;
+    }
+    let rows = (0..len)
+        .map(|i| {
+            let row = columns
+                .iter()
+                .map(|c| {
+                    c.get(i).cloned().unwrap_or(Value::Nothing {
+                        internal_span: span,
+                    })
+                })
+                .collect();
+            Value::Record {
+                val: Record::from_raw_cols_vals(cols.to_vec(), row, span, span)
+                    .unwrap()
+                    .into(),
+                internal_span: span,
+            }
+        })
+        .collect();
+    Some(Value::List {
+        vals: rows,
+        internal_span: span,
+    })
+}
+
+/// Turns a group of subgroups with the same members into a table with a row
+/// per subgroup, led by its name. The columns are the names of the members
+/// relative to each subgroup.
+fn to_group_table(cols: &[String], vals: &[Value], ctx: &Context) -> Option<Value> {
+    let span = ctx.span;
+    let mut rows = vec![];
+    let mut columns: Option<Vec<String>> = None;
+    for (col, val) in cols.iter().zip(vals) {
+        let Value::Record { val, .. } = val else {
+            return None;
+        };
+        if ctx.is_special(col) || is_error(val) {
+            return None;
+        }
+        let prefix = format!("{col}/");
+        let names: Vec<String> = val
+            .columns()
+            .map(|name| name.strip_prefix(&prefix).unwrap_or(name).to_string())
+            .collect();
+        if names.iter().any(|name| name == "name") {
+            return None;
+        }
+        match &columns {
+            Some(columns) if *columns != names => return None,
+            Some(_) => {}
+            None => columns = Some(names.clone()),
+        }
+        let mut row_cols = vec!["name".to_string()];
+        row_cols.extend(names);
+        let mut row_vals = vec![Value::String {
+            val: col.rsplit('/').next().unwrap_or_default().to_string(),
+            internal_span: span,
+        }];
+        row_vals.extend(val.values().cloned());
+        rows.push(Value::Record {
+            val: Record::from_raw_cols_vals(row_cols, row_vals, span, span)
+                .unwrap()
+                .into(),
+            internal_span: span,
+        });
+    }
+    columns?;
+    Some(Value::List {
+        vals: rows,
+        internal_span: span,
+    })
+}
+
+fn strip_name(name: String) -> String {
+    if let Some(s) = name.strip_prefix('/') {
+        s.to_string()
+    } else {
+        name
+    }
+}
+
+/// The number of elements read at a time by a stream.
+const STREAM_CHUNK: usize = 65536;
+
+/// Whether the selected object is a 1-D dataset over `--stream-threshold`
+/// elements, read as it's simply decoded.
+fn streamable(file: &File, selector: &str, options: &Options) -> Result<bool> {
+    let over_memory = matches!(options.over_memory, OverMemory::Stream);
+    if options.stream_threshold.is_none() && !(over_memory && options.max_memory.is_some()) {
+        return Ok(false);
+    }
+    let plain = !(options.meta
+        || options.checksum
+        || options.faithful
+        || options.with_dtype
+        || options.with_shape
+        || options.metadata
+        || options.fill_as_null
+        || options.nan_as_null
+        || options.images
+        || options.h5tb
+        || options.raw
+        || options.partial
+        || options.dates
+        || options.units
+        || options.transpose_compound
+        || options.netcdf
+        || options.lazy
+        || !options.selection.is_empty()
+        || options.select.is_some()
+        || options.int_as_time.is_some()
+        || options.virtual_prefix.is_some());
+    let path = selector.trim_matches('/');
+    if !plain || path.is_empty() || resolve(file, path)? != LocationType::Dataset {
+        return Ok(false);
+    }
+    let dataset = file.dataset(path)?;
+    let over_threshold = options
+        .stream_threshold
+        .is_some_and(|threshold| dataset.size() > threshold);
+    let over_budget = over_memory
+        && options
+            .max_memory
+            .is_some_and(|max| estimated_size(&dataset).is_ok_and(|size| size > max));
+    Ok(dataset.ndim() == 1 && (over_threshold || over_budget))
+}
+
+/// Streams the elements of a 1-D dataset, reading a chunk at a time.
+fn to_stream(
+    file: FileImage<'static>,
+    selector: &str,
+    options: &Options,
+    span: Span,
+    signals: Signals,
+) -> Result<PipelineData> {
+    let dataset = file.dataset(selector.trim_matches('/'))?;
+    let options = options.clone();
+    let ctx = Context {
+        file: &file,
+        options: &options,
+        span,
+        ancestors: Default::default(),
+        progress: None,
+        lazy: None,
+    };
+    let mem_dtype = ctx.mem_dtype(&dataset)?;
+    let dtype = Dtype::from_datatype(&mem_dtype)?;
+    let mut start = 0;
+    let mut failed = false;
+    let chunks = std::iter::from_fn(move || {
+        let len = dataset.size();
+        if failed || start >= len {
+            return None;
+        }
+        let ctx = Context {
+            file: &file,
+            options: &options,
+            span,
+            ancestors: Default::default(),
+            progress: None,
+            lazy: None,
+        };
+        let count = STREAM_CHUNK.min(len - start);
+        let vals = dataset
+            .read_raw_bytes_range(&mem_dtype, start, count)
+            .and_then(|data| {
+                data.chunks(dtype.size())
+                    .map(|slice| to_value(slice, &dtype, &ctx))
+                    .try_collect::<Vec<_>>()
+            })
+            .unwrap_or_else(|e| {
+                // Ends the stream with the error.
+                failed = true;
+                vec![Value::Error {
+                    error: Box::new(ShellError::GenericError {
+                        error: e.to_string(),
+                        msg: format!("while reading {}", dataset.name()),
+                        span: Some(span),
+                        help: None,
+                        inner: vec![],
+                    }),
+                    internal_span: span,
+                }]
+            });
+        start += count;
+        Some(vals)
+    });
+    Ok(PipelineData::ListStream(
+        ListStream::new(chunks.flatten(), span, signals),
+        None,
+    ))
+}
+
+/// Opens the file by HDF5 itself, so that only the parts read are loaded, or
+/// maps it with `--mmap`.
+fn open_path(path: &Path, options: &Options) -> Result<FileImage<'static>> {
+    if options.swmr {
+        if options.mmap {
+            return Err("--swmr and --mmap are mutually exclusive".into());
+        }
+        return Ok(open_swmr(path, &options.file_access()?)?.into());
+    }
+    if options.mmap && (options.vfd.is_some() || options.alignment.is_some()) {
+        return Err("--vfd and --alignment don't apply to --mmap".into());
+    }
+    if !options.mmap {
+        let file = File::with_options()
+            .set_access_plist(&options.file_access()?)?
+            .open(path)?;
+        return Ok(file.into());
+    }
+    #[cfg(unix)]
+    {
+        let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+        FileImage::map(&file)
+    }
+    #[cfg(not(unix))]
+    Err("--mmap is only supported on unix".into())
+}
+
+fn from_hdf5_bytes(
+    bytes: &[u8],
+    selector: Option<&str>,
+    options: &Options,
+    span: Span,
+) -> Result<Value> {
+    let file = FileImage::new(options.image(bytes)?)?;
+    from_hdf5_file(&file, selector, options, span)
+}
+
+/// Finds the type of the object at the path, naming the first segment
+/// that doesn't exist.
+fn resolve(file: &File, path: &str) -> Result<LocationType> {
+    if path.is_empty() {
+        return Ok(LocationType::Group);
+    }
+    let mut end = 0;
+    for segment in path.split('/') {
+        end += segment.len();
+        if !file.link_exists(&path[..end]) {
+            let parent = path[..end - segment.len()].trim_end_matches('/');
+            return Err(format!("{segment} not found in /{parent}").into());
+        }
+        end += 1;
+    }
+    file.loc_type_by_name(path)
+}
+
+fn from_hdf5_file(
+    file: &File,
+    selector: Option<&str>,
+    options: &Options,
+    span: Span,
+) -> Result<Value> {
+    from_hdf5_file_with(file, selector, options, None, span)
+}
+
+/// Reads the file, with the datasets as handles of the images if `lazy`.
+fn from_hdf5_file_with(
+    file: &File,
+    selector: Option<&str>,
+    options: &Options,
+    lazy: Option<(&Images, u64)>,
+    span: Span,
+) -> Result<Value> {
+    let progress = |total| options.progress.then(|| Progress::new(total));
+    let mut ctx = Context {
+        file,
+        options,
+        span,
+        ancestors: Default::default(),
+        progress: progress(Some(1)),
+        lazy,
+    };
+    let group = match selector.map(|s| s.trim_matches('/')) {
+        None | Some("") => file.as_group()?,
+        Some(path) => match resolve(file, path)? {
+            LocationType::Group => file.group(path)?,
+            LocationType::Dataset if options.checksum => {
+                return to_checksum(&file.dataset(path)?, &ctx)
+            }
+            LocationType::Dataset => return to_dataset(&file.dataset(path)?, &ctx),
+            _ => return Err(format!("{path} is neither a group nor a dataset").into()),
+        },
+    };
+    if options.progress {
+        ctx.progress = progress(count_datasets(&group, &mut vec![]).ok());
+    }
+    let value = if options.checksum {
+        let mut rows = vec![];
+        to_checksums(&group, &ctx, &mut rows)?;
+        Value::List {
+            vals: rows,
+            internal_span: span,
+        }
     } else {
-        name
+        to_record(&group, 0, &ctx)?
+    };
+    Ok(match &options.root_key {
+        Some(key) => Value::Record {
+            val: Record::from_raw_cols_vals(vec![key.clone()], vec![value], span, span)
+                .unwrap()
+                .into(),
+            internal_span: span,
+        },
+        None => value,
+    })
+}
+
+/// Reads the group or dataset at the path only, for `hdf5 get`.
+pub fn read_path(bytes: &[u8], path: &str, max_depth: Option<usize>, span: Span) -> Result<Value> {
+    read_file_path(&*FileImage::new(bytes)?, path, max_depth, span)
+}
+
+/// Reads the group or dataset at the path of an open file only.
+pub(crate) fn read_file_path(
+    file: &File,
+    path: &str,
+    max_depth: Option<usize>,
+    span: Span,
+) -> Result<Value> {
+    let options = Options {
+        max_depth,
+        flatten_separator: "/".to_string(),
+        attributes: true,
+        ..Default::default()
+    };
+    let ctx = Context {
+        file,
+        options: &options,
+        span,
+        ancestors: Default::default(),
+        progress: None,
+        lazy: None,
+    };
+    let path = path.trim_matches('/');
+    match resolve(file, path)? {
+        LocationType::Group if path.is_empty() => to_record(file, 0, &ctx),
+        LocationType::Group => to_record(&file.group(path)?, 0, &ctx),
+        LocationType::Dataset => to_dataset(&file.dataset(path)?, &ctx),
+        _ => Err(format!("{path} is neither a group nor a dataset").into()),
     }
 }
 
-fn from_hdf5_bytes(bytes: &[u8], span: Span) -> Result<Value> {
-    let file = FileImage::new(bytes)?;
-    to_record(&file, span)
+/// Reads the groups of the image with `--lazy`, keeping a copy of it open in
+/// `images` for the datasets, which are read with the same options.
+fn to_lazy(
+    images: &Images,
+    bytes: &[u8],
+    selector: Option<&str>,
+    options: &Options,
+    span: Span,
+) -> Result<Value> {
+    let file = open_image_copy(options.image(bytes)?)?.into();
+    let read_options = Options {
+        lazy: false,
+        progress: false,
+        ..options.clone()
+    };
+    let read = move |file: &File, path: &str, span| {
+        let ctx = Context {
+            file,
+            options: &read_options,
+            span,
+            ancestors: Default::default(),
+            progress: None,
+            lazy: None,
+        };
+        to_dataset(&file.dataset(path)?, &ctx)
+    };
+    let (id, file) = images.insert(file, Some(Arc::new(read)));
+    let res = from_hdf5_file_with(&file, selector, options, Some((images, id)), span);
+    // The file stays open for the datasets handed out, if any.
+    images.release(id);
+    res
+}
+
+/// Reads the data of a dataset or an attribute as `from hdf5` does by
+/// default, for commands that show it in another form.
+pub fn read_data<C>(file: &File, container: &C, span: Span) -> Result<Value>
+where
+    C: Deref<Target = Container> + ReadRawBytes,
+{
+    let options = Options {
+        flatten_separator: "/".to_string(),
+        ..Default::default()
+    };
+    let ctx = Context {
+        file,
+        options: &options,
+        span,
+        ancestors: Default::default(),
+        progress: None,
+        lazy: None,
+    };
+    to_list(container, &ctx)
+}
+
+/// Like `read_data`, but of only `count` rows of a dataset along its first
+/// dimension from `start`, for commands that go through it a block at a time.
+pub fn read_data_rows(
+    file: &File,
+    dataset: &Dataset,
+    start: usize,
+    count: usize,
+    span: Span,
+) -> Result<Value> {
+    let mut starts = vec![0; dataset.ndim()];
+    starts[0] = start;
+    let mut counts = dataset.shape();
+    counts[0] = count;
+    let options = Options {
+        flatten_separator: "/".to_string(),
+        selection: Selection {
+            start: Some(starts),
+            count: Some(counts),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let ctx = Context {
+        file,
+        options: &options,
+        span,
+        ancestors: Default::default(),
+        progress: None,
+        lazy: None,
+    };
+    read_dataset_list(dataset, &ctx)
+}
+
+/// How HDF5 datatypes map to values, for `help from hdf5`.
+pub const EXTRA_DESCRIPTION: &str = "Groups become records keyed by the paths of their members, and datasets nested lists of their shape, or a single value for scalars.
+Integers become ints, and uint64 out of the range of int strings of the exact digits. Floats of any width become floats, booleans bools, and Unix times dates.
+Strings, fixed or variable, become strings. Enums become the names of their members, compounds records of their fields, and arrays lists.
+Object references become the paths of their targets, and region references records of the target and the bounds of the selection.
+Bitfields become ints, or binary if wider than 8 bytes or above the range of int, and opaque data binary. Attributes are under `@attributes` of their group or as `{value, @attributes}` of their dataset.
+
+`$env.config.plugins.from_hdf5` may set `chunk_cache` and `sieve_buffer` sizes for files read from their paths, `follow_external`, `external_prefix`, `virtual_prefix`, and `flags`, a record of flags used when a call doesn't give them.";
+
+pub fn examples() -> Vec<Example<'static>> {
+    vec![
+        Example {
+            example: "open --raw data.h5 | from hdf5",
+            description: "Convert a whole file",
+            result: None,
+        },
+        Example {
+            example: "from hdf5 data.h5 /group/dataset",
+            description: "Read only a dataset of the file at a path, without reading the rest",
+            result: None,
+        },
+        Example {
+            example: "from hdf5 data.h5 /images --select '0:10, ::2, -1'",
+            description: "Read a slice of a dataset with NumPy-like indices",
+            result: None,
+        },
+        Example {
+            example: "from hdf5 data.h5 --head 20",
+            description: "Preview the first 20 elements of every dataset",
+            result: None,
+        },
+        Example {
+            example: "from hdf5 data.h5 --extract /images/frame_0 | save frame.bin",
+            description: "Save the bytes of a dataset as stored, without converting them",
+            result: None,
+        },
+        Example {
+            example: "from hdf5 data.h5 --schema",
+            description: "Describe the tree of the file without reading any data",
+            result: None,
+        },
+    ]
 }
 
 pub fn signature() -> Signature {
     Signature::build("from hdf5")
         .description("Convert from HDF5 binary into table")
         .allow_variants_without_examples(true)
-        .input_output_types(vec![(Type::Binary, Type::Any)])
+        .input_output_types(vec![
+            (Type::Binary, Type::Any),
+            (Type::Nothing, Type::Any),
+            (Type::list(Type::Binary), Type::list(Type::Any)),
+        ])
         .category(Category::Experimental)
+        .optional(
+            "path",
+            SyntaxShape::Filepath,
+            "read the file at the path, instead of binary input; with binary input, the selector",
+        )
+        .optional(
+            "selector",
+            SyntaxShape::String,
+            "read the group or dataset at this path in the file only",
+        )
+        .switch(
+            "mmap",
+            "map the file at the path into memory and read the mapping, instead of reading it with HDF5",
+            None,
+        )
+        .switch(
+            "swmr",
+            "open the file at the path for reading while a writer appends to it with SWMR, and refresh datasets before reading them",
+            None,
+        )
+        .named(
+            "vfd",
+            SyntaxShape::String,
+            "the file driver to read the file at the path with: sec2, stdio, or direct for unbuffered I/O if the linked HDF5 has it",
+            None,
+        )
+        .named(
+            "alignment",
+            SyntaxShape::Int,
+            "the alignment in bytes of the file at the path, e.g. the block size for --vfd direct",
+            None,
+        )
+        .switch(
+            "tabular",
+            "convert groups of 1-D datasets with equal lengths into tables",
+            Some('t'),
+        )
+        .switch(
+            "group-as-table",
+            "convert groups of subgroups with the same members into tables with a row per subgroup",
+            None,
+        )
+        .switch(
+            "pandas",
+            "convert groups written by `DataFrame.to_hdf` of pandas in the fixed format into tables led by the index",
+            None,
+        )
+        .switch(
+            "as-dataframe-friendly",
+            "like --tabular, but pad shorter datasets with nothing so that groups of differing lengths still become tables",
+            None,
+        )
+        .switch(
+            "meta",
+            "output the metadata of datasets instead of their data",
+            Some('m'),
+        )
+        .switch(
+            "schema",
+            "output the tree of groups with the name, dtype, shape, storage size, full datatype and attribute names of datasets, without reading any data",
+            None,
+        )
+        .switch(
+            "layout",
+            "output the metadata of datasets with their storage layout, chunk shape and filters, without reading any data",
+            None,
+        )
+        .switch(
+            "follow-external",
+            "read the objects external links point to, instead of `{external_file, external_path}`",
+            None,
+        )
+        .switch(
+            "follow-links",
+            "follow external links as well as soft links, as --follow-external does",
+            None,
+        )
+        .switch(
+            "no-follow-links",
+            "read soft links as `{soft_link}` and external links as `{external_file, external_path}`, instead of the objects they point to",
+            None,
+        )
+        .named(
+            "external-prefix",
+            SyntaxShape::String,
+            "look for the files of external links with relative paths under this directory first",
+            None,
+        )
+        .named(
+            "virtual-prefix",
+            SyntaxShape::String,
+            "look for the source files of virtual datasets with relative paths under this directory, instead of the directory of the file",
+            None,
+        )
+        .named(
+            "glob",
+            SyntaxShape::String,
+            "read only the datasets whose full paths match the glob, like `/sensors/*/pressure`, leaving out groups without any",
+            None,
+        )
+        .named(
+            "exclude",
+            SyntaxShape::String,
+            "leave out the datasets and groups whose full paths match the glob, with all in the groups",
+            None,
+        )
+        .named(
+            "max-depth",
+            SyntaxShape::Int,
+            "do not descend into groups nested deeper than this, leaving a placeholder instead",
+            None,
+        )
+        .named(
+            "max-children",
+            SyntaxShape::Int,
+            "read at most this many datasets and groups per group, counting the rest in an `@truncated` column",
+            None,
+        )
+        .named(
+            "offset",
+            SyntaxShape::Int,
+            "byte offset of the HDF5 image embedded in the input",
+            None,
+        )
+        .named(
+            "length",
+            SyntaxShape::Int,
+            "byte length of the HDF5 image embedded in the input, defaults to the rest of the input",
+            None,
+        )
+        .switch(
+            "flatten-paths",
+            "output a table of the full `path` and the `value` of each dataset instead of nested records",
+            None,
+        )
+        .named(
+            "flatten-separator",
+            SyntaxShape::String,
+            "separator joining the keys of --flatten-paths, defaults to `/`",
+            None,
+        )
+        .switch(
+            "flatten",
+            "read datasets of any rank as 1-D lists of their elements instead of nested lists",
+            None,
+        )
+        .switch(
+            "scalars-as-lists",
+            "read scalar datasets and attributes as lists of one element instead of bare values",
+            None,
+        )
+        .switch(
+            "sanitize-names",
+            "replace the characters of names other than letters, digits, `_` and `-`, so that they work in cell paths",
+            None,
+        )
+        .named(
+            "replacement",
+            SyntaxShape::String,
+            "replacement of the characters of --sanitize-names, defaults to `_`",
+            None,
+        )
+        .named(
+            "on-duplicate",
+            SyntaxShape::String,
+            "what to do with the same keys in a group: suffix them with `_1` and so on (the default), error, or keep the last",
+            None,
+        )
+        .switch(
+            "name-map",
+            "add `@names`, the original names of the sanitized keys of a group",
+            None,
+        )
+        .switch(
+            "no-attributes",
+            "do not read attributes, which are otherwise in an `@attributes` column of groups and beside the `value` of datasets, unless --tabular",
+            None,
+        )
+        .named(
+            "checksum",
+            SyntaxShape::String,
+            "output a table of the paths and content hashes of all datasets instead, only `sha256` is supported",
+            None,
+        )
+        .switch(
+            "with-checksum",
+            "like --checksum sha256, but keep the data in a `value` column",
+            None,
+        )
+        .switch(
+            "2d-as-table",
+            "convert 2-D numeric datasets into tables with a column per column of the matrix",
+            None,
+        )
+        .named(
+            "column-names",
+            SyntaxShape::String,
+            "comma-separated column names for --2d-as-table and --table, defaults to `col_0`, `col_1`, ...",
+            None,
+        )
+        .switch(
+            "table",
+            "read 2-D datasets of any type as a table of a record per row, with the columns named by a `column_names` attribute",
+            None,
+        )
+        .switch(
+            "no-read-attributes-data",
+            "describe attributes by dtype and shape instead of reading them, and list their names in the `attrs` column of --meta",
+            None,
+        )
+        .switch(
+            "transpose-compound",
+            "read 1-D compound datasets into a record of a list per field instead of a table, an error for any other dataset",
+            None,
+        )
+        .named(
+            "order",
+            SyntaxShape::String,
+            "read multidimensional datasets as row-major c (the default), column-major f with the axes reversed, or auto for f in MATLAB files",
+            None,
+        )
+        .named(
+            "columns",
+            SyntaxShape::List(Box::new(SyntaxShape::String)),
+            "read only these fields of compound datasets and attributes, in this order",
+            None,
+        )
+        .named(
+            "start",
+            SyntaxShape::OneOf(vec![
+                SyntaxShape::Int,
+                SyntaxShape::List(Box::new(SyntaxShape::Int)),
+            ]),
+            "read datasets from this index, given for all dimensions or as a list per dimension",
+            None,
+        )
+        .named(
+            "count",
+            SyntaxShape::OneOf(vec![
+                SyntaxShape::Int,
+                SyntaxShape::List(Box::new(SyntaxShape::Int)),
+            ]),
+            "read only this many elements of datasets, given for all dimensions or as a list per dimension",
+            None,
+        )
+        .named(
+            "stride",
+            SyntaxShape::OneOf(vec![
+                SyntaxShape::Int,
+                SyntaxShape::List(Box::new(SyntaxShape::Int)),
+            ]),
+            "read only every Kth element of datasets, given for all dimensions or as a list per dimension",
+            None,
+        )
+        .named(
+            "head",
+            SyntaxShape::Int,
+            "read at most this many elements of datasets along the first dimension, for a quick look, as `{value, truncated}`",
+            None,
+        )
+        .named(
+            "skip",
+            SyntaxShape::Int,
+            "skip this many elements of datasets along the first dimension, e.g. to page with --head",
+            None,
+        )
+        .named(
+            "select",
+            SyntaxShape::String,
+            "read datasets at NumPy-like indices instead, e.g. `0:100, ::2, -1`, where an index drops its dimension",
+            None,
+        )
+        .switch(
+            "lenient",
+            "replace unreadable datasets and groups with an `{error}` record instead of failing, and invalid bytes in fixed strings with the replacement character",
+            None,
+        )
+        .switch(
+            "strict",
+            "fail on values that can't be read exactly: uint64 out of the range of int, dangling references and invalid string bytes",
+            None,
+        )
+        .switch(
+            "fill-as-null",
+            "read elements equal to the fill value set for a dataset of numbers, strings or enums as nothing",
+            None,
+        )
+        .switch(
+            "nan-as-null",
+            "read NaN elements of float datasets as nothing",
+            None,
+        )
+        .switch(
+            "parallel",
+            "convert the elements of each dataset on several threads after reading them",
+            None,
+        )
+        .switch(
+            "images",
+            "read datasets of the HDF5 Image spec as `{width, height, planes, interlace, data}`, with the paths of their palettes",
+            None,
+        )
+        .switch(
+            "h5tb",
+            "read tables of the HDF5 Table spec as `{title, class, fields, data}`, with the columns of `data` named by their FIELD_n_NAME",
+            None,
+        )
+        .switch(
+            "raw-enums",
+            "read enums as their integer values instead of the names of their members",
+            None,
+        )
+        .switch(
+            "read-regions",
+            "read the elements selected by region references as `data` of their records",
+            None,
+        )
+        .named(
+            "int-as-time",
+            SyntaxShape::String,
+            "read the integers of the dataset at --time-path as offsets from the Unix epoch in seconds, millis, micros or nanos",
+            None,
+        )
+        .named(
+            "time-path",
+            SyntaxShape::String,
+            "path of the dataset for --int-as-time",
+            None,
+        )
+        .switch(
+            "dates",
+            "read integer datasets with CF-style `units` like `seconds since 1970-01-01` as dates",
+            None,
+        )
+        .switch(
+            "units",
+            "read numbers of datasets with `units` of time or bytes as durations or file sizes, and wrap others as `{value, unit}`",
+            None,
+        )
+        .switch(
+            "dim-scales",
+            "resolve dimension scales, into `@dimensions` of datasets and `@referenced_by` of scales",
+            None,
+        )
+        .switch(
+            "netcdf",
+            "read NetCDF-4 variables with dimension scales as tables indexed by their coordinates, a row per element",
+            None,
+        )
+        .named(
+            "order-by",
+            SyntaxShape::String,
+            "order the datasets of each group by name or by size in bytes",
+            None,
+        )
+        .switch("desc", "order by --order-by in descending order", None)
+        .named(
+            "max-memory",
+            SyntaxShape::Filesize,
+            "the most memory a dataset may take once converted, as roughly estimated from its elements",
+            None,
+        )
+        .named(
+            "over-memory",
+            SyntaxShape::String,
+            "what to do with a dataset over --max-memory: error (the default), skip, truncate, or stream a selected 1-D dataset",
+            None,
+        )
+        .switch(
+            "creation-order",
+            "read the members of groups in the order they were created, if tracked, instead of by name",
+            None,
+        )
+        .switch(
+            "opaque-tags",
+            "read opaque data as `{tag, data}` instead of bare binary",
+            None,
+        )
+        .switch(
+            "raw-compound",
+            "read compounds with the file datatype and member offsets instead of the native ones",
+            None,
+        )
+        .switch(
+            "complex-string",
+            "read complex numbers as strings like `3+4i` instead of `{re, im}`",
+            None,
+        )
+        .switch(
+            "bytes-as-binary",
+            "read fixed-length strings as binary of the exact bytes",
+            None,
+        )
+        .switch(
+            "raw",
+            "read numeric datasets as `{data, dtype, shape, order}` with the data as binary of the elements, without converting them",
+            None,
+        )
+        .named(
+            "extract",
+            SyntaxShape::String,
+            "stream the bytes of the dataset at the path as stored, a chunk at a time, without converting them",
+            None,
+        )
+        .switch(
+            "partial",
+            "read data of datatypes that can't be decoded as `{dtype, data}` with the raw bytes instead of failing",
+            None,
+        )
+        .named(
+            "root-key",
+            SyntaxShape::String,
+            "wrap the output in a record under this key, e.g. the file name",
+            None,
+        )
+        .switch(
+            "faithful",
+            "wrap datasets as `{_hdf5_dtype, _shape, data}` with the datatype and the shape they are stored with",
+            None,
+        )
+        .switch(
+            "with-dtype",
+            "wrap datasets as `{value, dtype}` with their datatype spelled out, members of compounds included",
+            None,
+        )
+        .switch(
+            "with-shape",
+            "wrap datasets as `{value, shape}` with the shape they are stored with, which is empty for scalars",
+            None,
+        )
+        .switch(
+            "metadata",
+            "wrap datasets as `{value, dtype, shape, chunks, filters}` with their stored datatype and creation properties",
+            None,
+        )
+        .switch(
+            "u64-as-float",
+            "read uint64 values out of the range of int as floats instead of strings of the exact digits",
+            None,
+        )
+        .named(
+            "stream-threshold",
+            SyntaxShape::Int,
+            "stream a selected 1-D dataset of more elements than this, reading a chunk at a time",
+            None,
+        )
+        .switch(
+            "lazy",
+            "read datasets only when they are accessed or collapsed, as handles of `hdf5 dataset` until then",
+            None,
+        )
+        .switch(
+            "progress",
+            "report each dataset converted to stderr, with the bytes read and the time taken",
+            None,
+        )
         .filter()
 }
 
+/// The settings of `$env.config.plugins.from_hdf5`.
+#[derive(Default)]
+struct Config {
+    chunk_cache: Option<usize>,
+    sieve_buffer: Option<usize>,
+    /// Named arguments used when the call doesn't have them.
+    flags: Vec<(Spanned<String>, Option<Value>)>,
+}
+
+impl Config {
+    fn parse(config: Option<Value>, head: Span) -> Result<Self, ShellError> {
+        let mut this = Self::default();
+        let Some(config) = config else {
+            return Ok(this);
+        };
+        let incorrect = |msg: String, val_span| ShellError::IncorrectValue {
+            msg,
+            val_span,
+            call_span: head,
+        };
+        let size = |value: &Value| match value {
+            Value::Filesize { val, .. } | Value::Int { val, .. } if *val >= 0 => {
+                Ok(Some(*val as usize))
+            }
+            value => Err(incorrect(
+                "expected a nonnegative filesize".to_string(),
+                value.span(),
+            )),
+        };
+        let signature = signature();
+        for (key, value) in config.as_record()? {
+            match key.as_str() {
+                "chunk_cache" => this.chunk_cache = size(value)?,
+                "sieve_buffer" => this.sieve_buffer = size(value)?,
+                "follow_external" => this.flag("follow-external", value.clone()),
+                "virtual_prefix" => this.flag("virtual-prefix", value.clone()),
+                "external_prefix" => this.flag("external-prefix", value.clone()),
+                "flags" => {
+                    for (name, value) in value.as_record()? {
+                        let name = name.replace('_', "-");
+                        if signature.get_long_flag(&name).is_none() {
+                            return Err(incorrect(
+                                format!("from hdf5 has no flag --{name}"),
+                                value.span(),
+                            ));
+                        }
+                        this.flag(&name, value.clone());
+                    }
+                }
+                key => {
+                    return Err(incorrect(
+                        format!("unknown setting {key} of the plugin config"),
+                        value.span(),
+                    ))
+                }
+            }
+        }
+        Ok(this)
+    }
+
+    fn flag(&mut self, name: &str, value: Value) {
+        let span = value.span();
+        self.flags.push((
+            Spanned {
+                item: name.to_string(),
+                span,
+            },
+            Some(value),
+        ));
+    }
+
+    /// The call with the default flags it doesn't override.
+    fn apply(&self, call: &EvaluatedCall) -> EvaluatedCall {
+        let mut call = call.clone();
+        for (name, value) in &self.flags {
+            if !call.named.iter().any(|(given, _)| given.item == name.item) {
+                call.named.push((name.clone(), value.clone()));
+            }
+        }
+        call
+    }
+}
+
+#[allow(clippy::result_large_err)]
+pub fn run(
+    images: &Images,
+    engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+) -> Result<PipelineData, LabeledError> {
+    let signals = engine.signals().clone();
+    let config = Config::parse(engine.get_plugin_config()?, call.head)?;
+    let call = &config.apply(call);
+    let options = Options {
+        signals: Some(signals.clone()),
+        chunk_cache: config.chunk_cache,
+        sieve_buffer: config.sieve_buffer,
+        ..Options::from_call(call)?
+    };
+    let first: Option<Spanned<String>> = call.opt(0)?;
+    let selector: Option<String> = call.opt(1)?;
+    if let Some(path) = &options.extract {
+        if selector.is_some() {
+            return Err(
+                LabeledError::new("--extract and a selector are mutually exclusive")
+                    .with_label("the dataset to extract", call.head),
+            );
+        }
+        return extract(engine, path, first, input, &options, signals);
+    }
+    if options.lazy {
+        return match (first, input) {
+            (Some(path), PipelineData::Empty) => {
+                let full_path = Path::new(&engine.get_current_dir()?).join(&path.item);
+                std::fs::read(&full_path)
+                    .map_err(|e| e.to_string().into())
+                    .and_then(|bytes| {
+                        to_lazy(images, &bytes, selector.as_deref(), &options, path.span)
+                    })
+                    .map(|value| PipelineData::Value(value, None))
+                    .map_err(|e| {
+                        LabeledError::new(e.to_string())
+                            .with_label(format!("while reading {}", full_path.display()), path.span)
+                    })
+            }
+            (Some(path), _) if selector.is_some() => Err(path_and_input(&path)),
+            (first, input) => with_binary_input(input, |bytes, span| {
+                let selector = first.as_ref().map(|first| first.item.as_str());
+                to_lazy(images, bytes, selector, &options, span)
+            }),
+        };
+    }
+    match (first, input) {
+        // A file opened by `open --raw` is read from the file rather than
+        // from the stream, which isn't collected into memory.
+        (
+            first,
+            PipelineData::ByteStream(
+                stream,
+                Some(PipelineMetadata {
+                    data_source: DataSource::FilePath(full_path),
+                    ..
+                }),
+            ),
+        ) if selector.is_none() && options.offset.is_none() && options.length.is_none() => {
+            let selector = first.map(|first| first.item);
+            read_file(
+                &full_path,
+                selector.as_deref(),
+                &options,
+                stream.span(),
+                signals,
+            )
+        }
+        (None, input) => with_binary_input(input, |bytes, span| {
+            from_hdf5_bytes(bytes, None, &options, span)
+        }),
+        (Some(path), PipelineData::Empty) => {
+            let full_path = Path::new(&engine.get_current_dir()?).join(&path.item);
+            read_file(
+                &full_path,
+                selector.as_deref(),
+                &options,
+                path.span,
+                signals,
+            )
+        }
+        // With binary input, the only positional is the selector.
+        (Some(first), input) if selector.is_none() => {
+            with_binary_pipeline(input, |bytes, span, meta| {
+                let selector = first.item.as_str();
+                let file = FileImage::new(options.image(bytes)?)?;
+                if streamable(&file, selector, &options)? {
+                    // The stream outlives the input, so it reads a copy.
+                    let file = open_image_copy(options.image(bytes)?)?.into();
+                    match to_stream(file, selector, &options, span, signals.clone())? {
+                        PipelineData::ListStream(stream, _) => {
+                            Ok(PipelineData::ListStream(stream, meta))
+                        }
+                        data => Ok(data),
+                    }
+                } else {
+                    let value = from_hdf5_file(&file, Some(selector), &options, span)?;
+                    Ok(PipelineData::Value(value, meta))
+                }
+            })
+        }
+        (Some(path), _) => Err(path_and_input(&path)),
+    }
+}
+
+fn path_and_input(path: &Spanned<String>) -> LabeledError {
+    LabeledError::new("a path and binary input are mutually exclusive")
+        .with_label("remove this path to read the input", path.span)
+}
+
+/// Streams the bytes of a dataset of the input, or of the file at the path.
+#[allow(clippy::result_large_err)]
+fn extract(
+    engine: &EngineInterface,
+    path: &str,
+    first: Option<Spanned<String>>,
+    input: PipelineData,
+    options: &Options,
+    signals: Signals,
+) -> Result<PipelineData, LabeledError> {
+    let from_file = |full_path: &Path, span| {
+        open_path(full_path, options)
+            .and_then(|file| to_byte_stream(file, path, span, signals.clone()))
+            .map_err(|e| {
+                LabeledError::new(e.to_string())
+                    .with_label(format!("while reading {}", full_path.display()), span)
+            })
+    };
+    match (first, input) {
+        (
+            None,
+            PipelineData::ByteStream(
+                stream,
+                Some(PipelineMetadata {
+                    data_source: DataSource::FilePath(full_path),
+                    ..
+                }),
+            ),
+        ) if options.offset.is_none() && options.length.is_none() => {
+            from_file(&full_path, stream.span())
+        }
+        (None, input) => with_binary_pipeline(input, |bytes, span, _| {
+            // The stream outlives the input, so it reads a copy.
+            let file = open_image_copy(options.image(bytes)?)?.into();
+            to_byte_stream(file, path, span, signals.clone())
+        }),
+        (Some(first), PipelineData::Empty) => from_file(
+            &Path::new(&engine.get_current_dir()?).join(&first.item),
+            first.span,
+        ),
+        (Some(first), _) => Err(LabeledError::new(
+            "a path and binary input are mutually exclusive",
+        )
+        .with_label("remove this path to read the input", first.span)),
+    }
+}
+
+/// The most bytes `--extract` reads at a time, unless a single row is larger.
+const EXTRACT_CHUNK: usize = 1 << 20;
+
+/// Streams the bytes of a dataset as stored, rows along its first dimension
+/// at a time. Variable-length data are pointers, so they have no such bytes.
+fn to_byte_stream(
+    file: FileImage<'static>,
+    path: &str,
+    span: Span,
+    signals: Signals,
+) -> Result<PipelineData> {
+    let dataset = file.dataset(path.trim_matches('/'))?;
+    let dtype = dataset.dtype()?;
+    if h5lock!(H5Tdetect_class(dtype.id(), H5T_class_t::H5T_VLEN)) > 0 {
+        return Err(format!("{} is of variable-length data", dataset.name()).into());
+    }
+    let shape = dataset.shape();
+    let len = shape.first().copied().unwrap_or(1);
+    let row = shape.iter().skip(1).product::<usize>() * dtype.size();
+    let rows = (EXTRACT_CHUNK / row.max(1)).max(1);
+    let mut start = 0;
+    let chunks = std::iter::from_fn(move || {
+        // Moves the file in, so that its image outlives the dataset.
+        let _ = &file;
+        if start >= len {
+            return None;
+        }
+        let count = rows.min(len - start);
+        let data = if shape.is_empty() {
+            dataset.read_raw_bytes(&dtype)
+        } else {
+            let selection = Selection {
+                skip: Some(start),
+                head: Some(count),
+                ..Default::default()
+            };
+            dataset
+                .read_raw_bytes_selection(&dtype, &selection)
+                .map(|(data, _)| data)
+        };
+        start += count;
+        Some(
+            data.map(|data| data.to_vec())
+                .map_err(|e| ShellError::GenericError {
+                    error: e.to_string(),
+                    msg: format!("while reading {}", dataset.name()),
+                    span: Some(span),
+                    help: None,
+                    inner: vec![],
+                }),
+        )
+    });
+    Ok(PipelineData::ByteStream(
+        ByteStream::from_result_iter(chunks, span, signals, ByteStreamType::Binary),
+        None,
+    ))
+}
+
+/// Reads the file at the path with HDF5, which reads only what is converted.
+#[allow(clippy::result_large_err)]
+fn read_file(
+    full_path: &Path,
+    selector: Option<&str>,
+    options: &Options,
+    span: Span,
+    signals: Signals,
+) -> Result<PipelineData, LabeledError> {
+    open_path(full_path, options)
+        .and_then(|file| match selector {
+            Some(selector) if streamable(&file, selector, options)? => {
+                Ok(to_stream(file, selector, options, span, signals)?)
+            }
+            selector => Ok(PipelineData::Value(
+                from_hdf5_file(&file, selector, options, span)?,
+                Some(PipelineMetadata {
+                    data_source: DataSource::FilePath(full_path.to_path_buf()),
+                    content_type: None,
+                }),
+            )),
+        })
+        .map_err(|e| {
+            LabeledError::new(e.to_string())
+                .with_label(format!("while reading {}", full_path.display()), span)
+        })
+}
+
+fn not_binary(v: &Value) -> LabeledError {
+    LabeledError::new(format!("requires binary input, got {}", v.get_type()))
+        .with_label("not binary", v.span())
+        .with_help("read the file with `open --raw`")
+}
+
+/// Collects the binary input of a command and converts it with `f`.
+#[allow(clippy::result_large_err)]
+pub fn with_binary_input(
+    input: PipelineData,
+    f: impl Fn(&[u8], Span) -> Result<Value>,
+) -> Result<PipelineData, LabeledError> {
+    with_binary_pipeline(input, |bytes, span, meta| {
+        Ok(PipelineData::Value(f(bytes, span)?, meta))
+    })
+}
+
+/// Points the error of reading the input at where it came from.
+/// The file is named when the input was opened from one.
+fn input_error(e: hdf5::Error, span: Span, meta: Option<&PipelineMetadata>) -> LabeledError {
+    let label = match meta.map(|meta| &meta.data_source) {
+        Some(DataSource::FilePath(path)) => format!("while reading {}", path.display()),
+        _ => "while reading this HDF5 input".to_string(),
+    };
+    LabeledError::new(e.to_string()).with_label(label, span)
+}
+
+/// The metadata of the output, which keeps where the input came from but is
+/// no longer of its content type.
+fn output_metadata(meta: &Option<PipelineMetadata>) -> Option<PipelineMetadata> {
+    meta.clone().map(|meta| meta.with_content_type(None))
+}
+
+/// Collects the binary input of a command and converts it with `f`, which
+/// may return a stream.
 #[allow(clippy::result_large_err)]
-pub fn run(input: PipelineData) -> Result<PipelineData, LabeledError> {
+fn with_binary_pipeline(
+    input: PipelineData,
+    f: impl Fn(&[u8], Span, Option<PipelineMetadata>) -> Result<PipelineData>,
+) -> Result<PipelineData, LabeledError> {
     match input {
         PipelineData::Empty => Ok(PipelineData::Empty),
         PipelineData::Value(v, meta) => match v {
             Value::Binary {
                 val,
                 internal_span: span,
-            } => {
-                let value =
-                    from_hdf5_bytes(&val, span).map_err(|e| LabeledError::new(e.to_string()))?;
-                Ok(PipelineData::Value(value, meta))
-            }
-            v => Err(LabeledError::new(format!(
-                "requires binary input, got {}",
-                v.get_type()
-            ))),
+            } => f(&val, span, output_metadata(&meta))
+                .map_err(|e| input_error(e, span, meta.as_ref())),
+            v => Err(not_binary(&v)),
         },
-        PipelineData::ListStream(_, _) => Err(LabeledError::new("unsupported list stream")),
+        // Each file of the stream is converted, into a list of them.
+        PipelineData::ListStream(stream, meta) => {
+            let span = stream.span();
+            let vals = stream
+                .into_iter()
+                .map(|v| match v {
+                    Value::Binary {
+                        val,
+                        internal_span: span,
+                    } => f(&val, span, None)
+                        .map_err(|e| input_error(e, span, None))
+                        .and_then(|data| Ok(data.into_value(span)?)),
+                    Value::Error { error, .. } => Err((*error).into()),
+                    v => Err(not_binary(&v)),
+                })
+                .try_collect()?;
+            Ok(PipelineData::Value(
+                Value::List {
+                    vals,
+                    internal_span: span,
+                },
+                output_metadata(&meta),
+            ))
+        }
         PipelineData::ByteStream(stream, meta) => {
             let value = stream.into_value()?;
             match value {
                 Value::Binary {
                     val,
                     internal_span: span,
-                } => {
-                    let value = from_hdf5_bytes(&val, span)
-                        .map_err(|e| LabeledError::new(e.to_string()))?;
-                    Ok(PipelineData::Value(value, meta))
-                }
+                } => f(&val, span, output_metadata(&meta))
+                    .map_err(|e| input_error(e, span, meta.as_ref())),
                 _ => unreachable!(),
             }
         }
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::hdf5_ext::ToImage;
+    use hdf5::h5call;
+    use hdf5_sys::h5i::hid_t;
+    use nu_protocol::record;
+
+    /// A call of `from hdf5` with the switches given.
+    pub(crate) fn call(flags: &[&str]) -> EvaluatedCall {
+        flags
+            .iter()
+            .fold(EvaluatedCall::new(Span::test_data()), |call, flag| {
+                call.with_flag(Spanned {
+                    item: flag.to_string(),
+                    span: Span::test_data(),
+                })
+            })
+    }
+
+    /// Converts the image as `from hdf5` does with the switches given.
+    pub(crate) fn convert(bytes: &[u8], flags: &[&str]) -> Result<Value> {
+        convert_call(bytes, &call(flags))
+    }
+
+    pub(crate) fn convert_call(bytes: &[u8], call: &EvaluatedCall) -> Result<Value> {
+        let options = Options::from_call(call).map_err(|e| e.to_string())?;
+        from_hdf5_bytes(bytes, None, &options, call.head)
+    }
+
+    /// The image of a file kept in memory, with what `build` writes to it.
+    pub(crate) fn image(name: &str, build: impl FnOnce(&File)) -> Vec<u8> {
+        let file = File::with_options()
+            .with_fapl(|p| p.core_filebacked(false))
+            .create(name)
+            .unwrap();
+        build(&file);
+        file.to_image().unwrap()
+    }
+
+    /// Writes a 1-D dataset of a datatype the `hdf5` crate can't describe,
+    /// from the bytes of its elements in the file datatype.
+    fn write_dataset(file: &File, name: &str, dtype: hid_t, len: usize, data: &[u8]) {
+        use hdf5_sys::{
+            h5d::{H5Dclose, H5Dcreate2, H5Dwrite},
+            h5p::H5P_DEFAULT,
+            h5s::{H5Sclose, H5Screate_simple, H5S_ALL},
+        };
+        let name = std::ffi::CString::new(name).unwrap();
+        let dims = [len as _];
+        let space = h5call!(H5Screate_simple(1, dims.as_ptr(), std::ptr::null())).unwrap();
+        let ds = h5call!(H5Dcreate2(
+            file.id(),
+            name.as_ptr(),
+            dtype,
+            space,
+            H5P_DEFAULT,
+            H5P_DEFAULT,
+            H5P_DEFAULT
+        ))
+        .unwrap();
+        h5call!(H5Dwrite(
+            ds,
+            dtype,
+            H5S_ALL,
+            H5S_ALL,
+            H5P_DEFAULT,
+            data.as_ptr().cast()
+        ))
+        .unwrap();
+        h5call!(H5Dclose(ds)).unwrap();
+        h5call!(H5Sclose(space)).unwrap();
+    }
+
+    /// Writes a 1-D attribute of a datatype the `hdf5` crate can't describe.
+    fn write_attribute(location: &Location, name: &str, dtype: hid_t, len: usize, data: &[u8]) {
+        use hdf5_sys::{
+            h5a::{H5Aclose, H5Acreate2, H5Awrite},
+            h5p::H5P_DEFAULT,
+            h5s::{H5Sclose, H5Screate_simple},
+        };
+        let name = std::ffi::CString::new(name).unwrap();
+        let dims = [len as _];
+        let space = h5call!(H5Screate_simple(1, dims.as_ptr(), std::ptr::null())).unwrap();
+        let attr = h5call!(H5Acreate2(
+            location.id(),
+            name.as_ptr(),
+            dtype,
+            space,
+            H5P_DEFAULT,
+            H5P_DEFAULT
+        ))
+        .unwrap();
+        h5call!(H5Awrite(attr, dtype, data.as_ptr().cast())).unwrap();
+        h5call!(H5Aclose(attr)).unwrap();
+        h5call!(H5Sclose(space)).unwrap();
+    }
+
+    /// The object reference to the path, as the bytes it's stored as.
+    fn object_ref(file: &File, path: &str) -> [u8; 8] {
+        use hdf5_sys::h5r::{hobj_ref_t, H5R_type_t, H5Rcreate};
+        let path = std::ffi::CString::new(path).unwrap();
+        let mut reference: hobj_ref_t = 0;
+        h5call!(H5Rcreate(
+            (&mut reference as *mut hobj_ref_t).cast(),
+            file.id(),
+            path.as_ptr(),
+            H5R_type_t::H5R_OBJECT1,
+            -1
+        ))
+        .unwrap();
+        reference.to_ne_bytes()
+    }
+
+    #[test]
+    fn reads_object_references_as_paths() {
+        use hdf5::globals::H5T_STD_REF_OBJ;
+        use hdf5_sys::h5t::{H5Tarray_create2, H5Tclose};
+        let bytes = image("reads_object_references_as_paths.h5", |file| {
+            file.new_dataset_builder()
+                .with_data(&[1i32])
+                .create("x")
+                .unwrap();
+            file.create_group("g").unwrap();
+            let refs = [object_ref(file, "/x"), object_ref(file, "/g")].concat();
+            write_dataset(file, "refs", *H5T_STD_REF_OBJ, 2, &refs);
+            let dims = [2];
+            let pair = h5call!(H5Tarray_create2(*H5T_STD_REF_OBJ, 1, dims.as_ptr())).unwrap();
+            write_dataset(file, "pairs", pair, 1, &refs);
+            h5call!(H5Tclose(pair)).unwrap();
+        });
+        let value = convert(&bytes, &[]).unwrap();
+        let paths = || Value::test_list(vec![Value::test_string("/x"), Value::test_string("/g")]);
+        assert_eq!(value.get_data_by_key("refs").unwrap(), paths());
+        // Also inside fixed arrays.
+        assert_eq!(
+            value.get_data_by_key("pairs").unwrap(),
+            Value::test_list(vec![paths()])
+        );
+    }
+
+    mod point {
+        // The derive implements the trait inside a named constant.
+        #![allow(non_local_definitions)]
+
+        #[derive(hdf5::H5Type, Clone, Copy)]
+        #[repr(C)]
+        pub struct Point {
+            pub x: i32,
+            pub y: f64,
+        }
+
+        #[derive(hdf5::H5Type, Clone, Copy)]
+        #[repr(C)]
+        pub struct Complex {
+            pub r: f64,
+            pub i: f64,
+        }
+
+        #[derive(hdf5::H5Type, Clone, Copy)]
+        #[repr(u8)]
+        pub enum Color {
+            Red = 1,
+            Green = 2,
+        }
+    }
+    use point::{Color, Complex, Point};
+
+    fn point(x: i32, y: f64) -> Value {
+        Value::test_record(record! {
+            "x" => Value::test_int(x as _),
+            "y" => Value::test_float(y),
+        })
+    }
+
+    #[test]
+    fn keeps_the_shape_of_compound_grids() {
+        let bytes = image("keeps_the_shape_of_compound_grids.h5", |file| {
+            let grid = ndarray::arr2(&[
+                [Point { x: 0, y: 0.5 }, Point { x: 1, y: 1.5 }],
+                [Point { x: 2, y: 2.5 }, Point { x: 3, y: 3.5 }],
+            ]);
+            file.new_dataset_builder()
+                .with_data(&grid)
+                .create("grid")
+                .unwrap();
+        });
+        assert_eq!(
+            convert(&bytes, &[])
+                .unwrap()
+                .get_data_by_key("grid")
+                .unwrap(),
+            Value::test_list(vec![
+                Value::test_list(vec![point(0, 0.5), point(1, 1.5)]),
+                Value::test_list(vec![point(2, 2.5), point(3, 3.5)]),
+            ])
+        );
+    }
+
+    #[test]
+    fn transposes_compounds_into_columns() {
+        let bytes = image("transposes_compounds_into_columns.h5", |file| {
+            file.new_dataset_builder()
+                .with_data(&[Point { x: 0, y: 0.5 }, Point { x: 1, y: 1.5 }])
+                .create("points")
+                .unwrap();
+        });
+        let value = convert(&bytes, &[]).unwrap();
+        assert_eq!(
+            value.get_data_by_key("points").unwrap(),
+            Value::test_list(vec![point(0, 0.5), point(1, 1.5)])
+        );
+        let value = convert(&bytes, &["transpose-compound"]).unwrap();
+        assert_eq!(
+            value.get_data_by_key("points").unwrap(),
+            Value::test_record(record! {
+                "x" => Value::test_list(vec![Value::test_int(0), Value::test_int(1)]),
+                "y" => Value::test_list(vec![Value::test_float(0.5), Value::test_float(1.5)]),
+            })
+        );
+    }
+
+    #[test]
+    fn refuses_to_transpose_other_datasets() {
+        let grid = image("refuses_to_transpose_grids.h5", |file| {
+            file.new_dataset_builder()
+                .with_data(&ndarray::arr2(&[
+                    [Point { x: 0, y: 0.5 }],
+                    [Point { x: 1, y: 1.5 }],
+                ]))
+                .create("g/grid")
+                .unwrap();
+        });
+        let err = convert(&grid, &["transpose-compound"]).unwrap_err();
+        assert!(err.to_string().contains("/g/grid is compound"), "{err}");
+        assert!(err.to_string().contains("of rank 2"), "{err}");
+        assert!(convert(&grid, &["transpose-compound", "flatten"]).is_ok());
+        let ints = image("refuses_to_transpose_ints.h5", |file| {
+            file.new_dataset_builder()
+                .with_data(&[1i32, 2])
+                .create("x")
+                .unwrap();
+        });
+        let err = convert(&ints, &["transpose-compound"]).unwrap_err();
+        assert!(err.to_string().contains("/x is int32 of rank 1"), "{err}");
+    }
+
+    #[test]
+    fn resolves_references_in_attributes() {
+        use hdf5::globals::H5T_STD_REF_OBJ;
+        let bytes = image("resolves_references_in_attributes.h5", |file| {
+            file.create_group("g").unwrap();
+            let x = file
+                .new_dataset_builder()
+                .with_data(&[1i32])
+                .create("x")
+                .unwrap();
+            let refs = object_ref(file, "/g");
+            write_attribute(&x, "targets", *H5T_STD_REF_OBJ, 1, &refs);
+        });
+        assert_eq!(
+            convert(&bytes, &[]).unwrap().get_data_by_key("x").unwrap(),
+            Value::test_record(record! {
+                "value" => Value::test_list(vec![Value::test_int(1)]),
+                "@attributes" => Value::test_record(record! {
+                    "targets" => Value::test_list(vec![Value::test_string("/g")]),
+                }),
+            })
+        );
+    }
+
+    /// A variable `t` with the coordinate variable `x` as the scale of its
+    /// dimension, linked both ways as the HDF5 dimension scale API and
+    /// netCDF-4 write them.
+    fn with_dimension_scale(file: &File) {
+        use hdf5::{
+            globals::{H5T_NATIVE_INT32, H5T_STD_REF_OBJ},
+            types::VarLenUnicode,
+        };
+        use hdf5_sys::h5t::{hvl_t, H5T_class_t, H5Tclose, H5Tcreate, H5Tinsert, H5Tvlen_create};
+        let x = file
+            .new_dataset_builder()
+            .with_data(&[0.0, 1.0])
+            .create("x")
+            .unwrap();
+        x.new_attr::<VarLenUnicode>()
+            .create("CLASS")
+            .unwrap()
+            .write_scalar(&"DIMENSION_SCALE".parse::<VarLenUnicode>().unwrap())
+            .unwrap();
+        let t = file
+            .new_dataset_builder()
+            .with_data(&[20.0, 21.0])
+            .create("t")
+            .unwrap();
+        // The scales of each dimension of `t`.
+        let mut scale = object_ref(file, "/x");
+        let dims = hvl_t {
+            len: 1,
+            p: scale.as_mut_ptr().cast(),
+        };
+        let dtype = h5call!(H5Tvlen_create(*H5T_STD_REF_OBJ)).unwrap();
+        let dims = unsafe {
+            std::slice::from_raw_parts(
+                std::ptr::addr_of!(dims).cast::<u8>(),
+                std::mem::size_of::<hvl_t>(),
+            )
+        };
+        write_attribute(&t, "DIMENSION_LIST", dtype, 1, dims);
+        h5call!(H5Tclose(dtype)).unwrap();
+        // The dimensions `x` is the scale of.
+        let dtype = h5call!(H5Tcreate(H5T_class_t::H5T_COMPOUND, 16)).unwrap();
+        h5call!(H5Tinsert(dtype, c"dataset".as_ptr(), 0, *H5T_STD_REF_OBJ)).unwrap();
+        h5call!(H5Tinsert(
+            dtype,
+            c"dimension".as_ptr(),
+            8,
+            *H5T_NATIVE_INT32
+        ))
+        .unwrap();
+        let refs = [&object_ref(file, "/t")[..], &0i32.to_ne_bytes(), &[0; 4]].concat();
+        write_attribute(&x, "REFERENCE_LIST", dtype, 1, &refs);
+        h5call!(H5Tclose(dtype)).unwrap();
+    }
+
+    #[test]
+    fn resolves_dimension_scales_both_ways() {
+        let bytes = image(
+            "resolves_dimension_scales_both_ways.h5",
+            with_dimension_scale,
+        );
+        let value = convert(&bytes, &["dim-scales"]).unwrap();
+        assert_eq!(
+            value
+                .get_data_by_key("t")
+                .unwrap()
+                .get_data_by_key("@dimensions")
+                .unwrap(),
+            Value::test_list(vec![Value::test_list(vec![Value::test_string("/x")])])
+        );
+        assert_eq!(
+            value
+                .get_data_by_key("x")
+                .unwrap()
+                .get_data_by_key("@referenced_by")
+                .unwrap(),
+            Value::test_list(vec![Value::test_record(record! {
+                "dataset" => Value::test_string("/t"),
+                "dimension" => Value::test_int(0),
+            })])
+        );
+    }
+
+    #[test]
+    fn reads_primitives_the_same_as_to_value() {
+        let bytes = image("reads_primitives_the_same_as_to_value.h5", |_| {});
+        let file = FileImage::new(&bytes).unwrap();
+        let options = Options::default();
+        let ctx = Context {
+            file: &file,
+            options: &options,
+            span: Span::test_data(),
+            ancestors: Default::default(),
+            progress: None,
+            lazy: None,
+        };
+        // The ends of the range, and zero or the special floats, of each width.
+        let cases = [
+            (
+                Dtype::Integer(IntSize::U1),
+                [i8::MIN, 0, i8::MAX].map(i8::to_ne_bytes).concat(),
+            ),
+            (
+                Dtype::Integer(IntSize::U2),
+                [i16::MIN, 0, i16::MAX].map(i16::to_ne_bytes).concat(),
+            ),
+            (
+                Dtype::Integer(IntSize::U4),
+                [i32::MIN, 0, i32::MAX].map(i32::to_ne_bytes).concat(),
+            ),
+            (
+                Dtype::Integer(IntSize::U8),
+                [i64::MIN, 0, i64::MAX].map(i64::to_ne_bytes).concat(),
+            ),
+            (
+                Dtype::Unsigned(IntSize::U1),
+                [0, u8::MAX].map(u8::to_ne_bytes).concat(),
+            ),
+            (
+                Dtype::Unsigned(IntSize::U2),
+                [0, u16::MAX].map(u16::to_ne_bytes).concat(),
+            ),
+            (
+                Dtype::Unsigned(IntSize::U4),
+                [0, u32::MAX].map(u32::to_ne_bytes).concat(),
+            ),
+            (
+                Dtype::Unsigned(IntSize::U8),
+                [0, u64::MAX].map(u64::to_ne_bytes).concat(),
+            ),
+            (
+                Dtype::Float(FloatSize::U4),
+                [f32::MIN, -0.0, f32::INFINITY, f32::NAN]
+                    .map(f32::to_ne_bytes)
+                    .concat(),
+            ),
+            (
+                Dtype::Float(FloatSize::U8),
+                [f64::MIN, -0.0, f64::INFINITY, f64::NAN]
+                    .map(f64::to_ne_bytes)
+                    .concat(),
+            ),
+        ];
+        for (dtype, data) in cases {
+            let slow: Vec<Value> = data
+                .chunks(dtype.size())
+                .map(|slice| to_value(slice, &dtype, &ctx))
+                .try_collect()
+                .unwrap();
+            // Compared by debug output, which NaN is equal in.
+            match primitive_values(&data, &dtype, ctx.span) {
+                Some(fast) => assert_eq!(format!("{fast:?}"), format!("{slow:?}"), "{dtype}"),
+                None => assert!(matches!(dtype, Dtype::Unsigned(IntSize::U8))),
+            }
+        }
+    }
+
+    #[test]
+    fn reads_uint64_out_of_the_range_of_int_as_digits() {
+        let bytes = image(
+            "reads_uint64_out_of_the_range_of_int_as_digits.h5",
+            |file| {
+                file.new_dataset_builder()
+                    .with_data(&[1u64, u64::MAX])
+                    .create("x")
+                    .unwrap();
+            },
+        );
+        assert_eq!(
+            convert(&bytes, &[]).unwrap().get_data_by_key("x").unwrap(),
+            Value::test_list(vec![
+                Value::test_int(1),
+                Value::test_string("18446744073709551615"),
+            ])
+        );
+        assert_eq!(
+            convert(&bytes, &["u64-as-float"])
+                .unwrap()
+                .get_data_by_key("x")
+                .unwrap(),
+            Value::test_list(vec![Value::test_int(1), Value::test_float(u64::MAX as f64)])
+        );
+        let err = convert(&bytes, &["strict"]).unwrap_err();
+        assert!(err.to_string().contains("uint64 18446744073709551615"));
+    }
+
+    #[test]
+    fn reads_bitfields_out_of_the_range_of_int_as_binary() {
+        use hdf5_sys::h5t::{H5Tcopy, H5Tset_size, H5T_NATIVE_B64};
+        let top = 1u64 << 63;
+        let bytes = image(
+            "reads_bitfields_out_of_the_range_of_int_as_binary.h5",
+            |file| {
+                let bits = [i64::MAX as u64, top, u64::MAX]
+                    .map(u64::to_ne_bytes)
+                    .concat();
+                write_dataset(file, "b64", *H5T_NATIVE_B64, 3, &bits);
+                let dtype = h5call!(H5Tcopy(*H5T_NATIVE_B64)).unwrap();
+                h5call!(H5Tset_size(dtype, 16)).unwrap();
+                let wide: Vec<u8> = (0..16).collect();
+                write_dataset(file, "b128", dtype, 1, &wide);
+            },
+        );
+        let value = convert(&bytes, &[]).unwrap();
+        assert_eq!(
+            value.get_data_by_key("b64").unwrap(),
+            Value::test_list(vec![
+                Value::test_int(i64::MAX),
+                Value::test_binary(top.to_ne_bytes()),
+                Value::test_binary(u64::MAX.to_ne_bytes()),
+            ])
+        );
+        assert_eq!(
+            value.get_data_by_key("b128").unwrap(),
+            Value::test_list(vec![Value::test_binary((0..16).collect::<Vec<u8>>())])
+        );
+    }
+
+    #[test]
+    fn reads_opaque_values_with_their_tags() {
+        use hdf5_sys::h5t::{H5T_class_t, H5Tclose, H5Tcreate, H5Tset_tag};
+        let bytes = image("reads_opaque_values_with_their_tags.h5", |file| {
+            let dtype = h5call!(H5Tcreate(H5T_class_t::H5T_OPAQUE, 2)).unwrap();
+            h5call!(H5Tset_tag(dtype, c"image/png".as_ptr())).unwrap();
+            write_dataset(file, "blobs", dtype, 2, b"abcd");
+            h5call!(H5Tclose(dtype)).unwrap();
+        });
+        assert_eq!(
+            convert(&bytes, &[])
+                .unwrap()
+                .get_data_by_key("blobs")
+                .unwrap(),
+            Value::test_list(vec![
+                Value::test_binary(b"ab".to_vec()),
+                Value::test_binary(b"cd".to_vec()),
+            ])
+        );
+        let tagged = |data: &[u8]| {
+            Value::test_record(record! {
+                "tag" => Value::test_string("image/png"),
+                "data" => Value::test_binary(data.to_vec()),
+            })
+        };
+        assert_eq!(
+            convert(&bytes, &["opaque-tags"])
+                .unwrap()
+                .get_data_by_key("blobs")
+                .unwrap(),
+            Value::test_list(vec![tagged(b"ab"), tagged(b"cd")])
+        );
+    }
+
+    /// Writes a dataset of a reference of the revised API to the root
+    /// group, a datatype which isn't supported.
+    fn write_revised_ref(file: &File, name: &str) {
+        use hdf5_sys::{
+            h5p::H5P_DEFAULT,
+            h5r::{H5R_ref_t, H5Rcreate_object, H5Rdestroy},
+            h5t::H5T_STD_REF,
+        };
+        let mut reference = std::mem::MaybeUninit::<H5R_ref_t>::uninit();
+        h5call!(H5Rcreate_object(
+            file.id(),
+            c"/".as_ptr(),
+            H5P_DEFAULT,
+            reference.as_mut_ptr()
+        ))
+        .unwrap();
+        let data = unsafe {
+            std::slice::from_raw_parts(
+                reference.as_ptr().cast::<u8>(),
+                std::mem::size_of::<H5R_ref_t>(),
+            )
+        };
+        write_dataset(file, name, *H5T_STD_REF, 1, data);
+        h5call!(H5Rdestroy(reference.as_mut_ptr())).unwrap();
+    }
+
+    #[test]
+    fn names_nested_datasets_failing_to_read() {
+        let bytes = image("names_nested_datasets_failing_to_read.h5", |file| {
+            file.create_group("a/b").unwrap();
+            write_revised_ref(file, "a/b/refs");
+        });
+        let err = convert(&bytes, &[]).unwrap_err();
+        assert!(err.to_string().contains("failed to read /a/b/refs"));
+    }
+
+    #[test]
+    fn reads_lazy_datasets_when_accessed() {
+        let bytes = image("reads_lazy_datasets_when_accessed.h5", |file| {
+            file.new_dataset_builder()
+                .with_data(&[1i32, 2])
+                .create("g/x")
+                .unwrap();
+            file.new_dataset_builder()
+                .with_data(&[3.5f64])
+                .create("y")
+                .unwrap();
+        });
+        let images = Images::default();
+        let options = Options::from_call(&call(&["lazy", "with-shape"])).unwrap();
+        let value = to_lazy(&images, &bytes, None, &options, Span::test_data()).unwrap();
+        drop(bytes);
+        let handle = |value: Value| match value {
+            Value::Custom { val, .. } => val.as_any().downcast_ref::<Hdf5Dataset>().cloned(),
+            _ => None,
+        };
+        let x = handle(
+            value
+                .get_data_by_key("g")
+                .unwrap()
+                .get_data_by_key("x")
+                .unwrap(),
+        )
+        .unwrap();
+        let y = handle(value.get_data_by_key("y").unwrap()).unwrap();
+        assert_eq!(x.path, "/g/x");
+        // Read with the options of the command.
+        assert_eq!(
+            images.read(&x, Span::test_data()).unwrap(),
+            Value::test_record(record! {
+                "value" => Value::test_list(vec![Value::test_int(1), Value::test_int(2)]),
+                "shape" => Value::test_list(vec![Value::test_int(2)]),
+            })
+        );
+        // Open until the last of the datasets is dropped.
+        images.release(x.id);
+        assert!(images.read(&y, Span::test_data()).is_ok());
+        images.release(y.id);
+        assert!(images.read(&y, Span::test_data()).is_err());
+    }
+
+    #[test]
+    fn never_reads_datasets_outside_the_glob() {
+        let bytes = image("never_reads_datasets_outside_the_glob.h5", |file| {
+            file.new_dataset_builder()
+                .with_data(&[1i32])
+                .create("keep/x")
+                .unwrap();
+            // Fails to read, so it mustn't be.
+            write_revised_ref(file, "refs");
+        });
+        let call = call(&[]).with_named(
+            Spanned {
+                item: "glob".to_string(),
+                span: Span::test_data(),
+            },
+            Value::test_string("/keep/*"),
+        );
+        assert_eq!(
+            convert_call(&bytes, &call).unwrap(),
+            Value::test_record(record! {
+                "keep" => Value::test_record(record! {
+                    "x" => Value::test_list(vec![Value::test_int(1)]),
+                }),
+            })
+        );
+    }
+
+    #[test]
+    fn reads_raw_bytes_of_unsupported_datatypes() {
+        use hdf5_sys::h5r::H5R_ref_t;
+        let bytes = image("reads_raw_bytes_of_unsupported_datatypes.h5", |file| {
+            write_revised_ref(file, "refs");
+        });
+        assert!(convert(&bytes, &[]).is_err());
+        let value = convert(&bytes, &["partial"]).unwrap();
+        let refs = value.get_data_by_key("refs").unwrap();
+        assert!(refs
+            .get_data_by_key("dtype")
+            .unwrap()
+            .as_str()
+            .unwrap()
+            .starts_with("H5T_REFERENCE"));
+        assert_eq!(
+            refs.get_data_by_key("data")
+                .unwrap()
+                .as_binary()
+                .unwrap()
+                .len(),
+            std::mem::size_of::<H5R_ref_t>()
+        );
+    }
+
+    #[test]
+    fn reads_misaligned_varlen_members() {
+        use hdf5::globals::{H5T_C_S1, H5T_NATIVE_INT32, H5T_NATIVE_UINT8};
+        use hdf5_sys::h5t::{
+            hvl_t, H5T_class_t, H5T_cset_t, H5Tclose, H5Tcopy, H5Tcreate, H5Tinsert, H5Tset_cset,
+            H5Tset_size, H5Tvlen_create, H5T_VARIABLE,
+        };
+        let bytes = image("reads_misaligned_varlen_members.h5", |file| {
+            let seq = h5call!(H5Tvlen_create(*H5T_NATIVE_INT32)).unwrap();
+            let string = h5call!(H5Tcopy(*H5T_C_S1)).unwrap();
+            h5call!(H5Tset_size(string, H5T_VARIABLE)).unwrap();
+            h5call!(H5Tset_cset(string, H5T_cset_t::H5T_CSET_UTF8)).unwrap();
+            // Packed, so that neither varlen member is aligned.
+            let hvl = std::mem::size_of::<hvl_t>();
+            let dtype = h5call!(H5Tcreate(H5T_class_t::H5T_COMPOUND, 1 + hvl + 8)).unwrap();
+            h5call!(H5Tinsert(dtype, c"tag".as_ptr(), 0, *H5T_NATIVE_UINT8)).unwrap();
+            h5call!(H5Tinsert(dtype, c"vals".as_ptr(), 1, seq)).unwrap();
+            h5call!(H5Tinsert(dtype, c"name".as_ptr(), 1 + hvl, string)).unwrap();
+            let mut vals = [1i32, 2];
+            let vals = hvl_t {
+                len: vals.len(),
+                p: vals.as_mut_ptr().cast(),
+            };
+            let name = c"héllo".as_ptr();
+            let vals_bytes =
+                unsafe { std::slice::from_raw_parts(std::ptr::addr_of!(vals).cast::<u8>(), hvl) };
+            let data = [&[7], vals_bytes, &(name as usize).to_ne_bytes()].concat();
+            write_dataset(file, "rows", dtype, 1, &data);
+            for dtype in [dtype, string, seq] {
+                h5call!(H5Tclose(dtype)).unwrap();
+            }
+        });
+        assert_eq!(
+            convert(&bytes, &["raw-compound"])
+                .unwrap()
+                .get_data_by_key("rows")
+                .unwrap(),
+            Value::test_list(vec![Value::test_record(record! {
+                "tag" => Value::test_int(7),
+                "vals" => Value::test_list(vec![Value::test_int(1), Value::test_int(2)]),
+                "name" => Value::test_string("héllo"),
+            })])
+        );
+    }
+
+    #[test]
+    fn stops_at_the_max_depth() {
+        let bytes = image("stops_at_the_max_depth.h5", |file| {
+            let mut group = file.create_group("a").unwrap();
+            for _ in 1..500 {
+                group = group.create_group("a").unwrap();
+            }
+        });
+        let call = call(&[]).with_named(
+            Spanned {
+                item: "max-depth".to_string(),
+                span: Span::test_data(),
+            },
+            Value::test_int(3),
+        );
+        let expected = (0..3).fold(Value::test_string("<truncated at depth 3>"), |val, _| {
+            Value::test_record(record! { "a" => val })
+        });
+        assert_eq!(
+            convert_call(&bytes, &call)
+                .unwrap()
+                .get_data_by_key("a")
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn indexes_netcdf_variables_by_coordinates() {
+        let bytes = image(
+            "indexes_netcdf_variables_by_coordinates.h5",
+            with_dimension_scale,
+        );
+        let row = |x, t| {
+            Value::test_record(record! {
+                "x" => Value::test_float(x),
+                "t" => Value::test_float(t),
+            })
+        };
+        assert_eq!(
+            convert(&bytes, &["netcdf"])
+                .unwrap()
+                .get_data_by_key("t")
+                .unwrap()
+                .get_data_by_key("value")
+                .unwrap(),
+            Value::test_list(vec![row(0.0, 20.0), row(1.0, 21.0)])
+        );
+    }
+
+    #[test]
+    fn names_h5tb_columns_by_their_fields() {
+        use hdf5::types::VarLenUnicode;
+        let bytes = image("names_h5tb_columns_by_their_fields.h5", |file| {
+            let ds = file
+                .new_dataset_builder()
+                .with_data(&[Point { x: 0, y: 0.5 }])
+                .create("points")
+                .unwrap();
+            for (name, val) in [
+                ("CLASS", "TABLE"),
+                ("TITLE", "Points"),
+                ("FIELD_0_NAME", "X"),
+                ("FIELD_1_NAME", "Y"),
+            ] {
+                ds.new_attr::<VarLenUnicode>()
+                    .create(name)
+                    .unwrap()
+                    .write_scalar(&val.parse::<VarLenUnicode>().unwrap())
+                    .unwrap();
+            }
+        });
+        assert_eq!(
+            convert(&bytes, &["h5tb"])
+                .unwrap()
+                .get_data_by_key("points")
+                .unwrap()
+                .get_data_by_key("value")
+                .unwrap(),
+            Value::test_record(record! {
+                "title" => Value::test_string("Points"),
+                "class" => Value::test_string("TABLE"),
+                "fields" => Value::test_list(vec![Value::test_string("X"), Value::test_string("Y")]),
+                "data" => Value::test_list(vec![Value::test_record(record! {
+                    "X" => Value::test_int(0),
+                    "Y" => Value::test_float(0.5),
+                })]),
+            })
+        );
+    }
+
+    #[test]
+    fn replaces_unreadable_groups_when_lenient() {
+        use hdf5_sys::{
+            h5p::H5P_DEFAULT,
+            h5t::{H5Tclose, H5Tcommit2, H5Tcopy, H5Tset_ebias, H5Tset_fields, H5Tset_size},
+        };
+        let bytes = image("replaces_unreadable_groups_when_lenient.h5", |file| {
+            file.create_group("good")
+                .unwrap()
+                .new_dataset_builder()
+                .with_data(&[1i32])
+                .create("x")
+                .unwrap();
+            // A bfloat16, which isn't supported.
+            let bad = file.create_group("bad").unwrap();
+            let dtype = h5call!(H5Tcopy(*hdf5::globals::H5T_IEEE_F32LE)).unwrap();
+            h5call!(H5Tset_fields(dtype, 15, 7, 8, 0, 7)).unwrap();
+            h5call!(H5Tset_ebias(dtype, 127)).unwrap();
+            h5call!(H5Tset_size(dtype, 2)).unwrap();
+            h5call!(H5Tcommit2(
+                bad.id(),
+                c"bfloat16".as_ptr(),
+                dtype,
+                H5P_DEFAULT,
+                H5P_DEFAULT,
+                H5P_DEFAULT
+            ))
+            .unwrap();
+            h5call!(H5Tclose(dtype)).unwrap();
+        });
+        assert!(convert(&bytes, &[]).is_err());
+        let value = convert(&bytes, &["lenient"]).unwrap();
+        assert_eq!(
+            value.get_data_by_key("good").unwrap(),
+            Value::test_record(record! {
+                "x" => Value::test_list(vec![Value::test_int(1)]),
+            })
+        );
+        let error = value.get_data_by_key("bad").unwrap();
+        let error = error.get_data_by_key("error").unwrap();
+        assert!(error.as_str().unwrap().contains("/bad"));
+    }
+
+    #[test]
+    fn names_enums_inside_arrays() {
+        use hdf5::types::VarLenArray;
+        let bytes = image("names_enums_inside_arrays.h5", |file| {
+            file.new_dataset_builder()
+                // Not a 1x2 grid.
+                .with_data(&ndarray::arr1(&[[Color::Red, Color::Green]]))
+                .create("fixed")
+                .unwrap();
+            file.new_dataset_builder()
+                .with_data(&[VarLenArray::from_slice(&[Color::Green])])
+                .create("varlen")
+                .unwrap();
+        });
+        let strings = |names: &[&str]| {
+            Value::test_list(vec![Value::test_list(
+                names.iter().map(|name| Value::test_string(*name)).collect(),
+            )])
+        };
+        let value = convert(&bytes, &[]).unwrap();
+        assert_eq!(
+            value.get_data_by_key("fixed").unwrap(),
+            strings(&["Red", "Green"])
+        );
+        assert_eq!(
+            value.get_data_by_key("varlen").unwrap(),
+            strings(&["Green"])
+        );
+        let value = convert(&bytes, &["raw-enums"]).unwrap();
+        assert_eq!(
+            value.get_data_by_key("fixed").unwrap(),
+            Value::test_list(vec![Value::test_list(vec![
+                Value::test_int(1),
+                Value::test_int(2),
+            ])])
+        );
+    }
+
+    #[test]
+    fn reads_booleans_of_any_size() {
+        use hdf5::globals::H5T_NATIVE_INT32;
+        use hdf5_sys::h5t::{
+            H5T_class_t, H5Tclose, H5Tcreate, H5Tenum_create, H5Tenum_insert, H5Tinsert,
+        };
+        let bytes = image("reads_booleans_of_any_size.h5", |file| {
+            let bool4 = h5call!(H5Tenum_create(*H5T_NATIVE_INT32)).unwrap();
+            for (name, value) in [(c"FALSE", 0i32), (c"TRUE", 1)] {
+                h5call!(H5Tenum_insert(
+                    bool4,
+                    name.as_ptr(),
+                    std::ptr::addr_of!(value).cast()
+                ))
+                .unwrap();
+            }
+            let dtype = h5call!(H5Tcreate(H5T_class_t::H5T_COMPOUND, 8)).unwrap();
+            h5call!(H5Tinsert(dtype, c"ok".as_ptr(), 0, bool4)).unwrap();
+            h5call!(H5Tinsert(dtype, c"n".as_ptr(), 4, *H5T_NATIVE_INT32)).unwrap();
+            let data: Vec<u8> = [(1i32, 7i32), (0, 8)]
+                .iter()
+                .flat_map(|(ok, n)| [ok.to_ne_bytes(), n.to_ne_bytes()].concat())
+                .collect();
+            write_dataset(file, "rows", dtype, 2, &data);
+            h5call!(H5Tclose(dtype)).unwrap();
+            h5call!(H5Tclose(bool4)).unwrap();
+        });
+        let row = |ok, n| {
+            Value::test_record(record! {
+                "ok" => Value::test_bool(ok),
+                "n" => Value::test_int(n),
+            })
+        };
+        assert_eq!(
+            convert(&bytes, &[])
+                .unwrap()
+                .get_data_by_key("rows")
+                .unwrap(),
+            Value::test_list(vec![row(true, 7), row(false, 8)])
+        );
+    }
+
+    #[test]
+    fn reads_unix_times_as_dates() {
+        let bytes = image("reads_unix_times_as_dates.h5", |file| {
+            let seconds = [0i64, 86400].map(i64::to_be_bytes).concat();
+            write_dataset(file, "t", *hdf5::globals::H5T_UNIX_D64BE, 2, &seconds);
+        });
+        let date =
+            |secs| Value::test_date(DateTime::from_timestamp(secs, 0).unwrap().fixed_offset());
+        assert_eq!(
+            convert(&bytes, &[]).unwrap().get_data_by_key("t").unwrap(),
+            Value::test_list(vec![date(0), date(86400)])
+        );
+    }
+
+    #[test]
+    fn refuses_each_lossy_case_when_strict() {
+        use hdf5::globals::{H5T_C_S1, H5T_STD_REF_DSETREG, H5T_STD_REF_OBJ};
+        use hdf5_sys::h5t::{H5T_cset_t, H5Tclose, H5Tcopy, H5Tset_cset, H5Tset_size};
+        let lossy = [
+            (
+                "uint64",
+                image("lossy_uint64.h5", |file| {
+                    file.new_dataset_builder()
+                        .with_data(&[u64::MAX])
+                        .create("x")
+                        .unwrap();
+                }),
+            ),
+            (
+                "object reference",
+                image("lossy_object_reference.h5", |file| {
+                    write_dataset(file, "x", *H5T_STD_REF_OBJ, 1, &[0; 8]);
+                }),
+            ),
+            (
+                "region reference",
+                image("lossy_region_reference.h5", |file| {
+                    write_dataset(file, "x", *H5T_STD_REF_DSETREG, 1, &[0; 12]);
+                }),
+            ),
+            (
+                "string bytes",
+                image("lossy_string_bytes.h5", |file| {
+                    let dtype = h5call!(H5Tcopy(*H5T_C_S1)).unwrap();
+                    h5call!(H5Tset_size(dtype, 3)).unwrap();
+                    h5call!(H5Tset_cset(dtype, H5T_cset_t::H5T_CSET_UTF8)).unwrap();
+                    write_dataset(file, "x", dtype, 1, b"ok\xff");
+                    h5call!(H5Tclose(dtype)).unwrap();
+                }),
+            ),
+        ];
+        // `--lenient` only matters to the string bytes, which fail without it.
+        for (case, bytes) in lossy {
+            assert!(convert(&bytes, &["lenient"]).is_ok(), "{case}");
+            let err = convert(&bytes, &["lenient", "strict"]).unwrap_err();
+            assert!(
+                err.to_string().contains("failed to read /x"),
+                "{case}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn reads_the_shape_of_rank_3_datasets() {
+        let bytes = image("reads_the_shape_of_rank_3_datasets.h5", |file| {
+            let data = ndarray::Array::from_iter(0..24i32)
+                .into_shape_with_order((2, 3, 4))
+                .unwrap();
+            file.new_dataset_builder()
+                .with_data(&data)
+                .create("x")
+                .unwrap();
+        });
+        let x = convert(&bytes, &["with-shape"])
+            .unwrap()
+            .get_data_by_key("x")
+            .unwrap();
+        let shape = x.get_data_by_key("shape").unwrap();
+        assert_eq!(
+            shape,
+            Value::test_list(vec![
+                Value::test_int(2),
+                Value::test_int(3),
+                Value::test_int(4),
+            ])
+        );
+        // One level of nesting a dimension, of the length of the dimension.
+        let mut lens = vec![];
+        let mut value = x.get_data_by_key("value").unwrap();
+        while let Value::List { vals, .. } = value {
+            lens.push(Value::test_int(vals.len() as _));
+            value = vals[0].clone();
+        }
+        assert_eq!(Value::test_list(lens), shape);
+        let x = convert(&bytes, &["with-shape", "with-dtype"])
+            .unwrap()
+            .get_data_by_key("x")
+            .unwrap();
+        assert_eq!(
+            x.as_record().unwrap().columns().collect::<Vec<_>>(),
+            ["value", "dtype", "shape"]
+        );
+        assert_eq!(
+            x.get_data_by_key("dtype").unwrap(),
+            Value::test_string("int32")
+        );
+    }
+
+    #[test]
+    fn sorts_members_unless_in_creation_order() {
+        use hdf5::globals::H5P_GROUP_CREATE;
+        use hdf5_sys::{
+            h5g::{H5Gclose, H5Gcreate2},
+            h5p::{
+                H5Pclose, H5Pcreate, H5Pset_link_creation_order, H5P_CRT_ORDER_INDEXED,
+                H5P_CRT_ORDER_TRACKED, H5P_DEFAULT,
+            },
+        };
+        let bytes = image("sorts_members_unless_in_creation_order.h5", |file| {
+            let gcpl = h5call!(H5Pcreate(*H5P_GROUP_CREATE)).unwrap();
+            h5call!(H5Pset_link_creation_order(
+                gcpl,
+                H5P_CRT_ORDER_TRACKED | H5P_CRT_ORDER_INDEXED
+            ))
+            .unwrap();
+            let group = h5call!(H5Gcreate2(
+                file.id(),
+                c"g".as_ptr(),
+                H5P_DEFAULT,
+                gcpl,
+                H5P_DEFAULT
+            ))
+            .unwrap();
+            h5call!(H5Gclose(group)).unwrap();
+            h5call!(H5Pclose(gcpl)).unwrap();
+            let group = file.group("g").unwrap();
+            for name in ["c", "a", "b"] {
+                group
+                    .new_dataset_builder()
+                    .with_data(&[1i32])
+                    .create(name)
+                    .unwrap();
+            }
+        });
+        let names = |flags: &[&str]| {
+            let value = convert(&bytes, flags).unwrap();
+            let g = value.get_data_by_key("g").unwrap();
+            g.as_record()
+                .unwrap()
+                .columns()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&[]), ["a", "b", "c"]);
+        assert_eq!(names(&["creation-order"]), ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn trims_space_padded_strings() {
+        use hdf5_sys::h5t::{H5T_str_t, H5Tclose, H5Tcopy, H5Tset_size, H5Tset_strpad};
+        let bytes = image("trims_space_padded_strings.h5", |file| {
+            let dtype = h5call!(H5Tcopy(*hdf5::globals::H5T_C_S1)).unwrap();
+            h5call!(H5Tset_size(dtype, 4)).unwrap();
+            h5call!(H5Tset_strpad(dtype, H5T_str_t::H5T_STR_SPACEPAD)).unwrap();
+            write_dataset(file, "names", dtype, 2, b"ab  a b ");
+            h5call!(H5Tclose(dtype)).unwrap();
+        });
+        assert_eq!(
+            convert(&bytes, &[])
+                .unwrap()
+                .get_data_by_key("names")
+                .unwrap(),
+            Value::test_list(vec![Value::test_string("ab"), Value::test_string("a b")])
+        );
+    }
+
+    #[test]
+    fn reads_soft_links_unless_not_following_them() {
+        let bytes = image("reads_soft_links_unless_not_following_them.h5", |file| {
+            file.new_dataset_builder()
+                .with_data(&[1i32])
+                .create("x")
+                .unwrap();
+            file.link_soft("/x", "alias").unwrap();
+        });
+        let value = convert(&bytes, &[]).unwrap();
+        assert_eq!(
+            value.get_data_by_key("alias").unwrap(),
+            value.get_data_by_key("x").unwrap()
+        );
+        let value = convert(&bytes, &["no-follow-links"]).unwrap();
+        assert_eq!(
+            value.get_data_by_key("alias").unwrap(),
+            Value::test_record(record! { "soft_link" => Value::test_string("/x") })
+        );
+    }
+
+    #[test]
+    fn rejects_following_and_not_following_links() {
+        assert!(Options::from_call(&call(&["follow-links", "no-follow-links"])).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_vfds() {
+        let call = call(&[]).with_named(
+            Spanned {
+                item: "vfd".to_string(),
+                span: Span::test_data(),
+            },
+            Value::test_string("nvme"),
+        );
+        let options = Options::from_call(&call).unwrap();
+        assert!(options.file_access().is_err());
+    }
+
+    #[test]
+    fn rejects_negative_max_memory() {
+        let max_memory = |max| {
+            call(&[]).with_named(
+                Spanned {
+                    item: "max-memory".to_string(),
+                    span: Span::test_data(),
+                },
+                Value::test_int(max),
+            )
+        };
+        let options = Options::from_call(&max_memory(0)).unwrap();
+        assert_eq!(options.max_memory, Some(0));
+        assert!(matches!(
+            Options::from_call(&max_memory(-1)),
+            Err(ShellError::IncorrectValue { .. })
+        ));
+    }
+
+    #[test]
+    fn applies_over_memory_policies() {
+        let bytes = image("applies_over_memory_policies.h5", |file| {
+            file.new_dataset_builder()
+                .with_data(&(0..100).collect::<Vec<i32>>())
+                .create("x")
+                .unwrap();
+        });
+        // Room for 10 of the 100 elements.
+        let max = 10 * std::mem::size_of::<Value>();
+        let policy = |policy: &str| {
+            call(&[])
+                .with_named(
+                    Spanned {
+                        item: "max-memory".to_string(),
+                        span: Span::test_data(),
+                    },
+                    Value::test_int(max as _),
+                )
+                .with_named(
+                    Spanned {
+                        item: "over-memory".to_string(),
+                        span: Span::test_data(),
+                    },
+                    Value::test_string(policy),
+                )
+        };
+        let err = convert_call(&bytes, &policy("error")).unwrap_err();
+        assert!(err.to_string().contains("over --max-memory"));
+        let value = convert_call(&bytes, &policy("skip")).unwrap();
+        assert!(value
+            .get_data_by_key("x")
+            .unwrap()
+            .get_data_by_key("skipped")
+            .is_some());
+        let value = convert_call(&bytes, &policy("truncate")).unwrap();
+        assert_eq!(
+            value.get_data_by_key("x").unwrap(),
+            Value::test_record(record! {
+                "value" => Value::test_list((0..10).map(Value::test_int).collect()),
+                "truncated" => Value::test_bool(true),
+            })
+        );
+        // Only a dataset selected alone streams.
+        let call = policy("stream");
+        let err = convert_call(&bytes, &call).unwrap_err();
+        assert!(err.to_string().contains("can't stream /x"));
+        let options = Options::from_call(&call).unwrap();
+        let file = FileImage::new(&bytes).unwrap();
+        assert!(streamable(&file, "x", &options).unwrap());
+        assert!(!streamable(&file, "/", &options).unwrap());
+    }
+
+    #[test]
+    fn rejects_random_bytes_as_not_hdf5() {
+        // A fixed sequence of a linear congruential generator.
+        let mut state = 0x2545f4914f6cdd1du64;
+        let mut random = |len: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| {
+                    state = state
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    (state >> 56) as u8
+                })
+                .collect()
+        };
+        let options = Options::default();
+        for len in [0, 7, 512, 4096] {
+            let input = PipelineData::Value(Value::test_binary(random(len)), None);
+            let err = with_binary_input(input, |bytes, span| {
+                from_hdf5_bytes(bytes, None, &options, span)
+            })
+            .unwrap_err();
+            assert_eq!(err.msg, "input is not an HDF5 file", "{len} bytes");
+            let err = read_path(&random(len), "x", None, Span::test_data()).unwrap_err();
+            assert_eq!(err.to_string(), "input is not an HDF5 file", "{len} bytes");
+        }
+    }
+
+    #[test]
+    fn renders_compounds_of_arrays() {
+        let field = |name: &str, ty: Dtype, offset: usize| CompoundField {
+            name: name.to_string(),
+            ty,
+            offset,
+        };
+        let int = Dtype::Integer(IntSize::U4);
+        let point = Dtype::Compound(CompoundDtype {
+            fields: vec![field("x", int.clone(), 0)],
+            size: 4,
+        });
+        let array = |ty: Dtype, len: usize| Dtype::FixedArray(Box::new(ty), len);
+        let dtype = Dtype::Compound(CompoundDtype {
+            fields: vec![
+                field("pos", array(Dtype::Float(FloatSize::U8), 3), 0),
+                field("ids", array(array(int, 2), 2), 24),
+                field("pts", array(point, 2), 40),
+            ],
+            size: 48,
+        });
+        assert_eq!(
+            render_dtype(&dtype),
+            "compound {pos: [float64; 3], ids: [[int32; 2]; 2], pts: [compound {x: int32}; 2]}"
+        );
+    }
+
+    #[test]
+    fn reverses_axes_of_column_major_data() {
+        // [[0, 1, 2], [3, 4, 5]] read as column-major is [[0, 3], [1, 4], [2, 5]].
+        assert_eq!(
+            reverse_axes(&[0, 1, 2, 3, 4, 5], 1, &[2, 3]),
+            [0, 3, 1, 4, 2, 5]
+        );
+        // Whole elements move, not bytes.
+        let data: Vec<u8> = [1u16, 2, 3, 4]
+            .iter()
+            .flat_map(|x| x.to_ne_bytes())
+            .collect();
+        let expected: Vec<u8> = [1u16, 3, 2, 4]
+            .iter()
+            .flat_map(|x| x.to_ne_bytes())
+            .collect();
+        assert_eq!(reverse_axes(&data, 2, &[2, 2]), expected);
+        // 2x2x2: the element at (i, j, k) moves to (k, j, i).
+        let data: Vec<u8> = (0..8).collect();
+        assert_eq!(reverse_axes(&data, 1, &[2, 2, 2]), [0, 4, 2, 6, 1, 5, 3, 7]);
+    }
+
+    fn select(s: &str) -> Result<Vec<Index>, ShellError> {
+        parse_select(&Spanned {
+            item: s.to_string(),
+            span: Span::test_data(),
+        })
+    }
+
+    #[test]
+    fn parses_numpy_indices_and_slices() {
+        let indices = select("1, ::-1, 2:, -3:-1:2").unwrap();
+        assert!(matches!(
+            indices.as_slice(),
+            [
+                Index::At(1),
+                Index::Slice(None, None, -1),
+                Index::Slice(Some(2), None, 1),
+                Index::Slice(Some(-3), Some(-1), 2),
+            ]
+        ));
+        for invalid in ["1::0", "a", "1:2:3:4", ""] {
+            assert!(select(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn resolves_selections_forwards() {
+        let (selection, reversed, dropped) =
+            resolve_select(&select("1, ::-1, 2:").unwrap(), &[4, 5, 6]).unwrap();
+        assert_eq!(selection.start, Some(vec![1, 0, 2]));
+        assert_eq!(selection.count, Some(vec![1, 5, 4]));
+        assert_eq!(selection.stride, Some(vec![1, 1, 1]));
+        assert_eq!(reversed, [false, true, false]);
+        assert_eq!(dropped, [true, false, false]);
+        // 4, 2 and 0, read as 0, 2 and 4.
+        let (selection, reversed, _) = resolve_select(&select("::-2").unwrap(), &[5]).unwrap();
+        assert_eq!(selection.start, Some(vec![0]));
+        assert_eq!(selection.count, Some(vec![3]));
+        assert_eq!(selection.stride, Some(vec![2]));
+        assert_eq!(reversed, [true]);
+        // Indices count from the end, and slices are clamped to the dimension.
+        let (selection, ..) = resolve_select(&select("-1, 3:100").unwrap(), &[4, 5]).unwrap();
+        assert_eq!(selection.start, Some(vec![3, 3]));
+        assert_eq!(selection.count, Some(vec![1, 2]));
+        assert!(resolve_select(&select("4").unwrap(), &[4]).is_err());
+        assert!(resolve_select(&select("0, 0").unwrap(), &[4]).is_err());
+    }
+
+    #[test]
+    fn parses_cf_time_units() {
+        let (unit, epoch) = parse_time_units("seconds since 1970-01-01").unwrap();
+        assert!(matches!(unit, TimeUnit::Seconds));
+        assert_eq!(epoch.timestamp(), 0);
+        let (unit, epoch) = parse_time_units(" ms since 2000-01-01T00:00:00+01:00 ").unwrap();
+        assert!(matches!(unit, TimeUnit::Millis));
+        assert_eq!(epoch.to_rfc3339(), "2000-01-01T00:00:00+01:00");
+        let (unit, epoch) = parse_time_units("ns since 2000-01-02 03:04:05.5").unwrap();
+        assert!(matches!(unit, TimeUnit::Nanos));
+        assert_eq!(epoch.to_rfc3339(), "2000-01-02T03:04:05.500+00:00");
+        assert!(parse_time_units("days since 1970-01-01").is_none());
+        assert!(parse_time_units("seconds").is_none());
+        assert!(parse_time_units("seconds since yesterday").is_none());
+    }
+
+    #[test]
+    fn widens_half_floats() {
+        assert_eq!(half_to_f64(0x3c00), 1.0);
+        assert_eq!(half_to_f64(0xc000), -2.0);
+        assert_eq!(half_to_f64(0x7bff), 65504.0);
+        // The smallest subnormal.
+        assert_eq!(half_to_f64(0x0001), 2f64.powi(-24));
+        assert_eq!(half_to_f64(0x7c00), f64::INFINITY);
+        assert_eq!(half_to_f64(0xfc00), f64::NEG_INFINITY);
+        assert!(half_to_f64(0x8000).is_sign_negative());
+        assert!(half_to_f64(0x7e00).is_nan());
+        // NaN keeps its sign and payload.
+        assert_eq!(half_to_f64(0xfe01).to_bits(), 0xfff8_0400_0000_0000);
+        assert_eq!(half_to_f64(0x7c01).to_bits(), 0x7ff0_0400_0000_0000);
+    }
+
+    #[test]
+    fn dedups_keys_by_policy() {
+        let cols = || ["a", "b", "a", "a"].map(String::from).to_vec();
+        let vals = || (1..=4).map(Value::test_int).collect::<Vec<_>>();
+        let (keys, kept) = dedup_keys(cols(), vals(), OnDuplicate::Suffix).unwrap();
+        assert_eq!(keys, ["a", "b", "a_1", "a_2"]);
+        assert_eq!(kept, vals());
+        let (keys, kept) = dedup_keys(cols(), vals(), OnDuplicate::Last).unwrap();
+        assert_eq!(keys, ["a", "b"]);
+        assert_eq!(kept, [Value::test_int(4), Value::test_int(2)]);
+        assert!(dedup_keys(cols(), vals(), OnDuplicate::Error).is_err());
+        // Suffixes skip keys already taken.
+        let cols = ["a", "a_1", "a"].map(String::from).to_vec();
+        let (keys, _) = dedup_keys(cols, vals()[..3].to_vec(), OnDuplicate::Suffix).unwrap();
+        assert_eq!(keys, ["a", "a_1", "a_2"]);
+    }
+
+    #[test]
+    fn rejects_ascii_over_7_bits() {
+        let options = Options::default();
+        assert_eq!(fixed_string(b"abc", true, &options).unwrap(), "abc");
+        let e = fixed_string(b"ab\xe9", true, &options).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "invalid ASCII byte 0xe9 at offset 2 of fixed string"
+        );
+        // The same bytes are valid as unicode if they're UTF-8.
+        assert_eq!(
+            fixed_string("caf\u{e9}".as_bytes(), false, &options).unwrap(),
+            "caf\u{e9}"
+        );
+    }
+
+    #[test]
+    fn reports_the_offset_of_invalid_utf8() {
+        let e = fixed_string(b"ok\xff\xfe", false, &Options::default()).unwrap_err();
+        assert_eq!(e.to_string(), "invalid UTF-8 at offset 2 of fixed string");
+    }
+
+    #[test]
+    fn replaces_bad_bytes_when_lenient() {
+        let lenient = Options {
+            lenient: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            fixed_string(b"ok\xff", false, &lenient).unwrap(),
+            "ok\u{fffd}"
+        );
+        assert_eq!(
+            fixed_string(b"ok\xff", true, &lenient).unwrap(),
+            "ok\u{fffd}"
+        );
+        // --strict wins over --lenient.
+        let strict = Options {
+            strict: true,
+            ..lenient
+        };
+        assert!(fixed_string(b"ok\xff", false, &strict).is_err());
+    }
+
+    #[test]
+    fn keeps_special_floats() {
+        let specials = [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, -0.0];
+        let bytes = image("keeps_special_floats.h5", |file| {
+            file.new_dataset_builder()
+                .with_data(&specials)
+                .create("f64")
+                .unwrap();
+            // A signaling NaN too, which stays a NaN when widened.
+            let f32s: Vec<f32> = specials
+                .iter()
+                .map(|&x| x as f32)
+                .chain([f32::from_bits(0x7fa0_0000)])
+                .collect();
+            file.new_dataset_builder()
+                .with_data(&f32s)
+                .create("f32")
+                .unwrap();
+        });
+        let value = convert(&bytes, &[]).unwrap();
+        for name in ["f64", "f32"] {
+            let vals = value.get_data_by_key(name).unwrap();
+            let vals: Vec<f64> = vals
+                .as_list()
+                .unwrap()
+                .iter()
+                .map(|val| val.as_float().unwrap())
+                .collect();
+            assert!(vals[0].is_nan());
+            assert_eq!(vals[1], f64::INFINITY);
+            assert_eq!(vals[2], f64::NEG_INFINITY);
+            assert_eq!(vals[3].to_bits(), (-0.0f64).to_bits());
+            if name == "f32" {
+                assert!(vals[4].is_nan());
+            }
+        }
+    }
+
+    #[test]
+    fn flattens_paths_into_a_table() {
+        let bytes = image("flattens_paths_into_a_table.h5", |file| {
+            file.create_group("g").unwrap();
+            for (path, x) in [("y", 2), ("g/x", 1)] {
+                file.new_dataset_builder()
+                    .with_data(&[x])
+                    .create(path)
+                    .unwrap();
+            }
+        });
+        let row = |path: &str, x| {
+            Value::test_record(record! {
+                "path" => Value::test_string(path),
+                "value" => Value::test_list(vec![Value::test_int(x)]),
+            })
+        };
+        assert_eq!(
+            convert(&bytes, &["flatten-paths"]).unwrap(),
+            Value::test_list(vec![row("y", 2), row("g/x", 1)])
+        );
+    }
+
+    #[test]
+    fn flattens_datasets_of_any_rank() {
+        let bytes = image("flattens_datasets_of_any_rank.h5", |file| {
+            file.new_dataset_builder()
+                .with_data(&ndarray::arr2(&[[1, 2], [3, 4]]))
+                .create("x")
+                .unwrap();
+        });
+        let ints =
+            |vals: &[i64]| Value::test_list(vals.iter().map(|&v| Value::test_int(v)).collect());
+        assert_eq!(
+            convert(&bytes, &[]).unwrap().get_data_by_key("x").unwrap(),
+            Value::test_list(vec![ints(&[1, 2]), ints(&[3, 4])])
+        );
+        assert_eq!(
+            convert(&bytes, &["flatten"])
+                .unwrap()
+                .get_data_by_key("x")
+                .unwrap(),
+            ints(&[1, 2, 3, 4])
+        );
+    }
+
+    #[test]
+    fn reads_scalars_as_lists_of_one() {
+        let bytes = image("reads_scalars_as_lists_of_one.h5", |file| {
+            file.new_dataset::<i32>()
+                .create("rate")
+                .unwrap()
+                .write_scalar(&48000)
+                .unwrap();
+        });
+        assert_eq!(
+            convert(&bytes, &[])
+                .unwrap()
+                .get_data_by_key("rate")
+                .unwrap(),
+            Value::test_int(48000)
+        );
+        assert_eq!(
+            convert(&bytes, &["scalars-as-lists"])
+                .unwrap()
+                .get_data_by_key("rate")
+                .unwrap(),
+            Value::test_list(vec![Value::test_int(48000)])
+        );
+    }
+
+    #[test]
+    fn reads_nan_as_null() {
+        let bytes = image("reads_nan_as_null.h5", |file| {
+            file.new_dataset_builder()
+                .with_data(&[1.5f64, f64::NAN])
+                .create("x")
+                .unwrap();
+            file.new_dataset_builder()
+                .with_data(&[
+                    Complex { r: 1.0, i: 2.0 },
+                    Complex {
+                        r: f64::NAN,
+                        i: 0.0,
+                    },
+                ])
+                .create("z")
+                .unwrap();
+        });
+        let value = convert(&bytes, &["nan-as-null"]).unwrap();
+        assert_eq!(
+            value.get_data_by_key("x").unwrap(),
+            Value::test_list(vec![Value::test_float(1.5), Value::test_nothing()])
+        );
+        // A complex value with a NaN part is nothing as a whole.
+        assert_eq!(
+            value.get_data_by_key("z").unwrap(),
+            Value::test_list(vec![
+                Value::test_record(record! {
+                    "re" => Value::test_float(1.0),
+                    "im" => Value::test_float(2.0),
+                }),
+                Value::test_nothing(),
+            ])
+        );
+        let value = convert(&bytes, &[]).unwrap();
+        let x = value.get_data_by_key("x").unwrap();
+        assert!(x.as_list().unwrap()[1].as_float().unwrap().is_nan());
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{
+    dtype::Dtype,
+    from_hdf5::{read_data, with_binary_input},
+    hdf5_ext::FileImage,
+};
+use hdf5::{File, Location, Result};
+use nu_plugin::EvaluatedCall;
+use nu_protocol::{
+    Category, LabeledError, PipelineData, Record, Signature, Span, SyntaxShape, Type, Value,
+};
+
+fn to_attrs(file: &File, location: &Location, span: Span) -> Result<Value> {
+    let cols = location.attr_names()?;
+    let vals = cols
+        .iter()
+        .map(|name| read_data(file, &location.attr(name)?, span))
+        .try_collect()?;
+    Ok(Value::Record {
+        val: Record::from_raw_cols_vals(cols, vals, span, span)
+            .unwrap()
+            .into(),
+        internal_span: span,
+    })
+}
+
+/// The names, datatypes and shapes of the attributes, without reading them.
+fn to_attr_names(location: &Location, span: Span) -> Result<Value> {
+    let rows = location
+        .attr_names()?
+        .into_iter()
+        .map(|name| -> Result<Value> {
+            let attr = location.attr(&name)?;
+            let cols = vec!["name".to_string(), "dtype".to_string(), "shape".to_string()];
+            let vals = vec![
+                Value::String {
+                    val: name,
+                    internal_span: span,
+                },
+                Value::String {
+                    val: Dtype::from_datatype(&attr.dtype()?)?.to_string(),
+                    internal_span: span,
+                },
+                Value::List {
+                    vals: attr
+                        .shape()
+                        .into_iter()
+                        .map(|d| Value::Int {
+                            val: d as _,
+                            internal_span: span,
+                        })
+                        .collect(),
+                    internal_span: span,
+                },
+            ];
+            Ok(Value::Record {
+                val: Record::from_raw_cols_vals(cols, vals, span, span)
+                    .unwrap()
+                    .into(),
+                internal_span: span,
+            })
+        })
+        .try_collect()?;
+    Ok(Value::List {
+        vals: rows,
+        internal_span: span,
+    })
+}
+
+fn attrs(bytes: &[u8], path: &str, names: bool, span: Span) -> Result<Value> {
+    let file = FileImage::new(bytes)?;
+    let read = |location: &Location| {
+        if names {
+            to_attr_names(location, span)
+        } else {
+            to_attrs(&file, location, span)
+        }
+    };
+    match path.trim_matches('/') {
+        "" => read(&file),
+        path => match file.dataset(path) {
+            Ok(ds) => read(&ds),
+            Err(_) => {
+                let group = file.group(path)?;
+                read(&group)
+            }
+        },
+    }
+}
+
+pub fn signature() -> Signature {
+    Signature::build("hdf5 attrs")
+        .description("Read the attributes of a group or a dataset in HDF5 binary")
+        .allow_variants_without_examples(true)
+        .input_output_types(vec![
+            (Type::Binary, Type::Record(vec![].into())),
+            (Type::Binary, Type::table()),
+        ])
+        .category(Category::Experimental)
+        .required("path", SyntaxShape::String, "path of the group or dataset")
+        .switch(
+            "names",
+            "list the names, datatypes and shapes of the attributes, without reading them",
+            Some('n'),
+        )
+        .filter()
+}
+
+#[allow(clippy::result_large_err)]
+pub fn run(call: &EvaluatedCall, input: PipelineData) -> Result<PipelineData, LabeledError> {
+    let path: String = call.req(0)?;
+    let names = call.has_flag("names")?;
+    with_binary_input(input, |bytes, span| attrs(bytes, &path, names, span))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_hdf5::tests::image;
+    use nu_protocol::record;
+
+    #[test]
+    fn lists_attribute_names_without_values() {
+        let bytes = image("lists_attribute_names_without_values.h5", |file| {
+            file.new_attr::<f64>()
+                .shape(3)
+                .create("range")
+                .unwrap()
+                .write(&[0.0, 1.0, 2.0])
+                .unwrap();
+        });
+        assert_eq!(
+            attrs(&bytes, "/", true, Span::test_data()).unwrap(),
+            Value::test_list(vec![Value::test_record(record! {
+                "name" => Value::test_string("range"),
+                "dtype" => Value::test_string(Dtype::Float(hdf5::types::FloatSize::U8).to_string()),
+                "shape" => Value::test_list(vec![Value::test_int(3)]),
+            })])
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{
+    dtype::Dtype,
+    from_hdf5::with_binary_input,
+    hdf5_ext::{FileImage, NativeType, ReadBlocks},
+};
+use hdf5::{Dataset, Datatype, Result};
+use nu_plugin::EvaluatedCall;
+use nu_protocol::{
+    Category, LabeledError, PipelineData, Record, Signature, Span, SyntaxShape, Type, Value,
+};
+
+/// The number of elements read at a time.
+const BLOCK: usize = 65536;
+
+/// Running statistics, with the mean and variance by Welford's method.
+#[derive(Default)]
+struct Stats {
+    count: usize,
+    min: f64,
+    max: f64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Stats {
+    fn push(&mut self, x: f64) {
+        if self.count == 0 {
+            self.min = x;
+            self.max = x;
+        }
+        self.count += 1;
+        self.min = self.min.min(x);
+        self.max = self.max.max(x);
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(std::mem::size_of::<f64>()) {
+            self.push(f64::from_ne_bytes(chunk.try_into().unwrap()));
+        }
+    }
+}
+
+/// Reads the dataset as floats a block of rows at a time, so that only the
+/// statistics are kept.
+fn stats(ds: &Dataset) -> Result<Stats> {
+    let dtype = Dtype::from_datatype(&ds.dtype()?.to_native()?)?;
+    if !matches!(
+        dtype,
+        Dtype::Integer(_) | Dtype::Unsigned(_) | Dtype::Float(_) | Dtype::Half
+    ) {
+        return Err(format!("{} is {dtype}, not numbers", ds.name()).into());
+    }
+    let f64_dtype = Datatype::from_type::<f64>()?;
+    let mut stats = Stats::default();
+    ds.read_blocks(&f64_dtype, BLOCK, |data| {
+        stats.push_bytes(data);
+        Ok(())
+    })?;
+    Ok(stats)
+}
+
+fn describe(bytes: &[u8], path: &str, span: Span) -> Result<Value> {
+    let file = FileImage::new(bytes)?;
+    let stats = stats(&file.dataset(path)?)?;
+    let float = |val: f64| Value::Float {
+        val,
+        internal_span: span,
+    };
+    let nothing = || Value::Nothing {
+        internal_span: span,
+    };
+    let (min, max, mean) = match stats.count {
+        0 => (nothing(), nothing(), nothing()),
+        _ => (float(stats.min), float(stats.max), float(stats.mean)),
+    };
+    let std = match stats.count {
+        0 => nothing(),
+        n => float((stats.m2 / n as f64).sqrt()),
+    };
+    let cols = vec![
+        "count".to_string(),
+        "min".to_string(),
+        "max".to_string(),
+        "mean".to_string(),
+        "std".to_string(),
+    ];
+    let vals = vec![
+        Value::Int {
+            val: stats.count as _,
+            internal_span: span,
+        },
+        min,
+        max,
+        mean,
+        std,
+    ];
+    Ok(Value::Record {
+        val: Record::from_raw_cols_vals(cols, vals, span, span)
+            .unwrap()
+            .into(),
+        internal_span: span,
+    })
+}
+
+pub fn signature() -> Signature {
+    Signature::build("hdf5 describe")
+        .description(
+            "Compute the count, min, max, mean and std of a numeric dataset in HDF5 binary",
+        )
+        .allow_variants_without_examples(true)
+        .input_output_types(vec![(Type::Binary, Type::Record(vec![].into()))])
+        .category(Category::Experimental)
+        .required("path", SyntaxShape::String, "path of the dataset")
+        .filter()
+}
+
+#[allow(clippy::result_large_err)]
+pub fn run(call: &EvaluatedCall, input: PipelineData) -> Result<PipelineData, LabeledError> {
+    let path: String = call.req(0)?;
+    with_binary_input(input, |bytes, span| describe(bytes, &path, span))
+}
@@ -0,0 +1,436 @@
+use crate::{
+    dtype::Dtype,
+    from_hdf5::{read_data, read_data_rows, with_binary_input},
+    hdf5_ext::{FileImage, NativeType},
+};
+use hdf5::{Container, Dataset, File, Group, Location, LocationToken, Result};
+use nu_plugin::{EngineInterface, EvaluatedCall};
+use nu_protocol::{
+    Category, LabeledError, PipelineData, Record, ShellError, Signature, Span, Spanned,
+    SyntaxShape, Type, Value,
+};
+use std::{collections::BTreeMap, path::Path};
+
+/// The number of elements of a dataset read from each file at a time.
+const BLOCK: usize = 65536;
+
+/// The groups and datasets of a file by their paths.
+enum Object {
+    Group(Group),
+    Dataset(Dataset),
+}
+
+/// Collects the objects under a group, not descending again into its `ancestors` at link cycles.
+fn objects(
+    group: &Group,
+    ancestors: &mut Vec<(u64, LocationToken)>,
+    map: &mut BTreeMap<String, Object>,
+) -> Result<()> {
+    let info = group.loc_info()?;
+    ancestors.push((info.fileno, info.token));
+    for ds in group.datasets()? {
+        map.insert(ds.name(), Object::Dataset(ds));
+    }
+    for g in group.groups()? {
+        let info = g.loc_info()?;
+        if !ancestors.contains(&(info.fileno, info.token)) {
+            objects(&g, ancestors, map)?;
+        }
+        map.insert(g.name(), Object::Group(g));
+    }
+    ancestors.pop();
+    Ok(())
+}
+
+struct Differ<'a> {
+    left: &'a File,
+    right: &'a File,
+    tolerance: f64,
+    span: Span,
+    rows: Vec<Value>,
+}
+
+impl Differ<'_> {
+    fn row(&mut self, path: &str, change: &str, index: Value, left: Value, right: Value) {
+        let span = self.span;
+        let cols = vec![
+            "path".to_string(),
+            "change".to_string(),
+            "index".to_string(),
+            "left".to_string(),
+            "right".to_string(),
+        ];
+        let vals = vec![
+            Value::String {
+                val: path.to_string(),
+                internal_span: span,
+            },
+            Value::String {
+                val: change.to_string(),
+                internal_span: span,
+            },
+            index,
+            left,
+            right,
+        ];
+        self.rows.push(Value::Record {
+            val: Record::from_raw_cols_vals(cols, vals, span, span)
+                .unwrap()
+                .into(),
+            internal_span: span,
+        });
+    }
+
+    fn nothing(&self) -> Value {
+        Value::Nothing {
+            internal_span: self.span,
+        }
+    }
+
+    fn string(&self, val: String) -> Value {
+        Value::String {
+            val,
+            internal_span: self.span,
+        }
+    }
+
+    fn shape(&self, shape: &[usize]) -> Value {
+        Value::List {
+            vals: shape
+                .iter()
+                .map(|&d| Value::Int {
+                    val: d as _,
+                    internal_span: self.span,
+                })
+                .collect(),
+            internal_span: self.span,
+        }
+    }
+
+    /// Whether the datatypes or the shapes differ, which are then reported
+    /// instead of the elements.
+    fn mismatch(&mut self, path: &str, left: &Container, right: &Container) -> Result<bool> {
+        let ltype = Dtype::from_datatype(&left.dtype()?.to_native()?)?.to_string();
+        let rtype = Dtype::from_datatype(&right.dtype()?.to_native()?)?.to_string();
+        if ltype != rtype {
+            let (l, r) = (self.string(ltype), self.string(rtype));
+            self.row(path, "dtype", self.nothing(), l, r);
+            return Ok(true);
+        }
+        if left.shape() != right.shape() {
+            let (l, r) = (self.shape(&left.shape()), self.shape(&right.shape()));
+            self.row(path, "shape", self.nothing(), l, r);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Compares the elements of values of the same shape, the first of which
+    /// is at `first_row` of the data.
+    fn values(&mut self, path: &str, left: Value, right: Value, first_row: usize) {
+        let mut lvals = vec![];
+        elements(left, &mut vec![], &mut lvals);
+        let mut rvals = vec![];
+        elements(right, &mut vec![], &mut rvals);
+        for ((mut index, l), (_, r)) in lvals.into_iter().zip(rvals) {
+            if !same(&l, &r, self.tolerance) {
+                if let Some(row) = index.first_mut() {
+                    *row += first_row;
+                }
+                let index = self.shape(&index);
+                self.row(path, "value", index, l, r);
+            }
+        }
+    }
+
+    /// Compares datasets a block of rows at a time, so that only a block of
+    /// each is in memory at once.
+    fn dataset(&mut self, path: &str, left: &Dataset, right: &Dataset) -> Result<()> {
+        if self.mismatch(path, left, right)? {
+            return Ok(());
+        }
+        let shape = left.shape();
+        let Some((&rows, rest)) = shape.split_first() else {
+            let l = read_data(self.left, left, self.span)?;
+            let r = read_data(self.right, right, self.span)?;
+            self.values(path, l, r, 0);
+            return Ok(());
+        };
+        let row_size = rest.iter().product::<usize>();
+        if row_size == 0 {
+            return Ok(());
+        }
+        let block = (BLOCK / row_size).max(1);
+        for start in (0..rows).step_by(block) {
+            let count = block.min(rows - start);
+            let l = read_data_rows(self.left, left, start, count, self.span)?;
+            let r = read_data_rows(self.right, right, start, count, self.span)?;
+            self.values(path, l, r, start);
+        }
+        Ok(())
+    }
+
+    /// Compares the attributes of objects at the same path, reported at the
+    /// path of the object and `@` the name of the attribute.
+    fn attributes(&mut self, path: &str, left: &Location, right: &Location) -> Result<()> {
+        let lnames = left.attr_names()?;
+        let rnames = right.attr_names()?;
+        for name in &lnames {
+            let attr_path = format!("{path}@{name}");
+            if !rnames.contains(name) {
+                self.row(
+                    &attr_path,
+                    "removed",
+                    self.nothing(),
+                    self.nothing(),
+                    self.nothing(),
+                );
+                continue;
+            }
+            let (l, r) = (left.attr(name)?, right.attr(name)?);
+            if self.mismatch(&attr_path, &l, &r)? {
+                continue;
+            }
+            let lval = read_data(self.left, &l, self.span)?;
+            let rval = read_data(self.right, &r, self.span)?;
+            self.values(&attr_path, lval, rval, 0);
+        }
+        for name in rnames.iter().filter(|name| !lnames.contains(name)) {
+            self.row(
+                &format!("{path}@{name}"),
+                "added",
+                self.nothing(),
+                self.nothing(),
+                self.nothing(),
+            );
+        }
+        Ok(())
+    }
+
+    fn diff(&mut self) -> Result<()> {
+        let mut left = BTreeMap::new();
+        objects(self.left, &mut vec![], &mut left)?;
+        let mut right = BTreeMap::new();
+        objects(self.right, &mut vec![], &mut right)?;
+        self.attributes("/", self.left, self.right)?;
+        for (path, l) in &left {
+            match (l, right.get(path)) {
+                (_, None) => self.row(
+                    path,
+                    "removed",
+                    self.nothing(),
+                    self.nothing(),
+                    self.nothing(),
+                ),
+                (Object::Dataset(l), Some(Object::Dataset(r))) => {
+                    self.dataset(path, l, r)?;
+                    self.attributes(path, l, r)?;
+                }
+                (Object::Group(l), Some(Object::Group(r))) => self.attributes(path, l, r)?,
+                (l, Some(r)) => {
+                    let (l, r) = (self.string(kind(l).into()), self.string(kind(r).into()));
+                    self.row(path, "type", self.nothing(), l, r);
+                }
+            }
+        }
+        for path in right.keys().filter(|path| !left.contains_key(*path)) {
+            self.row(
+                path,
+                "added",
+                self.nothing(),
+                self.nothing(),
+                self.nothing(),
+            );
+        }
+        Ok(())
+    }
+}
+
+fn kind(object: &Object) -> &'static str {
+    match object {
+        Object::Group(_) => "group",
+        Object::Dataset(_) => "dataset",
+    }
+}
+
+/// The elements of nested lists with their indices.
+fn elements(value: Value, index: &mut Vec<usize>, out: &mut Vec<(Vec<usize>, Value)>) {
+    match value {
+        Value::List { vals, .. } => {
+            for (i, val) in vals.into_iter().enumerate() {
+                index.push(i);
+                elements(val, index, out);
+                index.pop();
+            }
+        }
+        value => out.push((index.clone(), value)),
+    }
+}
+
+/// Numbers are the same within the tolerance, and other values when equal.
+fn same(left: &Value, right: &Value, tolerance: f64) -> bool {
+    match (left, right) {
+        (Value::Int { val: l, .. }, Value::Int { val: r, .. }) => {
+            l.abs_diff(*r) as f64 <= tolerance
+        }
+        _ => match (left.as_float(), right.as_float()) {
+            (Ok(l), Ok(r)) => (l - r).abs() <= tolerance || (l.is_nan() && r.is_nan()),
+            _ => left == right,
+        },
+    }
+}
+
+fn diff(bytes: &[u8], other: &[u8], tolerance: f64, span: Span) -> Result<Value> {
+    let left = FileImage::new(bytes)?;
+    let right = FileImage::new(other)?;
+    let mut differ = Differ {
+        left: &left,
+        right: &right,
+        tolerance,
+        span,
+        rows: vec![],
+    };
+    differ.diff()?;
+    Ok(Value::List {
+        vals: differ.rows,
+        internal_span: span,
+    })
+}
+
+pub fn signature() -> Signature {
+    Signature::build("hdf5 diff")
+        .description(
+            "Compare HDF5 binary with another HDF5 file, object by object, attribute by attribute and element by element",
+        )
+        .allow_variants_without_examples(true)
+        .input_output_types(vec![(Type::Binary, Type::table())])
+        .category(Category::Experimental)
+        .required_named(
+            "with",
+            SyntaxShape::Filepath,
+            "the file to compare the input with",
+            Some('w'),
+        )
+        .named(
+            "tolerance",
+            SyntaxShape::Number,
+            "the largest difference of numbers that are still the same, 0 by default",
+            Some('t'),
+        )
+        .filter()
+}
+
+#[allow(clippy::result_large_err)]
+pub fn run(
+    engine: &EngineInterface,
+    call: &EvaluatedCall,
+    input: PipelineData,
+) -> Result<PipelineData, LabeledError> {
+    let Some(with) = call.get_flag::<Spanned<String>>("with")? else {
+        return Err(ShellError::MissingParameter {
+            param_name: "with".to_string(),
+            span: call.head,
+        }
+        .into());
+    };
+    let tolerance: f64 = call.get_flag("tolerance")?.unwrap_or(0.0);
+    let path = Path::new(&engine.get_current_dir()?).join(&with.item);
+    let other = std::fs::read(&path).map_err(|e| {
+        LabeledError::new(e.to_string())
+            .with_label(format!("while reading {}", path.display()), with.span)
+    })?;
+    with_binary_input(input, |bytes, span| diff(bytes, &other, tolerance, span))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_hdf5::tests::image;
+    use nu_protocol::record;
+
+    #[test]
+    fn compares_elements_and_attributes() {
+        fn build(file: &File, x: &[i32], scale: i32, attr: &str) {
+            let ds = file.new_dataset_builder().with_data(x).create("x").unwrap();
+            for name in ["scale", attr] {
+                ds.new_attr::<i32>()
+                    .create(name)
+                    .unwrap()
+                    .write_scalar(&scale)
+                    .unwrap();
+            }
+        }
+        let left = image("compares_left.h5", |file| build(file, &[1, 2, 3], 1, "old"));
+        let right = image("compares_right.h5", |file| {
+            build(file, &[1, 5, 3], 2, "new")
+        });
+        let row = |path: &str, change: &str, index: Value, left: Value, right: Value| {
+            Value::test_record(record! {
+                "path" => Value::test_string(path),
+                "change" => Value::test_string(change),
+                "index" => index,
+                "left" => left,
+                "right" => right,
+            })
+        };
+        let nothing = Value::test_nothing;
+        assert_eq!(
+            diff(&left, &right, 0.0, Span::test_data()).unwrap(),
+            Value::test_list(vec![
+                row(
+                    "/x",
+                    "value",
+                    Value::test_list(vec![Value::test_int(1)]),
+                    Value::test_int(2),
+                    Value::test_int(5),
+                ),
+                row("/x@old", "removed", nothing(), nothing(), nothing()),
+                row(
+                    "/x@scale",
+                    "value",
+                    Value::test_list(vec![]),
+                    Value::test_int(1),
+                    Value::test_int(2),
+                ),
+                row("/x@new", "added", nothing(), nothing(), nothing()),
+            ])
+        );
+    }
+
+    #[test]
+    fn stops_at_link_cycles() {
+        fn build(file: &File) {
+            file.create_group("a").unwrap();
+            file.link_hard("/a", "/a/self").unwrap();
+        }
+        let left = image("cycles_left.h5", build);
+        let right = image("cycles_right.h5", build);
+        assert_eq!(
+            diff(&left, &right, 0.0, Span::test_data()).unwrap(),
+            Value::test_list(vec![])
+        );
+    }
+
+    #[test]
+    fn compares_numbers_within_the_tolerance() {
+        let int = Value::test_int;
+        let float = Value::test_float;
+        assert!(same(&int(1), &int(1), 0.0));
+        assert!(!same(&int(1), &int(2), 0.0));
+        assert!(same(&int(1), &int(2), 1.0));
+        // Without overflow at the ends of the range.
+        assert!(!same(&int(i64::MIN), &int(i64::MAX), 0.0));
+        assert!(same(&float(1.0), &float(1.05), 0.1));
+        assert!(!same(&float(1.0), &float(1.5), 0.1));
+        assert!(same(&float(f64::NAN), &float(f64::NAN), 0.0));
+        assert!(!same(&float(f64::NAN), &float(1.0), 0.0));
+    }
+
+    #[test]
+    fn compares_other_values_exactly() {
+        let string = Value::test_string;
+        assert!(same(&string("a"), &string("a"), 1.0));
+        assert!(!same(&string("a"), &string("b"), 1.0));
+        assert!(!same(&string("1"), &Value::test_int(1), 1.0));
+    }
+}
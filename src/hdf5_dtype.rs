@@ -0,0 +1,95 @@
+use crate::{
+    dtype::Dtype,
+    from_hdf5::with_binary_input,
+    hdf5_ext::{FileImage, NativeType},
+};
+use hdf5::Result;
+use nu_plugin::EvaluatedCall;
+use nu_protocol::{
+    Category, LabeledError, PipelineData, Record, Signature, Span, SyntaxShape, Type, Value,
+};
+
+/// Describes a datatype as `{type, size}`, with the `members` of compounds
+/// laid out by name, byte offset, size and type.
+pub fn describe(dtype: &Dtype, span: Span) -> Value {
+    let (cols, vals) = describe_cols(dtype, span);
+    to_record(cols, vals, span)
+}
+
+fn describe_cols(dtype: &Dtype, span: Span) -> (Vec<String>, Vec<Value>) {
+    let mut cols = vec!["type".to_string(), "size".to_string()];
+    let mut vals = vec![
+        Value::String {
+            val: dtype.to_string(),
+            internal_span: span,
+        },
+        Value::Int {
+            val: dtype.size() as _,
+            internal_span: span,
+        },
+    ];
+    if let Dtype::Compound(comp) = dtype {
+        let members = comp
+            .fields
+            .iter()
+            .map(|field| {
+                let (ty_cols, ty_vals) = describe_cols(&field.ty, span);
+                let cols = ["name".to_string(), "offset".to_string()]
+                    .into_iter()
+                    .chain(ty_cols)
+                    .collect();
+                let vals = [
+                    Value::String {
+                        val: field.name.clone(),
+                        internal_span: span,
+                    },
+                    Value::Int {
+                        val: field.offset as _,
+                        internal_span: span,
+                    },
+                ]
+                .into_iter()
+                .chain(ty_vals)
+                .collect();
+                to_record(cols, vals, span)
+            })
+            .collect();
+        cols.push("members".to_string());
+        vals.push(Value::List {
+            vals: members,
+            internal_span: span,
+        });
+    }
+    (cols, vals)
+}
+
+fn to_record(cols: Vec<String>, vals: Vec<Value>, span: Span) -> Value {
+    Value::Record {
+        val: Record::from_raw_cols_vals(cols, vals, span, span)
+            .unwrap()
+            .into(),
+        internal_span: span,
+    }
+}
+
+fn dtype_of(bytes: &[u8], path: &str, span: Span) -> Result<Value> {
+    let file = FileImage::new(bytes)?;
+    let dtype = Dtype::from_datatype(&file.dataset(path)?.dtype()?.to_native()?)?;
+    Ok(describe(&dtype, span))
+}
+
+pub fn signature() -> Signature {
+    Signature::build("hdf5 dtype")
+        .description("Describe the datatype of a dataset in HDF5 binary")
+        .allow_variants_without_examples(true)
+        .input_output_types(vec![(Type::Binary, Type::Record(vec![].into()))])
+        .category(Category::Experimental)
+        .required("path", SyntaxShape::String, "path of the dataset")
+        .filter()
+}
+
+#[allow(clippy::result_large_err)]
+pub fn run(call: &EvaluatedCall, input: PipelineData) -> Result<PipelineData, LabeledError> {
+    let path: String = call.req(0)?;
+    with_binary_input(input, |bytes, span| dtype_of(bytes, &path, span))
+}
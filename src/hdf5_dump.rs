@@ -0,0 +1,351 @@
+use crate::{
+    dtype::{Dtype, StrPad},
+    from_hdf5::{read_data, with_binary_input},
+    hdf5_ext::{FileImage, ReadRawBytes},
+};
+use hdf5::{
+    h5lock, types::FloatSize, Container, Dataset, File, Group, Location, LocationToken,
+    LocationType, Result,
+};
+use hdf5_sys::h5t::{H5T_order_t, H5Tget_order};
+use nu_plugin::EvaluatedCall;
+use nu_protocol::{
+    Category, LabeledError, PipelineData, Signature, Span, SyntaxShape, Type, Value,
+};
+use std::{fmt::Write, ops::Deref};
+
+/// Writes the text of `h5dump`, a level of indent being three spaces.
+struct Dumper<'a> {
+    file: &'a File,
+    header_only: bool,
+    span: Span,
+    out: String,
+    /// The paths the objects rendered were first met at, by file and address.
+    seen: Vec<((u64, LocationToken), String)>,
+}
+
+impl Dumper<'_> {
+    fn line(&mut self, depth: usize, text: &str) {
+        writeln!(self.out, "{:width$}{text}", "", width = depth * 3).unwrap();
+    }
+
+    /// Renders `HARDLINK` to the first path of an object met before, as
+    /// h5dump does, instead of it again.
+    fn hardlink(&mut self, location: &Location, depth: usize) -> Result<bool> {
+        let info = location.loc_info()?;
+        let id = (info.fileno, info.token);
+        match self.seen.iter().find(|(seen, _)| *seen == id) {
+            Some((_, path)) => {
+                let text = format!("HARDLINK {}", ddl_quote(path));
+                self.line(depth, &text);
+                Ok(true)
+            }
+            None => {
+                self.seen.push((id, location.name()));
+                Ok(false)
+            }
+        }
+    }
+
+    fn group(&mut self, group: &Group, name: &str, depth: usize) -> Result<()> {
+        self.line(depth, &format!("GROUP \"{name}\" {{"));
+        if self.hardlink(group, depth + 1)? {
+            self.line(depth, "}");
+            return Ok(());
+        }
+        self.attributes(group, depth + 1)?;
+        // In the order of names, datasets and groups alike, as h5dump does.
+        for name in group.member_names()? {
+            match group.loc_type_by_name(&name)? {
+                LocationType::Dataset => self.dataset(&group.dataset(&name)?, depth + 1)?,
+                LocationType::Group => self.group(&group.group(&name)?, &name, depth + 1)?,
+                _ => {}
+            }
+        }
+        self.line(depth, "}");
+        Ok(())
+    }
+
+    fn dataset(&mut self, ds: &Dataset, depth: usize) -> Result<()> {
+        let name = ds.name();
+        let name = name.rsplit('/').next().unwrap_or_default();
+        self.line(depth, &format!("DATASET \"{name}\" {{"));
+        if self.hardlink(ds, depth + 1)? {
+            self.line(depth, "}");
+            return Ok(());
+        }
+        self.container(ds, depth + 1)?;
+        self.attributes(ds, depth + 1)?;
+        self.line(depth, "}");
+        Ok(())
+    }
+
+    fn attributes(&mut self, location: &Location, depth: usize) -> Result<()> {
+        for name in location.attr_names()? {
+            let attr = location.attr(&name)?;
+            self.line(depth, &format!("ATTRIBUTE \"{name}\" {{"));
+            self.container(&attr, depth + 1)?;
+            self.line(depth, "}");
+        }
+        Ok(())
+    }
+
+    /// The datatype, the dataspace and the data of a dataset or an attribute.
+    fn container<C>(&mut self, container: &C, depth: usize) -> Result<()>
+    where
+        C: Deref<Target = Container> + ReadRawBytes,
+    {
+        let dtype = container.dtype()?;
+        let little = h5lock!(H5Tget_order(dtype.id())) != H5T_order_t::H5T_ORDER_BE;
+        let ddl = ddl_type(&Dtype::from_datatype(&dtype)?, little);
+        self.line(depth, &format!("DATATYPE  {ddl}"));
+        let space = container.space()?;
+        let ddl = if space.is_null() {
+            "NULL".to_string()
+        } else if space.is_scalar() {
+            "SCALAR".to_string()
+        } else {
+            let dims: Vec<String> = space.shape().iter().map(|d| d.to_string()).collect();
+            let maxdims: Vec<String> = space
+                .maxdims()
+                .iter()
+                .map(|d| d.map_or("H5S_UNLIMITED".to_string(), |d| d.to_string()))
+                .collect();
+            format!(
+                "SIMPLE {{ ( {} ) / ( {} ) }}",
+                dims.join(", "),
+                maxdims.join(", ")
+            )
+        };
+        self.line(depth, &format!("DATASPACE  {ddl}"));
+        if self.header_only || space.is_null() {
+            return Ok(());
+        }
+        let data = read_data(self.file, container, self.span)?;
+        let shape = container.shape();
+        self.line(depth, "DATA {");
+        // A row for each run of the last dimension, led by the index of its
+        // first element.
+        let mut elements = vec![];
+        flatten(data, &mut elements);
+        let run = shape.last().copied().unwrap_or(1).max(1);
+        for (row, chunk) in elements.chunks(run).enumerate() {
+            let mut index = vec![0; shape.len().max(1)];
+            let mut rest = row * run;
+            for (i, &n) in shape.iter().enumerate().rev() {
+                index[i] = rest % n.max(1);
+                rest /= n.max(1);
+            }
+            let index: Vec<String> = index.iter().map(|i| i.to_string()).collect();
+            self.line(
+                depth,
+                &format!("({}): {}", index.join(","), chunk.join(", ")),
+            );
+        }
+        self.line(depth, "}");
+        Ok(())
+    }
+}
+
+/// The name of a datatype in DDL, e.g. `H5T_STD_I32LE`.
+fn ddl_type(dtype: &Dtype, little: bool) -> String {
+    let order = if little { "LE" } else { "BE" };
+    match dtype {
+        Dtype::Integer(size) => format!("H5T_STD_I{}{order}", *size as usize * 8),
+        Dtype::Unsigned(size) => format!("H5T_STD_U{}{order}", *size as usize * 8),
+        Dtype::Float(FloatSize::U4) => format!("H5T_IEEE_F32{order}"),
+        Dtype::Float(FloatSize::U8) => format!("H5T_IEEE_F64{order}"),
+        Dtype::Half => format!("H5T_IEEE_F16{order}"),
+        Dtype::FixedAscii(len, pad) => ddl_string(&len.to_string(), *pad, "H5T_CSET_ASCII"),
+        Dtype::FixedUnicode(len, pad) => ddl_string(&len.to_string(), *pad, "H5T_CSET_UTF8"),
+        Dtype::VarLenAscii => ddl_string("H5T_VARIABLE", StrPad::NullTerm, "H5T_CSET_ASCII"),
+        Dtype::VarLenUnicode => ddl_string("H5T_VARIABLE", StrPad::NullTerm, "H5T_CSET_UTF8"),
+        Dtype::Compound(comp) => {
+            let fields: Vec<String> = comp
+                .fields
+                .iter()
+                .map(|field| format!("{} \"{}\";", ddl_type(&field.ty, little), field.name))
+                .collect();
+            format!("H5T_COMPOUND {{ {} }}", fields.join(" "))
+        }
+        Dtype::FixedArray(ty, len) => format!("H5T_ARRAY {{ [{len}] {} }}", ddl_type(ty, little)),
+        Dtype::VarLenArray(ty) => format!("H5T_VLEN {{ {} }}", ddl_type(ty, little)),
+        Dtype::ObjectReference => "H5T_REFERENCE { H5T_STD_REF_OBJECT }".to_string(),
+        Dtype::RegionReference => "H5T_REFERENCE { H5T_STD_REF_DSETREG }".to_string(),
+        dtype => dtype.to_string(),
+    }
+}
+
+fn ddl_string(size: &str, pad: StrPad, cset: &str) -> String {
+    let pad = match pad {
+        StrPad::NullTerm => "H5T_STR_NULLTERM",
+        StrPad::NullPad => "H5T_STR_NULLPAD",
+        StrPad::SpacePad => "H5T_STR_SPACEPAD",
+    };
+    format!("H5T_STRING {{ STRSIZE {size}; STRPAD {pad}; CSET {cset}; CTYPE H5T_C_S1; }}")
+}
+
+/// A string quoted as h5dump does, with C escapes of quotes, backslashes and
+/// the common control characters, and octal ones of other unprintable bytes.
+fn ddl_quote(s: &str) -> String {
+    let mut quoted = "\"".to_string();
+    for &b in s.as_bytes() {
+        match b {
+            b'"' => quoted.push_str("\\\""),
+            b'\\' => quoted.push_str("\\\\"),
+            0x08 => quoted.push_str("\\b"),
+            0x0c => quoted.push_str("\\f"),
+            b'\n' => quoted.push_str("\\n"),
+            b'\r' => quoted.push_str("\\r"),
+            b'\t' => quoted.push_str("\\t"),
+            b' '..=b'~' => quoted.push(b as char),
+            b => write!(quoted, "\\{b:03o}").unwrap(),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The elements of nested lists, as text.
+fn flatten(value: Value, elements: &mut Vec<String>) {
+    match value {
+        Value::List { vals, .. } => {
+            for val in vals {
+                flatten(val, elements);
+            }
+        }
+        value => elements.push(ddl_value(&value)),
+    }
+}
+
+fn ddl_value(value: &Value) -> String {
+    match value {
+        Value::String { val, .. } => ddl_quote(val),
+        Value::Record { val, .. } => {
+            let vals: Vec<String> = val.values().map(ddl_value).collect();
+            format!("{{ {} }}", vals.join(", "))
+        }
+        Value::List { vals, .. } => {
+            let vals: Vec<String> = vals.iter().map(ddl_value).collect();
+            format!("[ {} ]", vals.join(", "))
+        }
+        Value::Binary { val, .. } => val.iter().map(|b| format!("{b:02x}")).collect(),
+        Value::Nothing { .. } => "NULL".to_string(),
+        value => value.to_abbreviated_string(&Default::default()),
+    }
+}
+
+fn dump(bytes: &[u8], path: Option<&str>, header_only: bool, span: Span) -> Result<Value> {
+    let file = FileImage::new(bytes)?;
+    let mut dumper = Dumper {
+        file: &file,
+        header_only,
+        span,
+        out: String::new(),
+        seen: vec![],
+    };
+    dumper.line(0, "HDF5 {");
+    match path.map(|p| p.trim_matches('/')) {
+        None | Some("") => dumper.group(&file, "/", 0)?,
+        Some(path) => match file.dataset(path) {
+            Ok(ds) => dumper.dataset(&ds, 0)?,
+            Err(_) => dumper.group(&file.group(path)?, &format!("/{path}"), 0)?,
+        },
+    }
+    dumper.line(0, "}");
+    Ok(Value::String {
+        val: dumper.out,
+        internal_span: span,
+    })
+}
+
+pub fn signature() -> Signature {
+    Signature::build("hdf5 dump")
+        .description("Render HDF5 binary as the DDL text of h5dump")
+        .allow_variants_without_examples(true)
+        .input_output_types(vec![(Type::Binary, Type::String)])
+        .category(Category::Experimental)
+        .optional(
+            "path",
+            SyntaxShape::String,
+            "path of the group or dataset to render, defaults to the root group",
+        )
+        .switch(
+            "header",
+            "render only the datatypes and dataspaces, without the data",
+            Some('H'),
+        )
+        .filter()
+}
+
+#[allow(clippy::result_large_err)]
+pub fn run(call: &EvaluatedCall, input: PipelineData) -> Result<PipelineData, LabeledError> {
+    let path: Option<String> = call.opt(0)?;
+    let header_only = call.has_flag("header")?;
+    with_binary_input(input, |bytes, span| {
+        dump(bytes, path.as_deref(), header_only, span)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_hdf5::tests::image;
+
+    #[test]
+    fn quotes_strings_as_h5dump_does() {
+        assert_eq!(ddl_quote("plain"), r#""plain""#);
+        assert_eq!(ddl_quote("a \"b\" \\c"), r#""a \"b\" \\c""#);
+        assert_eq!(ddl_quote("1\n2\t3\r\x08\x0c"), r#""1\n2\t3\r\b\f""#);
+        assert_eq!(ddl_quote("\x01é"), r#""\001\303\251""#);
+    }
+
+    #[test]
+    fn names_the_padding_of_strings() {
+        assert_eq!(
+            ddl_type(&Dtype::FixedAscii(4, StrPad::SpacePad), true),
+            "H5T_STRING { STRSIZE 4; STRPAD H5T_STR_SPACEPAD; CSET H5T_CSET_ASCII; CTYPE H5T_C_S1; }"
+        );
+        assert_eq!(
+            ddl_type(&Dtype::FixedUnicode(2, StrPad::NullPad), true),
+            "H5T_STRING { STRSIZE 2; STRPAD H5T_STR_NULLPAD; CSET H5T_CSET_UTF8; CTYPE H5T_C_S1; }"
+        );
+    }
+
+    #[test]
+    fn interleaves_members_in_name_order() {
+        let bytes = image("interleaves_members_in_name_order.h5", |file| {
+            file.create_group("b").unwrap();
+            for name in ["a", "c"] {
+                file.new_dataset_builder()
+                    .with_data(&[1i32])
+                    .create(name)
+                    .unwrap();
+            }
+        });
+        let out = dump(&bytes, None, true, Span::test_data()).unwrap();
+        let out = out.as_str().unwrap();
+        let a = out.find("DATASET \"a\"").unwrap();
+        let b = out.find("GROUP \"b\"").unwrap();
+        let c = out.find("DATASET \"c\"").unwrap();
+        assert!(a < b && b < c);
+    }
+
+    #[test]
+    fn renders_objects_met_before_as_hardlinks() {
+        let bytes = image("renders_objects_met_before_as_hardlinks.h5", |file| {
+            let group = file.create_group("a").unwrap();
+            group
+                .new_dataset_builder()
+                .with_data(&[1i32])
+                .create("x")
+                .unwrap();
+            file.link_hard("/a", "/a/self").unwrap();
+            file.link_hard("/a/x", "/y").unwrap();
+        });
+        let out = dump(&bytes, None, true, Span::test_data()).unwrap();
+        let out = out.as_str().unwrap();
+        assert!(out.contains("      GROUP \"self\" {\n         HARDLINK \"/a\"\n      }\n"));
+        assert!(out.contains("   DATASET \"y\" {\n      HARDLINK \"/a/x\"\n   }\n"));
+    }
+}
@@ -1,7 +1,36 @@
 use core::ffi::{c_size_t, c_uint, c_void};
-use hdf5::{h5call, types::TypeDescriptor, Dataset, Datatype, File, Result};
-use hdf5_sys::{h5d::H5Dread, h5i::hid_t, h5p::H5P_DEFAULT, h5s::H5S_ALL};
-use std::{marker::PhantomData, ops::Deref};
+use hdf5::{
+    from_id, h5call, h5lock,
+    plist::{DatasetAccess, DatasetCreate, FileAccess, PropertyList},
+    Attribute, Dataset, Dataspace, Datatype, File, Group, Hyperslab, Location, Result,
+    SliceOrIndex,
+};
+use hdf5_sys::{
+    h5a::H5Aread,
+    h5d::{H5D_alloc_time_t, H5D_fill_value_t, H5Dopen2, H5Dread, H5Drefresh},
+    h5f::{
+        H5F_info2_t, H5F_libver_t, H5Fget_file_image, H5Fget_info2, H5Fget_obj_count,
+        H5Fget_obj_ids, H5Fopen, H5F_ACC_RDONLY, H5F_ACC_SWMR_READ, H5F_OBJ_ALL,
+    },
+    h5i::{hid_t, H5Idec_ref, H5Iis_valid},
+    h5l::{H5L_info1_t, H5Lget_val, H5Lunpack_elink_val},
+    h5o::H5Oopen,
+    h5p::{
+        H5Pcreate, H5Pfill_value_defined, H5Pget_alloc_time, H5Pget_fill_value,
+        H5Pget_libver_bounds, H5Pset_elink_prefix, H5Pset_virtual_prefix, H5P_DEFAULT,
+    },
+    h5r::{hdset_reg_ref_t, hobj_ref_t, H5R_type_t, H5Rdereference2, H5Rget_region},
+    h5s::{H5Sget_select_bounds, H5S_ALL},
+    h5t::{
+        H5T_class_t, H5T_direction_t, H5Tcreate, H5Tdetect_class, H5Tget_class,
+        H5Tget_member_index, H5Tget_member_type, H5Tget_native_type, H5Tinsert, H5Topen2,
+    },
+};
+use std::{
+    ffi::{c_char, CStr, CString},
+    ops::Deref,
+    path::Path,
+};
 
 const H5LT_FILE_IMAGE_DONT_COPY: c_uint = 0x2;
 const H5LT_FILE_IMAGE_DONT_RELEASE: c_uint = 0x4;
@@ -11,25 +40,198 @@ extern "C" {
     fn H5LTopen_file_image(buf_ptr: *mut c_void, buf_size: c_size_t, flags: c_uint) -> hid_t;
 }
 
-#[derive(Clone)]
+/// What the image of a file is read from, kept alive as long as the file.
+enum Backing<'a> {
+    /// Bytes the file is opened over without a copy.
+    Borrowed(&'a [u8]),
+    /// Owned by HDF5, e.g. a file it opened itself or a copy of an image.
+    Owned,
+    #[cfg(unix)]
+    Mapped(Mmap),
+}
+
 pub struct FileImage<'a> {
+    // Declared first, so that the file is closed before the backing is dropped.
     file: File,
-    _p: PhantomData<&'a [u8]>,
+    backing: Backing<'a>,
 }
 
 impl<'a> FileImage<'a> {
     pub fn new(bytes: &'a [u8]) -> Result<Self> {
-        let hid = h5call!(H5LTopen_file_image(
-            bytes.as_ptr() as *const c_void as _,
-            bytes.len(),
-            H5LT_FILE_IMAGE_DONT_COPY | H5LT_FILE_IMAGE_DONT_RELEASE
-        ))?;
-        let file: File = unsafe { std::mem::transmute(hid) };
         Ok(Self {
-            file,
-            _p: PhantomData,
+            file: open_image(bytes)?,
+            backing: Backing::Borrowed(bytes),
         })
     }
+
+    /// The bytes the file is read from, unless HDF5 owns them.
+    pub fn bytes(&self) -> Option<&[u8]> {
+        match &self.backing {
+            Backing::Borrowed(bytes) => Some(bytes),
+            Backing::Owned => None,
+            #[cfg(unix)]
+            Backing::Mapped(mmap) => Some(mmap),
+        }
+    }
+}
+
+impl FileImage<'static> {
+    /// Maps the file read-only and opens the mapping, instead of reading the
+    /// whole file into memory.
+    #[cfg(unix)]
+    pub fn map(file: &std::fs::File) -> Result<Self> {
+        let mmap = Mmap::new(file)?;
+        Ok(Self {
+            file: open_image(&mmap)?,
+            backing: Backing::Mapped(mmap),
+        })
+    }
+}
+
+impl Drop for FileImage<'_> {
+    fn drop(&mut self) {
+        // Objects opened from the file, e.g. its datasets, would keep it open
+        // in HDF5 after the bytes are gone. They're closed with it, so that
+        // reading any of them fails instead.
+        if self.bytes().is_some() {
+            close_all(&self.file);
+        }
+    }
+}
+
+/// Closes every identifier open in the file, however many references it has.
+fn close_all(file: &File) {
+    h5lock!({
+        let count = H5Fget_obj_count(file.id(), H5F_OBJ_ALL);
+        if count <= 0 {
+            return;
+        }
+        let mut ids = vec![0 as hid_t; count as usize];
+        let count = H5Fget_obj_ids(file.id(), H5F_OBJ_ALL, ids.len(), ids.as_mut_ptr());
+        for &id in ids.iter().take(count.max(0) as usize) {
+            while H5Iis_valid(id) > 0 && H5Idec_ref(id) > 0 {}
+        }
+    })
+}
+
+impl From<File> for FileImage<'static> {
+    fn from(file: File) -> Self {
+        Self {
+            file,
+            backing: Backing::Owned,
+        }
+    }
+}
+
+/// Opens the image without copying it, so the bytes must outlive the file.
+fn open_image(bytes: &[u8]) -> Result<File> {
+    open_image_with(
+        bytes,
+        H5LT_FILE_IMAGE_DONT_COPY | H5LT_FILE_IMAGE_DONT_RELEASE,
+    )
+}
+
+/// The signature at the start of the superblock.
+const HDF5_SIGNATURE: &[u8] = b"\x89HDF\r\n\x1a\n";
+
+/// Whether the superblock signature is at 0, or after a user block of 512
+/// bytes or any power of two times that.
+fn has_signature(bytes: &[u8]) -> bool {
+    std::iter::once(0)
+        .chain(std::iter::successors(Some(512usize), |n| n.checked_mul(2)))
+        .take_while(|&offset| offset + HDF5_SIGNATURE.len() <= bytes.len())
+        .any(|offset| bytes[offset..].starts_with(HDF5_SIGNATURE))
+}
+
+fn open_image_with(bytes: &[u8], flags: c_uint) -> Result<File> {
+    if !has_signature(bytes) {
+        return Err("input is not an HDF5 file".into());
+    }
+    let hid = h5call!(H5LTopen_file_image(
+        bytes.as_ptr() as *const c_void as _,
+        bytes.len(),
+        flags
+    ))?;
+    // Takes over the id, which is closed when the file is dropped.
+    let file: File = unsafe { from_id(hid)? };
+    debug_assert!(file.is_valid());
+    Ok(file)
+}
+
+/// A read-only mapping of a whole file.
+#[cfg(unix)]
+struct Mmap {
+    ptr: *mut c_void,
+    len: usize,
+}
+
+// The mapping is read-only, and only unmapped on drop.
+#[cfg(unix)]
+unsafe impl Send for Mmap {}
+#[cfg(unix)]
+unsafe impl Sync for Mmap {}
+
+#[cfg(unix)]
+impl Mmap {
+    fn new(file: &std::fs::File) -> Result<Self> {
+        use std::os::fd::AsRawFd;
+
+        let len = file.metadata().map_err(|e| e.to_string())?.len() as usize;
+        if len == 0 {
+            return Err("cannot map an empty file".into());
+        }
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error().to_string().into());
+        }
+        Ok(Self { ptr, len })
+    }
+}
+
+#[cfg(unix)]
+impl Deref for Mmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr, self.len);
+        }
+    }
+}
+
+/// Opens a copy of the image, which doesn't borrow the bytes.
+pub fn open_image_copy(bytes: &[u8]) -> Result<File> {
+    open_image_with(bytes, 0)
+}
+
+/// Opens a file being written with SWMR for reading, so that the writer may
+/// go on appending while it's read.
+pub fn open_swmr(path: &Path, fapl: &FileAccess) -> Result<File> {
+    let path = path.to_str().ok_or("path is not valid UTF-8")?;
+    let path = CString::new(path).map_err(|e| e.to_string())?;
+    unsafe {
+        from_id(h5call!(H5Fopen(
+            path.as_ptr(),
+            H5F_ACC_RDONLY | H5F_ACC_SWMR_READ,
+            fapl.id()
+        ))?)
+    }
 }
 
 impl Deref for FileImage<'_> {
@@ -40,20 +242,412 @@ impl Deref for FileImage<'_> {
     }
 }
 
+pub trait ToImage {
+    /// Copies out the image of a file, e.g. one kept in memory by the core driver.
+    fn to_image(&self) -> Result<Vec<u8>>;
+}
+
+impl ToImage for File {
+    fn to_image(&self) -> Result<Vec<u8>> {
+        self.flush()?;
+        let len = h5call!(H5Fget_file_image(self.id(), std::ptr::null_mut(), 0))? as usize;
+        let mut buffer = Vec::with_capacity(len);
+        h5call!(H5Fget_file_image(
+            self.id(),
+            buffer.spare_capacity_mut().as_mut_ptr() as *mut _,
+            len
+        ))?;
+        unsafe {
+            buffer.set_len(len);
+        }
+        Ok(buffer)
+    }
+}
+
+pub trait ExternalLink {
+    /// The file and the object path an external link points to.
+    fn external_link(&self, name: &str) -> Result<(String, String)>;
+
+    /// Opens the object an external link points to, looking for its file
+    /// with a relative path under the prefix first.
+    fn open_external(&self, name: &str, prefix: &str) -> Result<Location>;
+
+    /// The path a soft link points to.
+    fn soft_link(&self, name: &str) -> Result<String>;
+}
+
+/// The value of a soft or external link, which is where it points to.
+fn link_val(group: &Group, name: &CStr) -> Result<Vec<u8>> {
+    let mut info = H5L_info1_t::default();
+    // The only one with the size of the link value in all versions.
+    #[allow(deprecated)]
+    h5call!(hdf5_sys::h5l::H5Lget_info1(
+        group.id(),
+        name.as_ptr(),
+        &mut info,
+        H5P_DEFAULT
+    ))?;
+    let size = unsafe { *info.u.val_size() };
+    let mut buffer = vec![0u8; size];
+    h5call!(H5Lget_val(
+        group.id(),
+        name.as_ptr(),
+        buffer.as_mut_ptr().cast(),
+        size,
+        H5P_DEFAULT
+    ))?;
+    Ok(buffer)
+}
+
+impl ExternalLink for Group {
+    fn external_link(&self, name: &str) -> Result<(String, String)> {
+        let name = CString::new(name).map_err(|e| e.to_string())?;
+        let buffer = link_val(self, &name)?;
+        let mut flags = 0;
+        let mut file_name: *const c_char = std::ptr::null();
+        let mut obj_path: *const c_char = std::ptr::null();
+        h5call!(H5Lunpack_elink_val(
+            buffer.as_ptr().cast(),
+            buffer.len(),
+            &mut flags,
+            &mut file_name,
+            &mut obj_path
+        ))?;
+        // Both point into the buffer.
+        let (file_name, obj_path) = unsafe {
+            (
+                CStr::from_ptr(file_name).to_string_lossy().into_owned(),
+                CStr::from_ptr(obj_path).to_string_lossy().into_owned(),
+            )
+        };
+        Ok((file_name, obj_path))
+    }
+
+    fn open_external(&self, name: &str, prefix: &str) -> Result<Location> {
+        let name = CString::new(name).map_err(|e| e.to_string())?;
+        let prefix = CString::new(prefix).map_err(|e| e.to_string())?;
+        let lapl: PropertyList =
+            unsafe { from_id(h5call!(H5Pcreate(*hdf5::globals::H5P_LINK_ACCESS))?) }?;
+        h5call!(H5Pset_elink_prefix(lapl.id(), prefix.as_ptr()))?;
+        unsafe { from_id(h5call!(H5Oopen(self.id(), name.as_ptr(), lapl.id()))?) }
+    }
+
+    fn soft_link(&self, name: &str) -> Result<String> {
+        let name = CString::new(name).map_err(|e| e.to_string())?;
+        let buffer = link_val(self, &name)?;
+        // Null terminated.
+        let target = CStr::from_bytes_until_nul(&buffer).map_err(|e| e.to_string())?;
+        Ok(target.to_string_lossy().into_owned())
+    }
+}
+
+pub trait CommittedType {
+    /// Opens the named datatype committed in a group.
+    fn committed_type(&self, name: &str) -> Result<Datatype>;
+}
+
+impl CommittedType for Group {
+    fn committed_type(&self, name: &str) -> Result<Datatype> {
+        let name = CString::new(name).map_err(|e| e.to_string())?;
+        unsafe { from_id(h5call!(H5Topen2(self.id(), name.as_ptr(), H5P_DEFAULT))?) }
+    }
+}
+
+pub trait NativeType {
+    fn to_native(&self) -> Result<Datatype>;
+}
+
+impl NativeType for Datatype {
+    fn to_native(&self) -> Result<Datatype> {
+        // HDF5 has no native time types to convert to, so they're read as is.
+        if h5lock!(H5Tget_class(self.id())) == H5T_class_t::H5T_TIME {
+            return Ok(self.clone());
+        }
+        // Fits the current native endian and the native member layout.
+        unsafe {
+            from_id(h5call!(H5Tget_native_type(
+                self.id(),
+                H5T_direction_t::H5T_DIR_ASCEND
+            ))?)
+        }
+    }
+}
+
+pub trait SelectMembers {
+    /// A compound of only the named members of this one, in the order given
+    /// and packed, so that the library converts no more than them.
+    fn select_members(&self, names: &[String]) -> Result<Datatype>;
+}
+
+impl SelectMembers for Datatype {
+    fn select_members(&self, names: &[String]) -> Result<Datatype> {
+        let members = names
+            .iter()
+            .map(|name| -> Result<_> {
+                let c_name = CString::new(name.as_str()).map_err(|e| e.to_string())?;
+                let idx = h5lock!(H5Tget_member_index(self.id(), c_name.as_ptr()));
+                if idx < 0 {
+                    return Err(format!("no member {name} in the compound").into());
+                }
+                let ty: Datatype =
+                    unsafe { from_id(h5call!(H5Tget_member_type(self.id(), idx as _))?)? };
+                Ok((c_name, ty))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let size = members.iter().map(|(_, ty)| ty.size()).sum();
+        let compound: Datatype =
+            unsafe { from_id(h5call!(H5Tcreate(H5T_class_t::H5T_COMPOUND, size))?)? };
+        let mut offset = 0;
+        for (name, ty) in &members {
+            h5call!(H5Tinsert(compound.id(), name.as_ptr(), offset, ty.id()))?;
+            offset += ty.size();
+        }
+        Ok(compound)
+    }
+}
+
+pub trait Refresh {
+    /// Reloads the metadata of a dataset, e.g. its extent, which a writer
+    /// with SWMR may have changed since it was opened.
+    fn refresh(&self) -> Result<()>;
+}
+
+impl Refresh for Dataset {
+    fn refresh(&self) -> Result<()> {
+        h5call!(H5Drefresh(self.id()))?;
+        Ok(())
+    }
+}
+
+pub trait VirtualPrefix {
+    /// Reopens a virtual dataset to look for its source files with relative
+    /// paths under the prefix, instead of the directory of its file.
+    fn with_virtual_prefix(&self, prefix: &str) -> Result<Dataset>;
+}
+
+impl VirtualPrefix for Dataset {
+    fn with_virtual_prefix(&self, prefix: &str) -> Result<Dataset> {
+        let prefix = CString::new(prefix).map_err(|e| e.to_string())?;
+        let dapl = DatasetAccess::try_new()?;
+        h5call!(H5Pset_virtual_prefix(dapl.id(), prefix.as_ptr()))?;
+        unsafe { from_id(h5call!(H5Dopen2(self.id(), c".".as_ptr(), dapl.id()))?) }
+    }
+}
+
+pub trait Dereference {
+    fn dereference(&self, reference: hobj_ref_t) -> Result<String>;
+
+    /// The dataset a region reference selects from, and its selection.
+    fn dereference_region(&self, reference: hdset_reg_ref_t) -> Result<(Dataset, Dataspace)>;
+}
+
+impl Dereference for Location {
+    fn dereference(&self, reference: hobj_ref_t) -> Result<String> {
+        let target: Location = unsafe {
+            from_id(h5call!(H5Rdereference2(
+                self.id(),
+                H5P_DEFAULT,
+                H5R_type_t::H5R_OBJECT1,
+                (&reference as *const hobj_ref_t).cast()
+            ))?)
+        }?;
+        Ok(target.name())
+    }
+
+    fn dereference_region(&self, reference: hdset_reg_ref_t) -> Result<(Dataset, Dataspace)> {
+        let target: Dataset = unsafe {
+            from_id(h5call!(H5Rdereference2(
+                self.id(),
+                H5P_DEFAULT,
+                H5R_type_t::H5R_DATASET_REGION1,
+                reference.as_ptr().cast()
+            ))?)
+        }?;
+        let space: Dataspace = unsafe {
+            from_id(h5call!(H5Rget_region(
+                self.id(),
+                H5R_type_t::H5R_DATASET_REGION1,
+                reference.as_ptr().cast()
+            ))?)
+        }?;
+        Ok((target, space))
+    }
+}
+
+pub trait SelectionBounds {
+    /// The first element of the bounding box of the selection, and its
+    /// extent, in every dimension.
+    fn selection_bounds(&self) -> Result<(Vec<usize>, Vec<usize>)>;
+}
+
+impl SelectionBounds for Dataspace {
+    fn selection_bounds(&self) -> Result<(Vec<usize>, Vec<usize>)> {
+        let ndim = self.ndim();
+        let mut start = vec![0; ndim];
+        let mut end = vec![0; ndim];
+        if self.selection_size() > 0 {
+            h5call!(H5Sget_select_bounds(
+                self.id(),
+                start.as_mut_ptr(),
+                end.as_mut_ptr()
+            ))?;
+        }
+        let count = if self.selection_size() > 0 {
+            start
+                .iter()
+                .zip(&end)
+                .map(|(s, e)| (e - s + 1) as usize)
+                .collect()
+        } else {
+            vec![0; ndim]
+        };
+        Ok((start.into_iter().map(|s| s as usize).collect(), count))
+    }
+}
+
+pub trait AllocTime {
+    fn alloc_time_str(&self) -> Result<&'static str>;
+}
+
+impl AllocTime for DatasetCreate {
+    fn alloc_time_str(&self) -> Result<&'static str> {
+        // `DatasetCreate::alloc_time` folds the default into `late`.
+        let mut alloc_time = H5D_alloc_time_t::H5D_ALLOC_TIME_DEFAULT;
+        h5call!(H5Pget_alloc_time(self.id(), &mut alloc_time))?;
+        Ok(match alloc_time {
+            H5D_alloc_time_t::H5D_ALLOC_TIME_EARLY => "early",
+            H5D_alloc_time_t::H5D_ALLOC_TIME_LATE => "late",
+            H5D_alloc_time_t::H5D_ALLOC_TIME_INCR => "incremental",
+            _ => "default",
+        })
+    }
+}
+
+pub trait FillValue {
+    /// The fill value set by the writer, converted to the memory datatype.
+    fn user_fill_value(&self, mem_dtype: &Datatype) -> Result<Option<Vec<u8>>>;
+}
+
+impl FillValue for DatasetCreate {
+    fn user_fill_value(&self, mem_dtype: &Datatype) -> Result<Option<Vec<u8>>> {
+        let mut defined = H5D_fill_value_t::H5D_FILL_VALUE_UNDEFINED;
+        h5call!(H5Pfill_value_defined(self.id(), &mut defined))?;
+        if defined != H5D_fill_value_t::H5D_FILL_VALUE_USER_DEFINED {
+            return Ok(None);
+        }
+        let mut buffer = vec![0u8; mem_dtype.size()];
+        h5call!(H5Pget_fill_value(
+            self.id(),
+            mem_dtype.id(),
+            buffer.as_mut_ptr().cast()
+        ))?;
+        Ok(Some(buffer))
+    }
+}
+
+pub struct FormatInfo {
+    pub superblock_version: u32,
+    pub superblock_size: u64,
+    pub superblock_ext_size: u64,
+    pub libver_low: &'static str,
+    pub libver_high: &'static str,
+}
+
+pub trait Format {
+    fn format_info(&self) -> Result<FormatInfo>;
+}
+
+impl Format for File {
+    fn format_info(&self) -> Result<FormatInfo> {
+        let mut info = H5F_info2_t::default();
+        h5call!(H5Fget_info2(self.id(), &mut info))?;
+        let mut low = H5F_libver_t::H5F_LIBVER_EARLIEST;
+        let mut high = H5F_libver_t::H5F_LIBVER_EARLIEST;
+        h5call!(H5Pget_libver_bounds(
+            self.access_plist()?.id(),
+            &mut low,
+            &mut high
+        ))?;
+        Ok(FormatInfo {
+            superblock_version: info.super_.version,
+            superblock_size: info.super_.super_size,
+            superblock_ext_size: info.super_.super_ext_size,
+            libver_low: libver_str(low),
+            libver_high: libver_str(high),
+        })
+    }
+}
+
+fn libver_str(libver: H5F_libver_t) -> &'static str {
+    // Matched by value, as the newer variants depend on the library version.
+    match libver as i32 {
+        0 => "earliest",
+        1 => "v18",
+        2 => "v110",
+        3 => "v112",
+        4 => "v114",
+        _ => "unknown",
+    }
+}
+
+/// The bytes read with a memory datatype. The variable-length data they point
+/// to are allocated by HDF5, and reclaimed on drop.
+pub struct RawBytes {
+    buffer: Vec<u8>,
+    mem_dtype: Datatype,
+}
+
+impl RawBytes {
+    fn new(buffer: Vec<u8>, mem_dtype: &Datatype) -> Self {
+        Self {
+            buffer,
+            mem_dtype: mem_dtype.clone(),
+        }
+    }
+}
+
+impl Deref for RawBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl Drop for RawBytes {
+    // `H5Treclaim` replaces it only since 1.12.
+    #[allow(deprecated)]
+    fn drop(&mut self) {
+        let id = self.mem_dtype.id();
+        // Also true of variable-length strings, and of compounds and arrays
+        // that nest them.
+        if self.buffer.is_empty() || h5lock!(H5Tdetect_class(id, H5T_class_t::H5T_VLEN)) <= 0 {
+            return;
+        }
+        let len = self.buffer.len() / self.mem_dtype.size();
+        if let Ok(space) = Dataspace::try_new([len]) {
+            h5lock!(hdf5_sys::h5d::H5Dvlen_reclaim(
+                id,
+                space.id(),
+                H5P_DEFAULT,
+                self.buffer.as_mut_ptr().cast()
+            ));
+        }
+    }
+}
+
 pub trait ReadRawBytes {
-    fn read_raw_bytes(&self, dtype: &TypeDescriptor) -> Result<Vec<u8>>;
+    fn read_raw_bytes(&self, mem_dtype: &Datatype) -> Result<RawBytes>;
 }
 
 impl ReadRawBytes for Dataset {
-    fn read_raw_bytes(&self, dtype: &TypeDescriptor) -> Result<Vec<u8>> {
+    fn read_raw_bytes(&self, mem_dtype: &Datatype) -> Result<RawBytes> {
         let len = self.size();
-        let item_size = dtype.size();
+        let item_size = mem_dtype.size();
         let mut buffer = Vec::with_capacity(len * item_size);
-        // Convert again to fit the current native endian.
-        let native_dtype = Datatype::from_descriptor(dtype)?;
         h5call!(H5Dread(
             self.id(),
-            native_dtype.id(),
+            mem_dtype.id(),
             H5S_ALL,
             H5S_ALL,
             H5P_DEFAULT,
@@ -62,6 +656,379 @@ impl ReadRawBytes for Dataset {
         unsafe {
             buffer.set_len(len * item_size);
         }
-        Ok(buffer)
+        Ok(RawBytes::new(buffer, mem_dtype))
+    }
+}
+
+impl ReadRawBytes for Attribute {
+    fn read_raw_bytes(&self, mem_dtype: &Datatype) -> Result<RawBytes> {
+        let len = self.size();
+        let item_size = mem_dtype.size();
+        let mut buffer = Vec::with_capacity(len * item_size);
+        h5call!(H5Aread(
+            self.id(),
+            mem_dtype.id(),
+            buffer.spare_capacity_mut().as_mut_ptr() as *mut _
+        ))?;
+        unsafe {
+            buffer.set_len(len * item_size);
+        }
+        Ok(RawBytes::new(buffer, mem_dtype))
+    }
+}
+
+/// A hyperslab to read, each part given for every dimension or as a single
+/// value for all of them.
+#[derive(Clone, Default)]
+pub struct Selection {
+    pub start: Option<Vec<usize>>,
+    pub count: Option<Vec<usize>>,
+    pub stride: Option<Vec<usize>>,
+    /// At most this many elements along the first dimension.
+    pub head: Option<usize>,
+    /// This many elements along the first dimension skipped, after `start`.
+    pub skip: Option<usize>,
+}
+
+impl Selection {
+    pub fn is_empty(&self) -> bool {
+        self.start.is_none()
+            && self.count.is_none()
+            && self.stride.is_none()
+            && self.head.is_none()
+            && self.skip.is_none()
+    }
+}
+
+/// Spreads a single value over all dimensions.
+fn per_dim(name: &str, values: Option<&[usize]>, ndim: usize) -> Result<Option<Vec<usize>>> {
+    match values {
+        None => Ok(None),
+        Some([k]) => Ok(Some(vec![*k; ndim])),
+        Some(values) if values.len() == ndim => Ok(Some(values.to_vec())),
+        Some(values) => Err(format!(
+            "{} {name}s given for a dataset of {ndim} dimensions",
+            values.len()
+        )
+        .into()),
+    }
+}
+
+pub trait ReadSelection {
+    /// Reads the hyperslab of the selection, returning the shape of what is
+    /// read. The selection must be within the dataset.
+    fn read_raw_bytes_selection(
+        &self,
+        mem_dtype: &Datatype,
+        selection: &Selection,
+    ) -> Result<(RawBytes, Vec<usize>)>;
+}
+
+impl ReadSelection for Dataset {
+    fn read_raw_bytes_selection(
+        &self,
+        mem_dtype: &Datatype,
+        selection: &Selection,
+    ) -> Result<(RawBytes, Vec<usize>)> {
+        let shape = self.shape();
+        let ndim = shape.len();
+        let mut start =
+            per_dim("start", selection.start.as_deref(), ndim)?.unwrap_or_else(|| vec![0; ndim]);
+        // Skipping past the end leaves nothing, as with shorter datasets.
+        if let (Some(skip), Some(first)) = (selection.skip, start.first_mut()) {
+            *first = (*first + skip).min(shape[0]);
+        }
+        let stride =
+            per_dim("stride", selection.stride.as_deref(), ndim)?.unwrap_or_else(|| vec![1; ndim]);
+        let mut count = match per_dim("count", selection.count.as_deref(), ndim)? {
+            Some(count) => count,
+            None => shape
+                .iter()
+                .zip(&start)
+                .zip(&stride)
+                .map(|((&n, &start), &step)| n.saturating_sub(start).div_ceil(step))
+                .collect(),
+        };
+        if let (Some(head), Some(first)) = (selection.head, count.first_mut()) {
+            *first = (*first).min(head);
+        }
+        for (dim, &n) in shape.iter().enumerate() {
+            let (start, count, step) = (start[dim], count[dim], stride[dim]);
+            let fits = if count == 0 {
+                start <= n
+            } else {
+                start + (count - 1) * step < n
+            };
+            if !fits {
+                return Err(format!(
+                    "selection of {count} from {start} by {step} is out of dimension {dim} of size {n}"
+                )
+                .into());
+            }
+        }
+        let len = count.iter().product::<usize>();
+        let item_size = mem_dtype.size();
+        let mut buffer = Vec::with_capacity(len * item_size);
+        if len > 0 {
+            let hyperslab = Hyperslab::from(
+                (0..ndim)
+                    .map(|dim| SliceOrIndex::SliceCount {
+                        start: start[dim],
+                        step: stride[dim],
+                        count: count[dim],
+                        block: 1,
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            let file_space = self.space()?.select(hyperslab)?;
+            let mem_space = Dataspace::try_new(count.as_slice())?;
+            h5call!(H5Dread(
+                self.id(),
+                mem_dtype.id(),
+                mem_space.id(),
+                file_space.id(),
+                H5P_DEFAULT,
+                buffer.spare_capacity_mut().as_mut_ptr() as *mut _
+            ))?;
+            unsafe {
+                buffer.set_len(len * item_size);
+            }
+        }
+        Ok((RawBytes::new(buffer, mem_dtype), count))
+    }
+}
+
+pub trait ReadBlocks {
+    /// Reads the data whole rows along the first dimension at a time, about
+    /// `block` elements of them, so that only a block is in memory at once.
+    fn read_blocks(
+        &self,
+        mem_dtype: &Datatype,
+        block: usize,
+        f: impl FnMut(&[u8]) -> Result<()>,
+    ) -> Result<()>;
+}
+
+impl ReadBlocks for Dataset {
+    fn read_blocks(
+        &self,
+        mem_dtype: &Datatype,
+        block: usize,
+        mut f: impl FnMut(&[u8]) -> Result<()>,
+    ) -> Result<()> {
+        let shape = self.shape();
+        let Some((&rows, rest)) = shape.split_first() else {
+            return f(&self.read_raw_bytes(mem_dtype)?);
+        };
+        let row_size = rest.iter().product::<usize>();
+        if row_size == 0 {
+            return Ok(());
+        }
+        let block = (block / row_size).max(1);
+        for start in (0..rows).step_by(block) {
+            let mut starts = vec![0; shape.len()];
+            starts[0] = start;
+            let mut counts = shape.clone();
+            counts[0] = block.min(rows - start);
+            let selection = Selection {
+                start: Some(starts),
+                count: Some(counts),
+                ..Default::default()
+            };
+            let (data, _) = self.read_raw_bytes_selection(mem_dtype, &selection)?;
+            f(&data)?;
+        }
+        Ok(())
+    }
+}
+
+pub trait ReadRegion {
+    /// Reads the elements selected in the dataspace of the dataset, in the
+    /// order of the selection.
+    fn read_raw_bytes_region(&self, mem_dtype: &Datatype, space: &Dataspace) -> Result<RawBytes>;
+}
+
+impl ReadRegion for Dataset {
+    fn read_raw_bytes_region(&self, mem_dtype: &Datatype, space: &Dataspace) -> Result<RawBytes> {
+        let len = space.selection_size();
+        let item_size = mem_dtype.size();
+        let mut buffer = Vec::with_capacity(len * item_size);
+        if len > 0 {
+            let mem_space = Dataspace::try_new([len])?;
+            h5call!(H5Dread(
+                self.id(),
+                mem_dtype.id(),
+                mem_space.id(),
+                space.id(),
+                H5P_DEFAULT,
+                buffer.spare_capacity_mut().as_mut_ptr() as *mut _
+            ))?;
+            unsafe {
+                buffer.set_len(len * item_size);
+            }
+        }
+        Ok(RawBytes::new(buffer, mem_dtype))
+    }
+}
+
+pub trait ReadRange {
+    /// Reads `count` elements from `start` of a 1-D dataset.
+    fn read_raw_bytes_range(
+        &self,
+        mem_dtype: &Datatype,
+        start: usize,
+        count: usize,
+    ) -> Result<RawBytes>;
+}
+
+impl ReadRange for Dataset {
+    fn read_raw_bytes_range(
+        &self,
+        mem_dtype: &Datatype,
+        start: usize,
+        count: usize,
+    ) -> Result<RawBytes> {
+        let item_size = mem_dtype.size();
+        let mut buffer = Vec::with_capacity(count * item_size);
+        if count > 0 {
+            let hyperslab = Hyperslab::from(vec![SliceOrIndex::SliceCount {
+                start,
+                step: 1,
+                count,
+                block: 1,
+            }]);
+            let file_space = self.space()?.select(hyperslab)?;
+            let mem_space = Dataspace::try_new([count])?;
+            h5call!(H5Dread(
+                self.id(),
+                mem_dtype.id(),
+                mem_space.id(),
+                file_space.id(),
+                H5P_DEFAULT,
+                buffer.spare_capacity_mut().as_mut_ptr() as *mut _
+            ))?;
+            unsafe {
+                buffer.set_len(count * item_size);
+            }
+        }
+        Ok(RawBytes::new(buffer, mem_dtype))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_hdf5::tests::image;
+
+    #[test]
+    fn finds_the_signature_after_user_blocks() {
+        let at = |offset: usize| {
+            let mut bytes = vec![0; offset];
+            bytes.extend_from_slice(HDF5_SIGNATURE);
+            bytes
+        };
+        assert!(has_signature(&at(0)));
+        assert!(has_signature(&at(512)));
+        assert!(has_signature(&at(2048)));
+        // Only at powers of two of at least 512.
+        assert!(!has_signature(&at(100)));
+        assert!(!has_signature(&at(768)));
+        assert!(!has_signature(&HDF5_SIGNATURE[..4]));
+        assert!(!has_signature(b""));
+    }
+
+    fn numbers(name: &str) -> Vec<u8> {
+        image(name, |file| {
+            file.new_dataset_builder()
+                .with_data(&[1i32, 2])
+                .create("x")
+                .unwrap();
+        })
+    }
+
+    #[test]
+    fn closes_objects_with_the_image() {
+        let bytes = numbers("closes_objects_with_the_image.h5");
+        let dataset = {
+            let file = FileImage::new(&bytes).unwrap();
+            file.dataset("x").unwrap()
+        };
+        // Opened over the bytes, so it can't be read after the image.
+        assert!(!dataset.is_valid());
+        assert!(dataset.read_raw::<i32>().is_err());
+        drop(bytes);
+        drop(dataset);
+    }
+
+    #[test]
+    fn keeps_objects_of_copies_open() {
+        let bytes = numbers("keeps_objects_of_copies_open.h5");
+        let dataset = {
+            let file = FileImage::from(open_image_copy(&bytes).unwrap());
+            file.dataset("x").unwrap()
+        };
+        drop(bytes);
+        assert_eq!(dataset.read_raw::<i32>().unwrap(), [1, 2]);
+    }
+
+    mod row {
+        // The derive implements the trait inside a named constant.
+        #![allow(non_local_definitions)]
+
+        use hdf5::types::{VarLenArray, VarLenUnicode};
+
+        #[derive(hdf5::H5Type, Clone)]
+        #[repr(C)]
+        pub struct Row {
+            pub id: i32,
+            pub name: VarLenUnicode,
+            pub values: VarLenArray<i32>,
+        }
+    }
+
+    /// The resident memory of the process, in pages.
+    #[cfg(target_os = "linux")]
+    fn resident_pages() -> usize {
+        let statm = std::fs::read_to_string("/proc/self/statm").unwrap();
+        statm.split(' ').nth(1).unwrap().parse().unwrap()
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn reclaims_variable_length_data() {
+        use hdf5::types::{VarLenArray, VarLenUnicode};
+        use row::Row;
+        // About 4 MiB of variable-length data a read of each.
+        let name: VarLenUnicode = "x".repeat(1024).parse().unwrap();
+        let values = VarLenArray::from_slice(&[0i32; 256]);
+        let bytes = image("reclaims_variable_length_data.h5", |file| {
+            file.new_dataset_builder()
+                .with_data(&vec![name.clone(); 2048])
+                .create("strings")
+                .unwrap();
+            let row = Row {
+                id: 1,
+                name: name.clone(),
+                values: values.clone(),
+            };
+            file.new_dataset_builder()
+                .with_data(&vec![row; 1024])
+                .create("rows")
+                .unwrap();
+        });
+        let file = FileImage::new(&bytes).unwrap();
+        for name in ["strings", "rows"] {
+            let dataset = file.dataset(name).unwrap();
+            let mem_dtype = dataset.dtype().unwrap().to_native().unwrap();
+            let read = || drop(dataset.read_raw_bytes(&mem_dtype).unwrap());
+            read();
+            let before = resident_pages();
+            // Leaking would take about 400 MiB.
+            for _ in 0..100 {
+                read();
+            }
+            let grown = resident_pages().saturating_sub(before) * 4096;
+            assert!(grown < 64 << 20, "{name} grew by {grown} bytes");
+        }
     }
 }
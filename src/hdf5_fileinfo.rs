@@ -0,0 +1,61 @@
+use crate::{
+    from_hdf5::with_binary_input,
+    hdf5_ext::{FileImage, Format},
+};
+use hdf5::Result;
+use nu_plugin::EvaluatedCall;
+use nu_protocol::{Category, LabeledError, PipelineData, Record, Signature, Span, Type, Value};
+
+fn fileinfo(bytes: &[u8], span: Span) -> Result<Value> {
+    let file = FileImage::new(bytes)?;
+    let info = file.format_info()?;
+    let cols = vec![
+        "superblock_version".to_string(),
+        "superblock_size".to_string(),
+        "superblock_ext_size".to_string(),
+        "libver_low".to_string(),
+        "libver_high".to_string(),
+    ];
+    let vals = vec![
+        Value::Int {
+            val: info.superblock_version as _,
+            internal_span: span,
+        },
+        Value::Filesize {
+            val: info.superblock_size as _,
+            internal_span: span,
+        },
+        Value::Filesize {
+            val: info.superblock_ext_size as _,
+            internal_span: span,
+        },
+        Value::String {
+            val: info.libver_low.to_string(),
+            internal_span: span,
+        },
+        Value::String {
+            val: info.libver_high.to_string(),
+            internal_span: span,
+        },
+    ];
+    Ok(Value::Record {
+        val: Record::from_raw_cols_vals(cols, vals, span, span)
+            .unwrap()
+            .into(),
+        internal_span: span,
+    })
+}
+
+pub fn signature() -> Signature {
+    Signature::build("hdf5 fileinfo")
+        .description("Show the superblock version and format bounds of HDF5 binary")
+        .allow_variants_without_examples(true)
+        .input_output_types(vec![(Type::Binary, Type::Record(vec![].into()))])
+        .category(Category::Experimental)
+        .filter()
+}
+
+#[allow(clippy::result_large_err)]
+pub fn run(_call: &EvaluatedCall, input: PipelineData) -> Result<PipelineData, LabeledError> {
+    with_binary_input(input, fileinfo)
+}
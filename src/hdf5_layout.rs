@@ -0,0 +1,275 @@
+use crate::{
+    from_hdf5::{filter_name, with_binary_input},
+    hdf5_ext::FileImage,
+};
+use hdf5::{
+    filters::Filter,
+    plist::dataset_create::{Layout, VirtualMapping},
+    Dataset, Group, LocationToken, Result,
+};
+use nu_plugin::EvaluatedCall;
+use nu_protocol::{Category, LabeledError, PipelineData, Record, Signature, Span, Type, Value};
+
+/// Chunks smaller than this cost more in indexing than they save.
+const TINY_CHUNK: usize = 4 * 1024;
+/// Chunks larger than this don't fit the default chunk cache of 1 MiB.
+const HUGE_CHUNK: usize = 1024 * 1024;
+/// Datasets larger than this are worth compressing.
+const LARGE_DATASET: usize = 1024 * 1024;
+
+/// A filter with its parameters, e.g. `gzip 4`.
+fn filter_desc(filter: &Filter) -> String {
+    match filter {
+        Filter::Deflate(level) => format!("gzip {level}"),
+        Filter::SZip(_, pixels) => format!("szip {pixels}"),
+        filter => filter_name(filter),
+    }
+}
+
+fn source_row(mapping: VirtualMapping, span: Span) -> Value {
+    let string = |val: String| Value::String {
+        val,
+        internal_span: span,
+    };
+    Value::Record {
+        val: Record::from_raw_cols_vals(
+            vec![
+                "file".to_string(),
+                "dataset".to_string(),
+                "selection".to_string(),
+                "source_selection".to_string(),
+            ],
+            vec![
+                string(mapping.src_filename),
+                string(mapping.src_dataset),
+                string(mapping.vds_selection.to_string()),
+                string(mapping.src_selection.to_string()),
+            ],
+            span,
+            span,
+        )
+        .unwrap()
+        .into(),
+        internal_span: span,
+    }
+}
+
+fn dataset_row(ds: &Dataset, span: Span) -> Result<Value> {
+    let size = ds.size() * ds.dtype()?.size();
+    let storage_size = ds.storage_size() as usize;
+    let layout = ds.layout();
+    let filters = ds.filters();
+    let chunk = ds.chunk();
+    let mut warnings = vec![];
+    if let Some(chunk) = &chunk {
+        let bytes = chunk.iter().product::<usize>() * ds.dtype()?.size();
+        if bytes < TINY_CHUNK && size > TINY_CHUNK {
+            warnings.push(format!("tiny chunks of {bytes} bytes"));
+        }
+        if bytes > HUGE_CHUNK {
+            warnings.push(format!(
+                "chunks of {bytes} bytes over the default chunk cache"
+            ));
+        }
+    }
+    if filters.is_empty() && size > LARGE_DATASET {
+        warnings.push(format!("{size} bytes uncompressed"));
+    }
+    let int = |val: usize| Value::Int {
+        val: val as _,
+        internal_span: span,
+    };
+    let string = |val: String| Value::String {
+        val,
+        internal_span: span,
+    };
+    let list = |vals: Vec<Value>| Value::List {
+        vals,
+        internal_span: span,
+    };
+    let nothing = Value::Nothing {
+        internal_span: span,
+    };
+    let cols = vec![
+        "path".to_string(),
+        "layout".to_string(),
+        "chunk".to_string(),
+        "chunks".to_string(),
+        "filters".to_string(),
+        "size".to_string(),
+        "storage_size".to_string(),
+        "offset".to_string(),
+        "ratio".to_string(),
+        "sources".to_string(),
+        "warnings".to_string(),
+    ];
+    let vals = vec![
+        string(ds.name()),
+        string(
+            match layout {
+                Layout::Compact => "compact",
+                Layout::Contiguous => "contiguous",
+                Layout::Chunked => "chunked",
+                Layout::Virtual => "virtual",
+            }
+            .to_string(),
+        ),
+        match chunk {
+            Some(chunk) => list(chunk.into_iter().map(int).collect()),
+            None => nothing.clone(),
+        },
+        match ds.num_chunks() {
+            Some(n) if layout == Layout::Chunked => int(n),
+            _ => nothing.clone(),
+        },
+        list(filters.iter().map(|f| string(filter_desc(f))).collect()),
+        Value::Filesize {
+            val: size as _,
+            internal_span: span,
+        },
+        Value::Filesize {
+            val: storage_size as _,
+            internal_span: span,
+        },
+        // Where the raw data can be mapped from, if it's in one piece as is.
+        match ds.offset() {
+            Some(offset) if layout == Layout::Contiguous && filters.is_empty() => Value::Int {
+                val: offset as _,
+                internal_span: span,
+            },
+            _ => nothing.clone(),
+        },
+        // Of the data to the storage, so that compression is over 1.
+        if storage_size > 0 {
+            Value::Float {
+                val: size as f64 / storage_size as f64,
+                internal_span: span,
+            }
+        } else {
+            nothing.clone()
+        },
+        // The datasets a virtual dataset maps, and where in it.
+        if layout == Layout::Virtual {
+            list(
+                ds.dcpl()?
+                    .get_virtual_map()?
+                    .into_iter()
+                    .map(|mapping| source_row(mapping, span))
+                    .collect(),
+            )
+        } else {
+            nothing
+        },
+        list(warnings.into_iter().map(string).collect()),
+    ];
+    Ok(Value::Record {
+        val: Record::from_raw_cols_vals(cols, vals, span, span)
+            .unwrap()
+            .into(),
+        internal_span: span,
+    })
+}
+
+/// Reports the datasets under a group, not descending again into its `ancestors` at link cycles.
+fn layout_group(
+    group: &Group,
+    ancestors: &mut Vec<(u64, LocationToken)>,
+    rows: &mut Vec<Value>,
+    span: Span,
+) -> Result<()> {
+    let info = group.loc_info()?;
+    ancestors.push((info.fileno, info.token));
+    for ds in group.datasets()? {
+        rows.push(dataset_row(&ds, span)?);
+    }
+    for g in group.groups()? {
+        let info = g.loc_info()?;
+        if !ancestors.contains(&(info.fileno, info.token)) {
+            layout_group(&g, ancestors, rows, span)?;
+        }
+    }
+    ancestors.pop();
+    Ok(())
+}
+
+fn layout(bytes: &[u8], span: Span) -> Result<Value> {
+    let file = FileImage::new(bytes)?;
+    let mut rows = vec![];
+    layout_group(&file, &mut vec![], &mut rows, span)?;
+    Ok(Value::List {
+        vals: rows,
+        internal_span: span,
+    })
+}
+
+pub fn signature() -> Signature {
+    Signature::build("hdf5 layout")
+        .description("Report the storage layout of the datasets of HDF5 binary, with the offsets of contiguous data, the sources of virtual datasets and warnings of layouts worth repacking")
+        .allow_variants_without_examples(true)
+        .input_output_types(vec![(Type::Binary, Type::table())])
+        .category(Category::Experimental)
+        .filter()
+}
+
+#[allow(clippy::result_large_err)]
+pub fn run(_call: &EvaluatedCall, input: PipelineData) -> Result<PipelineData, LabeledError> {
+    with_binary_input(input, layout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_hdf5::tests::image, hdf5_ext::ToImage};
+    use hdf5::File;
+
+    #[test]
+    fn stops_at_link_cycles() {
+        let bytes = image("layout_stops_at_link_cycles.h5", |file| {
+            let group = file.create_group("a").unwrap();
+            group
+                .new_dataset_builder()
+                .with_data(&[1i32, 2])
+                .create("x")
+                .unwrap();
+            file.link_hard("/a", "/a/self").unwrap();
+        });
+        let rows = layout(&bytes, Span::test_data()).unwrap();
+        assert_eq!(rows.as_list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn lists_virtual_sources() {
+        let file = File::with_options()
+            .with_fapl(|p| p.core_filebacked(false))
+            .create("lists_virtual_sources.h5")
+            .unwrap();
+        file.new_dataset_builder()
+            .with_data(&[1i32, 2, 3, 4])
+            .create("source")
+            .unwrap();
+        file.new_dataset::<i32>()
+            .shape([4])
+            .with_dcpl(|p| p.virtual_map(".", "/source", [4], .., [4], ..))
+            .create("virtual")
+            .unwrap();
+        let bytes = file.to_image().unwrap();
+        let rows = layout(&bytes, Span::test_data()).unwrap();
+        let row = rows
+            .as_list()
+            .unwrap()
+            .iter()
+            .find(|row| row.get_data_by_key("path").unwrap().as_str().unwrap() == "/virtual")
+            .unwrap()
+            .clone();
+        let sources = row.get_data_by_key("sources").unwrap();
+        let source = &sources.as_list().unwrap()[0];
+        assert_eq!(
+            source.get_data_by_key("file").unwrap().as_str().unwrap(),
+            "."
+        );
+        assert_eq!(
+            source.get_data_by_key("dataset").unwrap().as_str().unwrap(),
+            "/source"
+        );
+    }
+}
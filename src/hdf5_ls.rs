@@ -0,0 +1,149 @@
+use crate::{
+    dtype::Dtype,
+    from_hdf5::with_binary_input,
+    hdf5_ext::{FileImage, NativeType},
+};
+use hdf5::{Dataset, Group, LocationToken, Result};
+use nu_plugin::EvaluatedCall;
+use nu_protocol::{Category, LabeledError, PipelineData, Record, Signature, Span, Type, Value};
+
+fn row(path: String, kind: &str, shape: Value, dtype: Value, size: Value, span: Span) -> Value {
+    let cols = vec![
+        "path".to_string(),
+        "type".to_string(),
+        "shape".to_string(),
+        "dtype".to_string(),
+        "size".to_string(),
+    ];
+    let vals = vec![
+        Value::String {
+            val: path,
+            internal_span: span,
+        },
+        Value::String {
+            val: kind.to_string(),
+            internal_span: span,
+        },
+        shape,
+        dtype,
+        size,
+    ];
+    Value::Record {
+        val: Record::from_raw_cols_vals(cols, vals, span, span)
+            .unwrap()
+            .into(),
+        internal_span: span,
+    }
+}
+
+/// Describes a dataset from its dataspace and datatype, without reading it.
+fn dataset_row(ds: &Dataset, span: Span) -> Result<Value> {
+    let dtype = ds.dtype()?.to_native()?;
+    let shape = ds
+        .shape()
+        .into_iter()
+        .map(|d| Value::Int {
+            val: d as _,
+            internal_span: span,
+        })
+        .collect();
+    Ok(row(
+        ds.name(),
+        "dataset",
+        Value::List {
+            vals: shape,
+            internal_span: span,
+        },
+        Value::String {
+            val: Dtype::from_datatype(&dtype)?.to_string(),
+            internal_span: span,
+        },
+        Value::Filesize {
+            val: (ds.size() * dtype.size()) as _,
+            internal_span: span,
+        },
+        span,
+    ))
+}
+
+/// Lists the members of a group, not descending again into its `ancestors` at link cycles.
+fn list_group(
+    group: &Group,
+    ancestors: &mut Vec<(u64, LocationToken)>,
+    rows: &mut Vec<Value>,
+    span: Span,
+) -> Result<()> {
+    let info = group.loc_info()?;
+    ancestors.push((info.fileno, info.token));
+    for ds in group.datasets()? {
+        rows.push(dataset_row(&ds, span)?);
+    }
+    for g in group.groups()? {
+        let nothing = || Value::Nothing {
+            internal_span: span,
+        };
+        rows.push(row(
+            g.name(),
+            "group",
+            nothing(),
+            nothing(),
+            nothing(),
+            span,
+        ));
+        let info = g.loc_info()?;
+        if !ancestors.contains(&(info.fileno, info.token)) {
+            list_group(&g, ancestors, rows, span)?;
+        }
+    }
+    ancestors.pop();
+    Ok(())
+}
+
+fn ls(bytes: &[u8], span: Span) -> Result<Value> {
+    let file = FileImage::new(bytes)?;
+    let mut rows = vec![];
+    list_group(&file, &mut vec![], &mut rows, span)?;
+    Ok(Value::List {
+        vals: rows,
+        internal_span: span,
+    })
+}
+
+pub fn signature() -> Signature {
+    Signature::build("hdf5 ls")
+        .description("List the groups and datasets of HDF5 binary without reading any data")
+        .allow_variants_without_examples(true)
+        .input_output_types(vec![(Type::Binary, Type::table())])
+        .category(Category::Experimental)
+        .filter()
+}
+
+#[allow(clippy::result_large_err)]
+pub fn run(_call: &EvaluatedCall, input: PipelineData) -> Result<PipelineData, LabeledError> {
+    with_binary_input(input, ls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_hdf5::tests::image;
+
+    #[test]
+    fn lists_link_cycles_once() {
+        let bytes = image("lists_link_cycles_once.h5", |file| {
+            file.create_group("a").unwrap();
+            file.link_hard("/a", "/a/self").unwrap();
+        });
+        let paths = ls(&bytes, Span::test_data())
+            .unwrap()
+            .into_list()
+            .unwrap()
+            .into_iter()
+            .map(|row| row.get_data_by_key("path").unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![Value::test_string("/a"), Value::test_string("/a/self")]
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use crate::{
+    from_hdf5::{read_file_path, with_binary_input},
+    hdf5_ext::{open_image_copy, FileImage},
+};
+use hdf5::File;
+use nu_plugin::EvaluatedCall;
+use nu_protocol::{
+    Category, CustomValue, LabeledError, PipelineData, ShellError, Signature, Span, SyntaxShape,
+    Type, Value,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// Reads a dataset of a file read by `from hdf5 --lazy` at its path, with
+/// the options of the command.
+pub(crate) type ReadDataset = Arc<dyn Fn(&File, &str, Span) -> hdf5::Result<Value> + Send + Sync>;
+
+/// A file kept open, with the number of values still referring to it.
+struct Image {
+    file: Arc<FileImage<'static>>,
+    refs: usize,
+    read: Option<ReadDataset>,
+}
+
+/// The files opened by `hdf5 open` and `from hdf5 --lazy`, kept open by the
+/// plugin until their handles are dropped, so that they aren't opened again
+/// and the handles passed around are small.
+#[derive(Default)]
+pub struct Images {
+    next_id: AtomicU64,
+    images: Mutex<HashMap<u64, Image>>,
+}
+
+impl Images {
+    /// Keeps the file open for one handle, or for `read` of the datasets
+    /// handed out by [`Images::retain`] when lazy.
+    pub(crate) fn insert(
+        &self,
+        file: FileImage<'static>,
+        read: Option<ReadDataset>,
+    ) -> (u64, Arc<FileImage<'static>>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let file = Arc::new(file);
+        let image = Image {
+            file: file.clone(),
+            refs: 1,
+            read,
+        };
+        self.images.lock().unwrap().insert(id, image);
+        (id, file)
+    }
+
+    /// Counts another value referring to the file.
+    pub(crate) fn retain(&self, id: u64) {
+        if let Some(image) = self.images.lock().unwrap().get_mut(&id) {
+            image.refs += 1;
+        }
+    }
+
+    fn get(&self, id: u64) -> Option<Arc<FileImage<'static>>> {
+        self.images
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|image| image.file.clone())
+    }
+
+    /// Drops a value referring to the file, which is closed after the last.
+    pub fn release(&self, id: u64) {
+        let mut images = self.images.lock().unwrap();
+        if let Some(image) = images.get_mut(&id) {
+            image.refs -= 1;
+            if image.refs == 0 {
+                images.remove(&id);
+            }
+        }
+    }
+
+    /// Reads the dataset of a handle of `from hdf5 --lazy`.
+    #[allow(clippy::result_large_err)]
+    pub fn read(&self, dataset: &Hdf5Dataset, span: Span) -> Result<Value, LabeledError> {
+        // Read without the lock, which other commands wait for.
+        let (file, read) = {
+            let images = self.images.lock().unwrap();
+            let image = images.get(&dataset.id).ok_or_else(|| {
+                LabeledError::new("the file of the hdf5 dataset is no longer open")
+            })?;
+            (image.file.clone(), image.read.clone())
+        };
+        let read =
+            read.ok_or_else(|| LabeledError::new("the hdf5 handle isn't of a lazy dataset"))?;
+        read(&file, &dataset.path, span).map_err(|e| {
+            LabeledError::new(e.to_string())
+                .with_label(format!("while reading {}", dataset.path), span)
+        })
+    }
+}
+
+/// A handle to an image opened by `hdf5 open`, read on demand by `hdf5 get`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Hdf5Handle {
+    pub id: u64,
+}
+
+#[typetag::serde]
+impl CustomValue for Hdf5Handle {
+    fn clone_value(&self, span: Span) -> Value {
+        Value::custom(Box::new(self.clone()), span)
+    }
+
+    fn type_name(&self) -> String {
+        "hdf5 handle".to_string()
+    }
+
+    fn to_base_value(&self, span: Span) -> Result<Value, ShellError> {
+        Ok(Value::String {
+            val: format!("<hdf5 handle {}>", self.id),
+            internal_span: span,
+        })
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn notify_plugin_on_drop(&self) -> bool {
+        true
+    }
+}
+
+/// A dataset of `from hdf5 --lazy`, read by the plugin when the value is
+/// accessed or collapsed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Hdf5Dataset {
+    pub id: u64,
+    pub path: String,
+}
+
+#[typetag::serde]
+impl CustomValue for Hdf5Dataset {
+    fn clone_value(&self, span: Span) -> Value {
+        Value::custom(Box::new(self.clone()), span)
+    }
+
+    fn type_name(&self) -> String {
+        "hdf5 dataset".to_string()
+    }
+
+    // Only without the plugin, which reads the data instead.
+    fn to_base_value(&self, span: Span) -> Result<Value, ShellError> {
+        Ok(Value::String {
+            val: format!("<hdf5 dataset {}>", self.path),
+            internal_span: span,
+        })
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn notify_plugin_on_drop(&self) -> bool {
+        true
+    }
+}
+
+pub fn open_signature() -> Signature {
+    Signature::build("hdf5 open")
+        .description("Keep HDF5 binary open for reading parts of it with `hdf5 get`")
+        .allow_variants_without_examples(true)
+        .input_output_types(vec![(Type::Binary, Type::Custom("hdf5 handle".into()))])
+        .category(Category::Experimental)
+        .filter()
+}
+
+#[allow(clippy::result_large_err)]
+pub fn open(images: &Images, input: PipelineData) -> Result<PipelineData, LabeledError> {
+    with_binary_input(input, |bytes, span| {
+        // A copy owned by HDF5, as the input is gone after the call.
+        let (id, _) = images.insert(open_image_copy(bytes)?.into(), None);
+        Ok(Value::custom(Box::new(Hdf5Handle { id }), span))
+    })
+}
+
+pub fn get_signature() -> Signature {
+    Signature::build("hdf5 get")
+        .description("Read a group or a dataset from a handle of `hdf5 open`")
+        .allow_variants_without_examples(true)
+        .input_output_types(vec![(Type::Custom("hdf5 handle".into()), Type::Any)])
+        .category(Category::Experimental)
+        .required("path", SyntaxShape::String, "path of the group or dataset")
+        .named(
+            "max-depth",
+            SyntaxShape::Int,
+            "do not descend into groups nested deeper than this, leaving a placeholder instead",
+            None,
+        )
+        .filter()
+}
+
+#[allow(clippy::result_large_err)]
+pub fn get(
+    images: &Images,
+    call: &EvaluatedCall,
+    input: PipelineData,
+) -> Result<PipelineData, LabeledError> {
+    let path: String = call.req(0)?;
+    let max_depth: Option<usize> = call.get_flag("max-depth")?;
+    let value = input.into_value(call.head)?;
+    let span = value.span();
+    let handle = match &value {
+        Value::Custom { val, .. } => val.as_any().downcast_ref::<Hdf5Handle>(),
+        _ => None,
+    }
+    .ok_or_else(|| {
+        LabeledError::new(format!(
+            "requires an hdf5 handle input, got {}",
+            value.get_type()
+        ))
+    })?;
+    let file = images
+        .get(handle.id)
+        .ok_or_else(|| LabeledError::new("the hdf5 handle is no longer open"))?;
+    let value = read_file_path(&file, &path, max_depth, span)
+        .map_err(|e| LabeledError::new(e.to_string()))?;
+    Ok(PipelineData::Value(value, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_hdf5::tests::image;
+
+    #[test]
+    fn keeps_files_open_until_released() {
+        let bytes = image("keeps_files_open_until_released.h5", |file| {
+            file.new_dataset_builder()
+                .with_data(&[1i32, 2])
+                .create("x")
+                .unwrap();
+        });
+        let images = Images::default();
+        let (id, _) = images.insert(open_image_copy(&bytes).unwrap().into(), None);
+        // The file doesn't need the input any more.
+        drop(bytes);
+        let file = images.get(id).unwrap();
+        assert_eq!(
+            read_file_path(&file, "x", None, Span::test_data()).unwrap(),
+            Value::test_list(vec![Value::test_int(1), Value::test_int(2)])
+        );
+        images.release(id);
+        assert!(images.get(id).is_none());
+    }
+}
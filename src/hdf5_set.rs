@@ -0,0 +1,248 @@
+use crate::{
+    dtype::Dtype,
+    to_hdf5::{to_data, write_attr, Data},
+};
+use hdf5::{Container, File, H5Type, Location, Result};
+use nu_plugin::{EngineInterface, EvaluatedCall};
+use nu_protocol::{
+    Category, LabeledError, PipelineData, Signature, Spanned, SyntaxShape, Type, Value,
+};
+use std::path::Path;
+
+/// Whether the data can be written to the datatype, which HDF5 converts to,
+/// with ints in the range of an integer datatype.
+fn check_type(data: &Data, dtype: &Dtype) -> Result<()> {
+    let range = match (data, dtype) {
+        (Data::Int(_), Dtype::Integer(size)) => {
+            let shift = 64 - *size as u32 * 8;
+            Some((i64::MIN >> shift, i64::MAX >> shift))
+        }
+        (Data::Int(_), Dtype::Unsigned(size)) => {
+            let shift = 64 - *size as u32 * 8;
+            Some((0, i64::try_from(u64::MAX >> shift).unwrap_or(i64::MAX)))
+        }
+        (Data::String(_), Dtype::FixedAscii(..) | Dtype::FixedUnicode(..)) => {
+            return Err(format!(
+                "can't write a string to {dtype}, only to variable-length strings"
+            )
+            .into());
+        }
+        _ => None,
+    };
+    if let (Data::Int(data), Some((min, max))) = (data, range) {
+        if let Some(val) = data.iter().find(|val| !(min..=max).contains(*val)) {
+            return Err(format!("can't write {val} to {dtype}, out of its range").into());
+        }
+    }
+    let fits = matches!(
+        (data, dtype),
+        (
+            Data::Int(_),
+            Dtype::Integer(_) | Dtype::Unsigned(_) | Dtype::Float(_)
+        ) | (Data::Float(_), Dtype::Float(_))
+            | (Data::Bool(_), Dtype::Boolean(_))
+            | (Data::String(_), Dtype::VarLenAscii | Dtype::VarLenUnicode)
+    );
+    if fits {
+        Ok(())
+    } else {
+        Err(format!("can't write {} to {dtype}", data_type(data)).into())
+    }
+}
+
+fn data_type(data: &Data) -> &'static str {
+    match data {
+        Data::Int(_) => "int",
+        Data::Float(_) => "float",
+        Data::Bool(_) => "bool",
+        Data::String(_) => "string",
+    }
+}
+
+/// Writes the data over the elements of a dataset or an attribute, of the
+/// same datatype class and shape.
+fn overwrite(container: &Container, data: Data, shape: &[usize]) -> Result<()> {
+    check_type(&data, &Dtype::from_datatype(&container.dtype()?)?)?;
+    if container.shape() != shape {
+        return Err(format!(
+            "can't write data of shape {shape:?} to shape {:?}",
+            container.shape()
+        )
+        .into());
+    }
+    fn write<T: H5Type>(container: &Container, data: &[T], scalar: bool) -> Result<()> {
+        if scalar {
+            container.write_scalar(&data[0])
+        } else {
+            container.write_raw(data)
+        }
+    }
+    let scalar = shape.is_empty();
+    match data {
+        Data::Int(data) => write(container, &data, scalar),
+        Data::Float(data) => write(container, &data, scalar),
+        Data::Bool(data) => write(container, &data, scalar),
+        Data::String(data) => write(container, &data, scalar),
+    }
+}
+
+fn set(file: &Path, path: &str, value: &Value, attribute: Option<&str>) -> Result<()> {
+    let file = File::open_rw(file)?;
+    let (data, shape) = to_data(value)?;
+    let path = path.trim_matches('/');
+    let Some(name) = attribute else {
+        let ds = file.dataset(path)?;
+        return overwrite(&ds, data, &shape);
+    };
+    let location = if path.is_empty() {
+        Location::clone(&file)
+    } else {
+        match file.dataset(path) {
+            Ok(ds) => Location::clone(&ds),
+            Err(_) => {
+                let group = file.group(path)?;
+                Location::clone(&group)
+            }
+        }
+    };
+    if location.attr_names()?.iter().any(|attr| attr == name) {
+        let attr = location.attr(name)?;
+        return overwrite(&attr, data, &shape);
+    }
+    match data {
+        Data::Int(data) => write_attr(&location, name, &data, &shape),
+        Data::Float(data) => write_attr(&location, name, &data, &shape),
+        Data::Bool(data) => write_attr(&location, name, &data, &shape),
+        Data::String(data) => write_attr(&location, name, &data, &shape),
+    }
+}
+
+pub fn signature() -> Signature {
+    Signature::build("hdf5 set")
+        .description("Write a value over a dataset or an attribute of an HDF5 file in place")
+        .allow_variants_without_examples(true)
+        .input_output_types(vec![(Type::Nothing, Type::Nothing)])
+        .category(Category::Experimental)
+        .required("file", SyntaxShape::Filepath, "the HDF5 file to modify")
+        .required(
+            "path",
+            SyntaxShape::String,
+            "path of the dataset, or of the object of the attribute",
+        )
+        .required(
+            "value",
+            SyntaxShape::Any,
+            "the value, or nested lists of the shape of the dataset",
+        )
+        .named(
+            "attribute",
+            SyntaxShape::String,
+            "write the attribute of this name instead, which is created if missing",
+            Some('a'),
+        )
+}
+
+#[allow(clippy::result_large_err)]
+pub fn run(engine: &EngineInterface, call: &EvaluatedCall) -> Result<PipelineData, LabeledError> {
+    let file: Spanned<String> = call.req(0)?;
+    let path: String = call.req(1)?;
+    let value: Value = call.req(2)?;
+    let attribute: Option<String> = call.get_flag("attribute")?;
+    let full_path = Path::new(&engine.get_current_dir()?).join(&file.item);
+    set(&full_path, &path, &value, attribute.as_deref()).map_err(|e| {
+        LabeledError::new(e.to_string())
+            .with_label(format!("while writing {}", full_path.display()), file.span)
+    })?;
+    Ok(PipelineData::Empty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hdf5::types::FixedAscii;
+    use std::path::PathBuf;
+
+    /// A file on disk with `x`, the u8 dataset [1, 2, 3], and `s`, a
+    /// fixed-length string.
+    fn file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let file = File::create(&path).unwrap();
+        file.new_dataset_builder()
+            .with_data(&[1u8, 2, 3])
+            .create("x")
+            .unwrap();
+        file.new_dataset_builder()
+            .with_data(&[FixedAscii::<4>::from_ascii("ab").unwrap()])
+            .create("s")
+            .unwrap();
+        path
+    }
+
+    fn ints(vals: &[i64]) -> Value {
+        Value::test_list(vals.iter().copied().map(Value::test_int).collect())
+    }
+
+    #[test]
+    fn writes_in_place() {
+        let path = file("writes_in_place.h5");
+        set(&path, "x", &ints(&[4, 5, 6]), None).unwrap();
+        let x = File::open(&path).unwrap().dataset("x").unwrap();
+        assert_eq!(x.read_raw::<u8>().unwrap(), [4, 5, 6]);
+    }
+
+    #[test]
+    fn rejects_other_types_and_shapes() {
+        let path = file("rejects_other_types_and_shapes.h5");
+        let strings = Value::test_list(vec![Value::test_string("a"); 3]);
+        let err = set(&path, "x", &strings, None).unwrap_err();
+        assert!(err.to_string().contains("can't write string"));
+        let err = set(&path, "x", &ints(&[4, 5]), None).unwrap_err();
+        assert!(err.to_string().contains("shape"));
+        let strings = Value::test_list(vec![Value::test_string("cd")]);
+        let err = set(&path, "s", &strings, None).unwrap_err();
+        assert!(err.to_string().contains("only to variable-length strings"));
+    }
+
+    #[test]
+    fn rejects_ints_out_of_range() {
+        let path = file("rejects_ints_out_of_range.h5");
+        for val in [300, -1] {
+            let err = set(&path, "x", &ints(&[1, val, 3]), None).unwrap_err();
+            assert!(err.to_string().contains(&format!("can't write {val}")));
+        }
+        let x = File::open(&path).unwrap().dataset("x").unwrap();
+        assert_eq!(x.read_raw::<u8>().unwrap(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn checks_the_range_of_each_width() {
+        use hdf5::types::IntSize;
+        let fits = |val: i64, dtype: Dtype| check_type(&Data::Int(vec![val]), &dtype).is_ok();
+        assert!(fits(255, Dtype::Unsigned(IntSize::U1)));
+        assert!(!fits(256, Dtype::Unsigned(IntSize::U1)));
+        assert!(!fits(-1, Dtype::Unsigned(IntSize::U8)));
+        assert!(fits(i64::MAX, Dtype::Unsigned(IntSize::U8)));
+        assert!(fits(-128, Dtype::Integer(IntSize::U1)));
+        assert!(!fits(128, Dtype::Integer(IntSize::U1)));
+        assert!(!fits(-32769, Dtype::Integer(IntSize::U2)));
+        assert!(fits(i64::MIN, Dtype::Integer(IntSize::U8)));
+    }
+
+    #[test]
+    fn creates_missing_attributes() {
+        let path = file("creates_missing_attributes.h5");
+        set(&path, "x", &Value::test_string("m"), Some("units")).unwrap();
+        let attr = File::open(&path)
+            .unwrap()
+            .dataset("x")
+            .unwrap()
+            .attr("units")
+            .unwrap();
+        assert_eq!(
+            attr.read_scalar::<hdf5::types::VarLenUnicode>()
+                .unwrap()
+                .as_str(),
+            "m"
+        );
+    }
+}
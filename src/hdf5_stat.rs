@@ -0,0 +1,162 @@
+use crate::{
+    from_hdf5::with_binary_input,
+    hdf5_ext::{FileImage, Format},
+};
+use hdf5::{Dataset, Group, LocationToken, Result};
+use nu_plugin::EvaluatedCall;
+use nu_protocol::{Category, LabeledError, PipelineData, Record, Signature, Span, Type, Value};
+
+#[derive(Default)]
+struct Counts {
+    groups: usize,
+    attributes: usize,
+    datasets: Vec<Dataset>,
+    /// The groups being counted, by file and address, to stop at link cycles.
+    ancestors: Vec<(u64, LocationToken)>,
+}
+
+fn count(group: &Group, counts: &mut Counts) -> Result<()> {
+    let info = group.loc_info()?;
+    counts.ancestors.push((info.fileno, info.token));
+    counts.attributes += group.attr_names()?.len();
+    for ds in group.datasets()? {
+        counts.attributes += ds.attr_names()?.len();
+        counts.datasets.push(ds);
+    }
+    for g in group.groups()? {
+        let info = g.loc_info()?;
+        if counts.ancestors.contains(&(info.fileno, info.token)) {
+            continue;
+        }
+        counts.groups += 1;
+        count(&g, counts)?;
+    }
+    counts.ancestors.pop();
+    Ok(())
+}
+
+fn to_record(cols: &[&str], vals: Vec<Value>, span: Span) -> Value {
+    Value::Record {
+        val: Record::from_raw_cols_vals(
+            cols.iter().map(|col| col.to_string()).collect(),
+            vals,
+            span,
+            span,
+        )
+        .unwrap()
+        .into(),
+        internal_span: span,
+    }
+}
+
+fn filesize(val: u64, span: Span) -> Value {
+    Value::Filesize {
+        val: val as _,
+        internal_span: span,
+    }
+}
+
+/// The size of a dataset in memory, and on disk after filters.
+fn sizes(ds: &Dataset) -> Result<(u64, u64)> {
+    Ok(((ds.size() * ds.dtype()?.size()) as u64, ds.storage_size()))
+}
+
+fn stat(bytes: &[u8], span: Span) -> Result<Value> {
+    let file = FileImage::new(bytes)?;
+    let info = file.format_info()?;
+    let mut counts = Counts::default();
+    count(&file, &mut counts)?;
+    let mut data_size = 0;
+    let mut storage_size = 0;
+    let datasets = counts
+        .datasets
+        .iter()
+        .map(|ds| -> Result<Value> {
+            let (data, storage) = sizes(ds)?;
+            data_size += data;
+            storage_size += storage;
+            Ok(to_record(
+                &["path", "data_size", "storage_size"],
+                vec![
+                    Value::String {
+                        val: ds.name(),
+                        internal_span: span,
+                    },
+                    filesize(data, span),
+                    filesize(storage, span),
+                ],
+                span,
+            ))
+        })
+        .try_collect()?;
+    let int = |val: usize| Value::Int {
+        val: val as _,
+        internal_span: span,
+    };
+    Ok(to_record(
+        &[
+            "superblock_version",
+            "groups",
+            "datasets",
+            "attributes",
+            "file_size",
+            "data_size",
+            "storage_size",
+            "sizes",
+        ],
+        vec![
+            int(info.superblock_version as _),
+            // The root group counts.
+            int(counts.groups + 1),
+            int(counts.datasets.len()),
+            int(counts.attributes),
+            filesize(bytes.len() as _, span),
+            filesize(data_size, span),
+            filesize(storage_size, span),
+            Value::List {
+                vals: datasets,
+                internal_span: span,
+            },
+        ],
+        span,
+    ))
+}
+
+pub fn signature() -> Signature {
+    Signature::build("hdf5 stat")
+        .description("Count the objects of HDF5 binary and sum the sizes of its datasets")
+        .allow_variants_without_examples(true)
+        .input_output_types(vec![(Type::Binary, Type::Record(vec![].into()))])
+        .category(Category::Experimental)
+        .filter()
+}
+
+#[allow(clippy::result_large_err)]
+pub fn run(_call: &EvaluatedCall, input: PipelineData) -> Result<PipelineData, LabeledError> {
+    with_binary_input(input, stat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_hdf5::tests::image;
+
+    #[test]
+    fn stops_at_link_cycles() {
+        let bytes = image("stops_at_link_cycles.h5", |file| {
+            let group = file.create_group("a").unwrap();
+            group
+                .new_dataset_builder()
+                .with_data(&[1i32, 2])
+                .create("x")
+                .unwrap();
+            file.link_hard("/a", "/a/self").unwrap();
+        });
+        let value = stat(&bytes, Span::test_data()).unwrap();
+        assert_eq!(value.get_data_by_key("groups").unwrap(), Value::test_int(2));
+        assert_eq!(
+            value.get_data_by_key("datasets").unwrap(),
+            Value::test_int(1)
+        );
+    }
+}
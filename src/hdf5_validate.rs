@@ -0,0 +1,374 @@
+use crate::{
+    dtype::Dtype,
+    from_hdf5::with_binary_input,
+    hdf5_ext::{FileImage, NativeType},
+};
+use hdf5::{Dataset, Group, LocationToken, Result};
+use nu_plugin::EvaluatedCall;
+use nu_protocol::{
+    Category, LabeledError, PipelineData, Record, ShellError, Signature, Span, SyntaxShape, Type,
+    Value,
+};
+use std::collections::HashMap;
+
+/// The part of the schema checked for a dataset.
+struct Expected {
+    dtype: Option<String>,
+    shape: Option<Vec<i64>>,
+}
+
+/// Reads the schema in the shape of `from hdf5 --meta --flatten-paths` as a
+/// record, `{dtype, shape}` keyed by dataset path, or of the nested tree of
+/// `from hdf5 --schema`, whose groups are records of their members keyed by
+/// path. Other columns are ignored, as are the `@` entries about groups, and
+/// datasets with attributes are `{value: {dtype, shape}, @attributes}`.
+fn parse_schema(schema: &Record, expected: &mut Vec<(String, Expected)>) -> Result<()> {
+    for (path, val) in schema.iter().filter(|(path, _)| !is_special(path)) {
+        let record = val
+            .as_record()
+            .map_err(|_| format!("schema of {path} should be a record"))?;
+        let meta = match record.get("value") {
+            Some(Value::Record { val, .. })
+                if record
+                    .columns()
+                    .all(|col| col == "value" || is_special(col)) =>
+            {
+                val
+            }
+            _ => record,
+        };
+        if !is_meta(meta) {
+            parse_schema(meta, expected)?;
+            continue;
+        }
+        let dtype = meta
+            .get("dtype")
+            .map(|v| v.coerce_string())
+            .transpose()
+            .map_err(|_| format!("dtype of {path} should be a string"))?;
+        let shape = meta
+            .get("shape")
+            .map(|v| v.as_list()?.iter().map(|d| d.as_int()).try_collect())
+            .transpose()
+            .map_err(|_| format!("shape of {path} should be a list of ints"))?;
+        expected.push((
+            path.trim_start_matches('/').to_string(),
+            Expected { dtype, shape },
+        ));
+    }
+    Ok(())
+}
+
+/// Whether the record describes a dataset rather than being a group of the
+/// nested tree, whose members are records themselves.
+fn is_meta(record: &Record) -> bool {
+    matches!(record.get("dtype"), Some(Value::String { .. }))
+        || matches!(record.get("shape"), Some(Value::List { .. }))
+}
+
+/// Whether the key is about a group rather than a dataset, e.g. `@attributes`
+/// or `grp/@attributes`.
+fn is_special(path: &str) -> bool {
+    path.rsplit('/')
+        .next()
+        .is_some_and(|name| name.starts_with('@'))
+}
+
+/// Collects the datasets under a group, not descending again into its `ancestors` at link cycles.
+fn collect_datasets(
+    group: &Group,
+    ancestors: &mut Vec<(u64, LocationToken)>,
+    datasets: &mut HashMap<String, Dataset>,
+) -> Result<()> {
+    let info = group.loc_info()?;
+    ancestors.push((info.fileno, info.token));
+    for ds in group.datasets()? {
+        datasets.insert(ds.name().trim_start_matches('/').to_string(), ds);
+    }
+    for g in group.groups()? {
+        let info = g.loc_info()?;
+        if !ancestors.contains(&(info.fileno, info.token)) {
+            collect_datasets(&g, ancestors, datasets)?;
+        }
+    }
+    ancestors.pop();
+    Ok(())
+}
+
+fn mismatch(path: &str, field: &str, expected: String, actual: String, span: Span) -> Value {
+    let cols = vec![
+        "path".to_string(),
+        "field".to_string(),
+        "expected".to_string(),
+        "actual".to_string(),
+    ];
+    let vals = vec![
+        Value::String {
+            val: path.to_string(),
+            internal_span: span,
+        },
+        Value::String {
+            val: field.to_string(),
+            internal_span: span,
+        },
+        Value::String {
+            val: expected,
+            internal_span: span,
+        },
+        Value::String {
+            val: actual,
+            internal_span: span,
+        },
+    ];
+    Value::Record {
+        val: Record::from_raw_cols_vals(cols, vals, span, span)
+            .unwrap()
+            .into(),
+        internal_span: span,
+    }
+}
+
+fn validate(bytes: &[u8], schema: &Record, span: Span) -> Result<Value> {
+    let mut expected = vec![];
+    parse_schema(schema, &mut expected)?;
+    let file = FileImage::new(bytes)?;
+    let mut datasets = HashMap::new();
+    collect_datasets(&file, &mut vec![], &mut datasets)?;
+    let mut mismatches = vec![];
+    for (path, expected) in expected {
+        let Some(ds) = datasets.get(&path) else {
+            mismatches.push(mismatch(
+                &path,
+                "exists",
+                "dataset".to_string(),
+                "missing".to_string(),
+                span,
+            ));
+            continue;
+        };
+        if let Some(dtype) = expected.dtype {
+            let actual = Dtype::from_datatype(&ds.dtype()?.to_native()?)?.to_string();
+            if actual != dtype {
+                mismatches.push(mismatch(&path, "dtype", dtype, actual, span));
+            }
+        }
+        if let Some(shape) = expected.shape {
+            let actual: Vec<i64> = ds.shape().into_iter().map(|d| d as _).collect();
+            if actual != shape {
+                mismatches.push(mismatch(
+                    &path,
+                    "shape",
+                    format!("{shape:?}"),
+                    format!("{actual:?}"),
+                    span,
+                ));
+            }
+        }
+    }
+    if mismatches.is_empty() {
+        Ok(Value::String {
+            val: "ok".to_string(),
+            internal_span: span,
+        })
+    } else {
+        Ok(Value::List {
+            vals: mismatches,
+            internal_span: span,
+        })
+    }
+}
+
+pub fn signature() -> Signature {
+    Signature::build("hdf5 validate")
+        .description("Check HDF5 binary against an expected schema")
+        .allow_variants_without_examples(true)
+        .input_output_types(vec![(Type::Binary, Type::Any)])
+        .category(Category::Experimental)
+        .required(
+            "schema",
+            SyntaxShape::OneOf(vec![SyntaxShape::Record(vec![]), SyntaxShape::Table(vec![])]),
+            "record of `{dtype, shape}` keyed by dataset path, or the table of them from `from hdf5 --meta --flatten-paths`, or the nested tree of `from hdf5 --schema`",
+        )
+        .filter()
+}
+
+/// The schema as a record, also from the `path` and `value` table of
+/// `from hdf5 --meta --flatten-paths`.
+#[allow(clippy::result_large_err)]
+fn schema_record(schema: Value) -> Result<Record, ShellError> {
+    let Value::List { vals, .. } = schema else {
+        return schema.into_record();
+    };
+    vals.into_iter()
+        .map(|row| {
+            let mut row = row.into_record()?;
+            let span = row.get("path").map_or(Span::unknown(), Value::span);
+            match (row.remove("path"), row.remove("value")) {
+                (Some(path), Some(value)) => Ok((path.into_string()?, value)),
+                _ => Err(ShellError::GenericError {
+                    error: "schema rows should have a path and a value".to_string(),
+                    msg: String::new(),
+                    span: Some(span),
+                    help: None,
+                    inner: vec![],
+                }),
+            }
+        })
+        .collect()
+}
+
+#[allow(clippy::result_large_err)]
+pub fn run(call: &EvaluatedCall, input: PipelineData) -> Result<PipelineData, LabeledError> {
+    let schema: Value = call.req(0)?;
+    let schema = schema_record(schema)?;
+    with_binary_input(input, |bytes, span| validate(bytes, &schema, span))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        from_hdf5::tests::{convert, image},
+        to_hdf5::to_hdf5_bytes,
+    };
+    use nu_protocol::record;
+
+    fn file_with_attributes() -> Vec<u8> {
+        let units = Value::test_record(record! { "units" => Value::test_string("m") });
+        let input = record! {
+            "@attributes" => Value::test_record(record! { "title" => Value::test_string("t") }),
+            "grp" => Value::test_record(record! {
+                "@attributes" => units.clone(),
+                "x" => Value::test_record(record! {
+                    "value" => Value::test_list(vec![Value::test_int(1), Value::test_int(2)]),
+                    "@attributes" => units,
+                }),
+            }),
+            "y" => Value::test_list(vec![Value::test_float(1.0)]),
+        };
+        to_hdf5_bytes(&input).unwrap()
+    }
+
+    #[test]
+    fn skips_group_entries_and_unwraps_values() {
+        let schema = record! {
+            "@attributes" => Value::test_record(record! { "title" => Value::test_string("t") }),
+            "grp/@attributes" => Value::test_record(Record::new()),
+            "grp/x" => Value::test_record(record! {
+                "value" => Value::test_record(record! {
+                    "dtype" => Value::test_string("int64"),
+                    "shape" => Value::test_list(vec![Value::test_int(2)]),
+                }),
+                "@attributes" => Value::test_record(Record::new()),
+            }),
+        };
+        let mut expected = vec![];
+        parse_schema(&schema, &mut expected).unwrap();
+        assert_eq!(expected.len(), 1);
+        let (path, expected) = &expected[0];
+        assert_eq!(path, "grp/x");
+        assert_eq!(expected.dtype.as_deref(), Some("int64"));
+        assert_eq!(expected.shape, Some(vec![2]));
+    }
+
+    #[test]
+    fn walks_nested_groups() {
+        let meta = |dtype: &str| {
+            Value::test_record(record! {
+                "name" => Value::test_string("x"),
+                "dtype" => Value::test_string(dtype),
+                "shape" => Value::test_list(vec![]),
+            })
+        };
+        let schema = record! {
+            "grp" => Value::test_record(record! {
+                "grp/x" => meta("int8"),
+                "grp/sub" => Value::test_record(record! { "grp/sub/y" => meta("float64") }),
+            }),
+            "z" => meta("bool"),
+        };
+        let mut expected = vec![];
+        parse_schema(&schema, &mut expected).unwrap();
+        let paths: Vec<(&str, Option<&str>)> = expected
+            .iter()
+            .map(|(path, e)| (path.as_str(), e.dtype.as_deref()))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                ("grp/x", Some("int8")),
+                ("grp/sub/y", Some("float64")),
+                ("z", Some("bool")),
+            ]
+        );
+    }
+
+    #[test]
+    fn stops_at_link_cycles() {
+        let bytes = image("validate_stops_at_link_cycles.h5", |file| {
+            let group = file.create_group("a").unwrap();
+            group
+                .new_dataset_builder()
+                .with_data(&[1i32, 2])
+                .create("x")
+                .unwrap();
+            file.link_hard("/a", "/a/self").unwrap();
+        });
+        let schema = record! {
+            "a/x" => Value::test_record(record! {
+                "dtype" => Value::test_string("int32"),
+                "shape" => Value::test_list(vec![Value::test_int(2)]),
+            }),
+        };
+        let result = validate(&bytes, &schema, Span::test_data()).unwrap();
+        assert_eq!(result, Value::test_string("ok"));
+    }
+
+    #[test]
+    fn matches_own_schema() {
+        let bytes = file_with_attributes();
+        let schema = convert(&bytes, &["schema"]).unwrap();
+        let result = validate(&bytes, &schema.into_record().unwrap(), Span::test_data()).unwrap();
+        assert_eq!(result, Value::test_string("ok"));
+    }
+
+    #[test]
+    fn reports_mismatches_against_schema() {
+        let schema = convert(&file_with_attributes(), &["schema"]).unwrap();
+        let other = to_hdf5_bytes(&record! {
+            "grp" => Value::test_record(record! {
+                "x" => Value::test_list(vec![Value::test_float(1.0)]),
+            }),
+        })
+        .unwrap();
+        let result = validate(&other, &schema.into_record().unwrap(), Span::test_data()).unwrap();
+        let mut rows: Vec<(String, String)> = result
+            .into_list()
+            .unwrap()
+            .into_iter()
+            .map(|row| {
+                let row = row.into_record().unwrap();
+                let field = |name| row.get(name).unwrap().as_str().unwrap().to_string();
+                (field("path"), field("field"))
+            })
+            .collect();
+        rows.sort();
+        assert_eq!(
+            rows,
+            [
+                ("grp/x".to_string(), "dtype".to_string()),
+                ("grp/x".to_string(), "shape".to_string()),
+                ("y".to_string(), "exists".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn round_trips_meta_with_attributes() {
+        let bytes = file_with_attributes();
+        let schema = convert(&bytes, &["meta", "flatten-paths"]).unwrap();
+        let schema = schema_record(schema).unwrap();
+        let result = validate(&bytes, &schema, Span::test_data()).unwrap();
+        assert_eq!(result, Value::test_string("ok"));
+    }
+}
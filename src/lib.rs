@@ -0,0 +1,36 @@
+#![feature(c_size_t)]
+#![feature(iterator_try_collect)]
+
+//! The conversion of HDF5 files into Nushell values behind the commands of
+//! the plugin, for other plugins to reuse.
+//!
+//! [`hdf5_to_value`] converts a whole image as `from hdf5` does without
+//! flags, and [`from_hdf5::read_path`] only a group or a dataset of it.
+//! [`hdf5_ext::FileImage`] opens an image in memory for reading it with the
+//! `hdf5` crate, and closes the objects opened from it when it's dropped.
+
+pub mod dtype;
+pub mod from_hdf5;
+pub mod hdf5_attrs;
+pub mod hdf5_describe;
+pub mod hdf5_diff;
+pub mod hdf5_dtype;
+pub mod hdf5_dump;
+pub mod hdf5_ext;
+pub mod hdf5_fileinfo;
+pub mod hdf5_layout;
+pub mod hdf5_ls;
+pub mod hdf5_open;
+pub mod hdf5_set;
+pub mod hdf5_stat;
+pub mod hdf5_validate;
+mod sha256;
+pub mod to_hdf5;
+
+use nu_protocol::{Span, Value};
+
+/// Converts a whole HDF5 image into a record of its groups and datasets,
+/// with attributes, the same as `from hdf5` without flags.
+pub fn hdf5_to_value(bytes: &[u8], span: Span) -> hdf5::Result<Value> {
+    from_hdf5::read_path(bytes, "/", None, span)
+}
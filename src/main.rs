@@ -1,28 +1,109 @@
-#![feature(c_size_t)]
-#![feature(iterator_try_collect)]
-
-mod from_hdf5;
-mod hdf5_ext;
-
+use hdf5_open::{Hdf5Dataset, Hdf5Handle, Images};
 use nu_plugin::{
     serve_plugin, EngineInterface, EvaluatedCall, MsgPackSerializer, Plugin, PluginCommand,
 };
-use nu_protocol::{LabeledError, PipelineData, Signature};
+use nu_plugin_from_hdf5::{
+    from_hdf5, hdf5_attrs, hdf5_describe, hdf5_diff, hdf5_dtype, hdf5_dump, hdf5_fileinfo,
+    hdf5_layout, hdf5_ls, hdf5_open, hdf5_set, hdf5_stat, hdf5_validate, to_hdf5,
+};
+use nu_protocol::{
+    ast::PathMember, CustomValue, Example, LabeledError, PipelineData, Signature, Spanned, Value,
+};
 
-struct FromHdf5;
+#[derive(Default)]
+struct Hdf5Plugin {
+    images: Images,
+}
 
-impl Plugin for FromHdf5 {
+impl Plugin for Hdf5Plugin {
     fn version(&self) -> String {
         env!("CARGO_PKG_VERSION").to_string()
     }
 
     fn commands(&self) -> Vec<Box<dyn PluginCommand<Plugin = Self>>> {
-        vec![Box::new(FromHdf5)]
+        vec![
+            Box::new(FromHdf5),
+            Box::new(Hdf5Attrs),
+            Box::new(Hdf5Describe),
+            Box::new(Hdf5Diff),
+            Box::new(Hdf5Dtype),
+            Box::new(Hdf5Dump),
+            Box::new(Hdf5Fileinfo),
+            Box::new(Hdf5Layout),
+            Box::new(Hdf5Ls),
+            Box::new(Hdf5Set),
+            Box::new(Hdf5Stat),
+            Box::new(Hdf5Validate),
+            Box::new(Hdf5Open),
+            Box::new(Hdf5Get),
+            Box::new(ToHdf5),
+        ]
+    }
+
+    fn custom_value_to_base_value(
+        &self,
+        _engine: &EngineInterface,
+        custom_value: Spanned<Box<dyn CustomValue>>,
+    ) -> Result<Value, LabeledError> {
+        match custom_value.item.as_any().downcast_ref::<Hdf5Dataset>() {
+            Some(dataset) => self.images.read(dataset, custom_value.span),
+            None => Ok(custom_value.item.to_base_value(custom_value.span)?),
+        }
+    }
+
+    fn custom_value_follow_path_int(
+        &self,
+        engine: &EngineInterface,
+        custom_value: Spanned<Box<dyn CustomValue>>,
+        index: Spanned<usize>,
+    ) -> Result<Value, LabeledError> {
+        let member = PathMember::int(index.item, false, index.span);
+        self.follow_path(engine, custom_value, member)
+    }
+
+    fn custom_value_follow_path_string(
+        &self,
+        engine: &EngineInterface,
+        custom_value: Spanned<Box<dyn CustomValue>>,
+        column_name: Spanned<String>,
+    ) -> Result<Value, LabeledError> {
+        let member = PathMember::string(column_name.item, false, column_name.span);
+        self.follow_path(engine, custom_value, member)
+    }
+
+    fn custom_value_dropped(
+        &self,
+        _engine: &EngineInterface,
+        custom_value: Box<dyn CustomValue>,
+    ) -> Result<(), LabeledError> {
+        let any = custom_value.as_any();
+        if let Some(handle) = any.downcast_ref::<Hdf5Handle>() {
+            self.images.release(handle.id);
+        } else if let Some(dataset) = any.downcast_ref::<Hdf5Dataset>() {
+            self.images.release(dataset.id);
+        }
+        Ok(())
+    }
+}
+
+impl Hdf5Plugin {
+    /// Follows a cell path into the data of a lazy dataset, read for it.
+    #[allow(clippy::result_large_err)]
+    fn follow_path(
+        &self,
+        engine: &EngineInterface,
+        custom_value: Spanned<Box<dyn CustomValue>>,
+        member: PathMember,
+    ) -> Result<Value, LabeledError> {
+        let value = self.custom_value_to_base_value(engine, custom_value)?;
+        Ok(value.follow_cell_path(&[member], false)?)
     }
 }
 
+struct FromHdf5;
+
 impl PluginCommand for FromHdf5 {
-    type Plugin = FromHdf5;
+    type Plugin = Hdf5Plugin;
 
     fn name(&self) -> &str {
         "from hdf5"
@@ -36,17 +117,417 @@ impl PluginCommand for FromHdf5 {
         from_hdf5::signature()
     }
 
+    fn extra_description(&self) -> &str {
+        from_hdf5::EXTRA_DESCRIPTION
+    }
+
+    fn examples(&self) -> Vec<Example<'_>> {
+        from_hdf5::examples()
+    }
+
+    fn run(
+        &self,
+        plugin: &Hdf5Plugin,
+        engine: &EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        from_hdf5::run(&plugin.images, engine, call, input)
+    }
+}
+
+struct Hdf5Attrs;
+
+impl PluginCommand for Hdf5Attrs {
+    type Plugin = Hdf5Plugin;
+
+    fn name(&self) -> &str {
+        "hdf5 attrs"
+    }
+
+    fn description(&self) -> &str {
+        "Read the attributes of a group or a dataset as a record of their names"
+    }
+
+    fn signature(&self) -> Signature {
+        hdf5_attrs::signature()
+    }
+
+    fn run(
+        &self,
+        _plugin: &Hdf5Plugin,
+        _engine: &EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        hdf5_attrs::run(call, input)
+    }
+}
+
+struct Hdf5Describe;
+
+impl PluginCommand for Hdf5Describe {
+    type Plugin = Hdf5Plugin;
+
+    fn name(&self) -> &str {
+        "hdf5 describe"
+    }
+
+    fn description(&self) -> &str {
+        "Compute statistics of a numeric dataset a block at a time, without reading it into values"
+    }
+
+    fn signature(&self) -> Signature {
+        hdf5_describe::signature()
+    }
+
+    fn run(
+        &self,
+        _plugin: &Hdf5Plugin,
+        _engine: &EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        hdf5_describe::run(call, input)
+    }
+}
+
+struct Hdf5Diff;
+
+impl PluginCommand for Hdf5Diff {
+    type Plugin = Hdf5Plugin;
+
+    fn name(&self) -> &str {
+        "hdf5 diff"
+    }
+
+    fn description(&self) -> &str {
+        "Report the objects added, removed or changed between two HDF5 files, and the elements that differ"
+    }
+
+    fn signature(&self) -> Signature {
+        hdf5_diff::signature()
+    }
+
+    fn run(
+        &self,
+        _plugin: &Hdf5Plugin,
+        engine: &EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        hdf5_diff::run(engine, call, input)
+    }
+}
+
+struct Hdf5Dtype;
+
+impl PluginCommand for Hdf5Dtype {
+    type Plugin = Hdf5Plugin;
+
+    fn name(&self) -> &str {
+        "hdf5 dtype"
+    }
+
+    fn description(&self) -> &str {
+        "Describe the datatype of a dataset, with the layout of compound members"
+    }
+
+    fn signature(&self) -> Signature {
+        hdf5_dtype::signature()
+    }
+
+    fn run(
+        &self,
+        _plugin: &Hdf5Plugin,
+        _engine: &EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        hdf5_dtype::run(call, input)
+    }
+}
+
+struct Hdf5Dump;
+
+impl PluginCommand for Hdf5Dump {
+    type Plugin = Hdf5Plugin;
+
+    fn name(&self) -> &str {
+        "hdf5 dump"
+    }
+
+    fn description(&self) -> &str {
+        "Render a file or a part of it as text in the DDL of h5dump, for diffing or other tools"
+    }
+
+    fn signature(&self) -> Signature {
+        hdf5_dump::signature()
+    }
+
+    fn run(
+        &self,
+        _plugin: &Hdf5Plugin,
+        _engine: &EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        hdf5_dump::run(call, input)
+    }
+}
+
+struct Hdf5Fileinfo;
+
+impl PluginCommand for Hdf5Fileinfo {
+    type Plugin = Hdf5Plugin;
+
+    fn name(&self) -> &str {
+        "hdf5 fileinfo"
+    }
+
+    fn description(&self) -> &str {
+        "Show the superblock version and the library version bounds of the file format"
+    }
+
+    fn signature(&self) -> Signature {
+        hdf5_fileinfo::signature()
+    }
+
+    fn run(
+        &self,
+        _plugin: &Hdf5Plugin,
+        _engine: &EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        hdf5_fileinfo::run(call, input)
+    }
+}
+
+struct Hdf5Layout;
+
+impl PluginCommand for Hdf5Layout {
+    type Plugin = Hdf5Plugin;
+
+    fn name(&self) -> &str {
+        "hdf5 layout"
+    }
+
+    fn description(&self) -> &str {
+        "Report the chunks, filters and compression ratio of each dataset, flagging tiny chunks and large uncompressed data"
+    }
+
+    fn signature(&self) -> Signature {
+        hdf5_layout::signature()
+    }
+
+    fn run(
+        &self,
+        _plugin: &Hdf5Plugin,
+        _engine: &EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        hdf5_layout::run(call, input)
+    }
+}
+
+struct Hdf5Ls;
+
+impl PluginCommand for Hdf5Ls {
+    type Plugin = Hdf5Plugin;
+
+    fn name(&self) -> &str {
+        "hdf5 ls"
+    }
+
+    fn description(&self) -> &str {
+        "List the paths, shapes, datatypes and sizes of the groups and datasets of a file"
+    }
+
+    fn signature(&self) -> Signature {
+        hdf5_ls::signature()
+    }
+
+    fn run(
+        &self,
+        _plugin: &Hdf5Plugin,
+        _engine: &EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        hdf5_ls::run(call, input)
+    }
+}
+
+struct Hdf5Set;
+
+impl PluginCommand for Hdf5Set {
+    type Plugin = Hdf5Plugin;
+
+    fn name(&self) -> &str {
+        "hdf5 set"
+    }
+
+    fn description(&self) -> &str {
+        "Write a value over a dataset or an attribute of an HDF5 file, checked against its datatype and shape"
+    }
+
+    fn signature(&self) -> Signature {
+        hdf5_set::signature()
+    }
+
+    fn run(
+        &self,
+        _plugin: &Hdf5Plugin,
+        engine: &EngineInterface,
+        call: &EvaluatedCall,
+        _input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        hdf5_set::run(engine, call)
+    }
+}
+
+struct Hdf5Stat;
+
+impl PluginCommand for Hdf5Stat {
+    type Plugin = Hdf5Plugin;
+
+    fn name(&self) -> &str {
+        "hdf5 stat"
+    }
+
+    fn description(&self) -> &str {
+        "Count the groups, datasets and attributes of a file, with the data and storage sizes of the datasets"
+    }
+
+    fn signature(&self) -> Signature {
+        hdf5_stat::signature()
+    }
+
+    fn run(
+        &self,
+        _plugin: &Hdf5Plugin,
+        _engine: &EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        hdf5_stat::run(call, input)
+    }
+}
+
+struct Hdf5Validate;
+
+impl PluginCommand for Hdf5Validate {
+    type Plugin = Hdf5Plugin;
+
+    fn name(&self) -> &str {
+        "hdf5 validate"
+    }
+
+    fn description(&self) -> &str {
+        "Compare the datasets of a file with an expected schema, outputting ok or the mismatches"
+    }
+
+    fn signature(&self) -> Signature {
+        hdf5_validate::signature()
+    }
+
     fn run(
         &self,
-        _plugin: &FromHdf5,
+        _plugin: &Hdf5Plugin,
+        _engine: &EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        hdf5_validate::run(call, input)
+    }
+}
+
+struct Hdf5Open;
+
+impl PluginCommand for Hdf5Open {
+    type Plugin = Hdf5Plugin;
+
+    fn name(&self) -> &str {
+        "hdf5 open"
+    }
+
+    fn description(&self) -> &str {
+        "Open HDF5 binary as a handle, to read only the groups and datasets asked for"
+    }
+
+    fn signature(&self) -> Signature {
+        hdf5_open::open_signature()
+    }
+
+    fn run(
+        &self,
+        plugin: &Hdf5Plugin,
         _engine: &EngineInterface,
         _call: &EvaluatedCall,
         input: PipelineData,
     ) -> Result<PipelineData, LabeledError> {
-        from_hdf5::run(input)
+        hdf5_open::open(&plugin.images, input)
+    }
+}
+
+struct Hdf5Get;
+
+impl PluginCommand for Hdf5Get {
+    type Plugin = Hdf5Plugin;
+
+    fn name(&self) -> &str {
+        "hdf5 get"
+    }
+
+    fn description(&self) -> &str {
+        "Read a group or a dataset at a path of a handle from `hdf5 open`"
+    }
+
+    fn signature(&self) -> Signature {
+        hdf5_open::get_signature()
+    }
+
+    fn run(
+        &self,
+        plugin: &Hdf5Plugin,
+        _engine: &EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        hdf5_open::get(&plugin.images, call, input)
+    }
+}
+
+struct ToHdf5;
+
+impl PluginCommand for ToHdf5 {
+    type Plugin = Hdf5Plugin;
+
+    fn name(&self) -> &str {
+        "to hdf5"
+    }
+
+    fn description(&self) -> &str {
+        "Write a record as HDF5 binary, with nested records as groups and lists as datasets"
+    }
+
+    fn signature(&self) -> Signature {
+        to_hdf5::signature()
+    }
+
+    fn run(
+        &self,
+        _plugin: &Hdf5Plugin,
+        _engine: &EngineInterface,
+        call: &EvaluatedCall,
+        input: PipelineData,
+    ) -> Result<PipelineData, LabeledError> {
+        to_hdf5::run(call, input)
     }
 }
 
 fn main() {
-    serve_plugin(&FromHdf5, MsgPackSerializer);
+    serve_plugin(&Hdf5Plugin::default(), MsgPackSerializer);
 }
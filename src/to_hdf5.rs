@@ -0,0 +1,324 @@
+use crate::hdf5_ext::ToImage;
+use hdf5::{
+    types::{VarLenAscii, VarLenUnicode},
+    File, Group, H5Type, Location, Result,
+};
+use ndarray::{ArrayView, IxDyn};
+use nu_plugin::EvaluatedCall;
+use nu_protocol::{Category, LabeledError, PipelineData, Record, Signature, Type, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The elements of a dataset or an attribute, all of the same type.
+pub(crate) enum Data {
+    Int(Vec<i64>),
+    Float(Vec<f64>),
+    Bool(Vec<bool>),
+    String(Vec<VarLenUnicode>),
+}
+
+/// The shape of nested lists, following the first elements.
+fn infer_shape(mut value: &Value) -> Vec<usize> {
+    let mut shape = vec![];
+    while let Value::List { vals, .. } = value {
+        shape.push(vals.len());
+        match vals.first() {
+            Some(first) => value = first,
+            None => break,
+        }
+    }
+    shape
+}
+
+fn collect<'v>(value: &'v Value, shape: &[usize], leaves: &mut Vec<&'v Value>) -> Result<()> {
+    match (value, shape) {
+        (Value::List { vals, .. }, [len, rest @ ..]) if vals.len() == *len => {
+            for val in vals {
+                collect(val, rest, leaves)?;
+            }
+            Ok(())
+        }
+        (Value::List { .. }, _) | (_, [_, ..]) => Err("ragged lists are not supported".into()),
+        (val, []) => {
+            leaves.push(val);
+            Ok(())
+        }
+    }
+}
+
+pub(crate) fn to_data(value: &Value) -> Result<(Data, Vec<usize>)> {
+    let shape = infer_shape(value);
+    let mut leaves = vec![];
+    collect(value, &shape, &mut leaves)?;
+    let mixed = || format!("mixed types in a list of {}", leaves[0].get_type());
+    let data = match leaves.first() {
+        None => return Err("the type of an empty list is unknown".into()),
+        Some(Value::Int { .. }) => Data::Int(
+            leaves
+                .iter()
+                .map(|v| v.as_int().map_err(|_| mixed()))
+                .try_collect()?,
+        ),
+        Some(Value::Float { .. }) => Data::Float(
+            leaves
+                .iter()
+                .map(|v| v.as_float().map_err(|_| mixed()))
+                .try_collect()?,
+        ),
+        Some(Value::Bool { .. }) => Data::Bool(
+            leaves
+                .iter()
+                .map(|v| v.as_bool().map_err(|_| mixed()))
+                .try_collect()?,
+        ),
+        Some(Value::String { .. }) => Data::String(
+            leaves
+                .iter()
+                .map(|v| {
+                    v.as_str()
+                        .map_err(|_| mixed())
+                        .and_then(|s| s.parse::<VarLenUnicode>().map_err(|e| e.to_string()))
+                })
+                .try_collect()?,
+        ),
+        Some(v) => return Err(format!("unsupported value of type {}", v.get_type()).into()),
+    };
+    Ok((data, shape))
+}
+
+fn write_dataset<T: H5Type>(group: &Group, name: &str, data: &[T], shape: &[usize]) -> Result<()> {
+    let view = ArrayView::from_shape(IxDyn(shape), data).map_err(|e| e.to_string())?;
+    group.new_dataset_builder().with_data(view).create(name)?;
+    Ok(())
+}
+
+pub(crate) fn write_attr<T: H5Type>(
+    location: &Location,
+    name: &str,
+    data: &[T],
+    shape: &[usize],
+) -> Result<()> {
+    let view = ArrayView::from_shape(IxDyn(shape), data).map_err(|e| e.to_string())?;
+    location.new_attr_builder().with_data(view).create(name)?;
+    Ok(())
+}
+
+fn write_value(group: &Group, name: &str, value: &Value) -> Result<()> {
+    let (data, shape) = to_data(value)?;
+    match data {
+        Data::Int(data) => write_dataset(group, name, &data, &shape),
+        Data::Float(data) => write_dataset(group, name, &data, &shape),
+        Data::Bool(data) => write_dataset(group, name, &data, &shape),
+        Data::String(data) => write_dataset(group, name, &data, &shape),
+    }
+}
+
+fn write_attrs(location: &Location, attrs: &Value) -> Result<()> {
+    let attrs = attrs
+        .as_record()
+        .map_err(|_| "@attributes should be a record")?;
+    for (name, value) in attrs.iter() {
+        let (data, shape) = to_data(value).map_err(|e| format!("attribute {name}: {e}"))?;
+        match data {
+            Data::Int(data) => write_attr(location, name, &data, &shape),
+            Data::Float(data) => write_attr(location, name, &data, &shape),
+            Data::Bool(data) => write_attr(location, name, &data, &shape),
+            Data::String(data) => write_attr(location, name, &data, &shape),
+        }?;
+    }
+    Ok(())
+}
+
+/// Whether the record is data wrapped with its datatype and shape, as
+/// `from hdf5 --faithful` outputs it.
+fn is_faithful(record: &Record) -> bool {
+    record.contains("_hdf5_dtype") && record.contains("_shape") && record.contains("data")
+}
+
+/// Converts the elements into the type of a column of `--faithful`.
+fn to_typed<T>(
+    leaves: &[&Value],
+    ty: &str,
+    convert: impl Fn(&Value) -> Option<T>,
+) -> Result<Vec<T>> {
+    leaves
+        .iter()
+        .map(|v| {
+            convert(v).ok_or_else(|| {
+                format!(
+                    "{} is not a {ty}",
+                    v.to_abbreviated_string(&Default::default())
+                )
+                .into()
+            })
+        })
+        .try_collect()
+}
+
+fn int<T: TryFrom<i64>>(v: &Value) -> Option<T> {
+    T::try_from(v.as_int().ok()?).ok()
+}
+
+/// Writes data of `--faithful` with the datatype and the shape it was read
+/// with. Datatypes without a counterpart here, e.g. compounds, are inferred
+/// from the data as usual.
+fn write_faithful(group: &Group, name: &str, record: &Record) -> Result<()> {
+    let ty = record
+        .get("_hdf5_dtype")
+        .and_then(|dtype| dtype.as_record().ok()?.get("type")?.as_str().ok())
+        .ok_or("_hdf5_dtype should be a record of the type")?;
+    let shape: Vec<usize> = record
+        .get("_shape")
+        .unwrap()
+        .as_list()
+        .and_then(|dims| dims.iter().map(|d| Ok(d.as_int()? as usize)).try_collect())
+        .map_err(|_| "_shape should be a list of ints")?;
+    let data = record.get("data").unwrap();
+    let mut leaves = vec![];
+    if shape.iter().product::<usize>() > 0 {
+        collect(data, &shape, &mut leaves).map_err(|_| "data should be of _shape")?;
+    }
+    macro_rules! write {
+        ($convert: expr) => {
+            write_dataset(group, name, &to_typed(&leaves, ty, $convert)?, &shape)
+        };
+    }
+    match ty {
+        "int8" => write!(int::<i8>),
+        "int16" => write!(int::<i16>),
+        "int32" => write!(int::<i32>),
+        "int64" => write!(int::<i64>),
+        "uint8" => write!(int::<u8>),
+        "uint16" => write!(int::<u16>),
+        "uint32" => write!(int::<u32>),
+        // Out of the range of int, they are strings of the digits.
+        "uint64" => write!(|v| match v {
+            Value::String { val, .. } => val.parse::<u64>().ok(),
+            v => int::<u64>(v),
+        }),
+        "float32" => write!(|v| v.as_float().ok().map(|f| f as f32)),
+        "float64" => write!(|v| v.as_float().ok()),
+        "bool" => write!(|v| v.as_bool().ok()),
+        "string (var len)" => write!(|v| VarLenAscii::from_ascii(v.as_str().ok()?).ok()),
+        "unicode (var len)" => write!(|v| v.as_str().ok()?.parse::<VarLenUnicode>().ok()),
+        _ => write_value(group, name, data),
+    }
+}
+
+/// Writes a column as a dataset, with the datatype of `--faithful` if wrapped.
+fn write_member(group: &Group, name: &str, value: &Value) -> Result<()> {
+    match value {
+        Value::Record { val, .. } if is_faithful(val) => write_faithful(group, name, val),
+        value => write_value(group, name, value),
+    }
+}
+
+/// Whether the record is a dataset beside its attributes, as `from hdf5`
+/// outputs them.
+fn is_dataset(record: &Record) -> bool {
+    record.contains("value")
+        && record
+            .columns()
+            .all(|col| col == "value" || col.starts_with('@'))
+}
+
+/// Writes the columns of a record as datasets, and nested records as groups.
+fn write_group(group: &Group, record: &Record) -> Result<()> {
+    for (key, value) in record.iter() {
+        // `from hdf5` keys objects by their full paths.
+        let name = key.rsplit('/').next().unwrap_or(key);
+        if key == "@attributes" {
+            write_attrs(group, value)?;
+        } else if name.starts_with('@') {
+            // Other columns about the group, e.g. `@truncated`.
+        } else if let Value::Record { val, .. } = value {
+            if is_faithful(val) {
+                write_faithful(group, name, val).map_err(|e| format!("{key}: {e}"))?;
+            } else if is_dataset(val) {
+                write_member(group, name, val.get("value").unwrap())?;
+                let ds = group.dataset(name)?;
+                if let Some(attrs) = val.get("@attributes") {
+                    write_attrs(&ds, attrs)?;
+                }
+            } else {
+                write_group(&group.create_group(name)?, val)?;
+            }
+        } else {
+            write_value(group, name, value).map_err(|e| format!("{key}: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn to_hdf5_bytes(record: &Record) -> Result<Vec<u8>> {
+    // The name must be unique among the files open in memory.
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    let name = format!("to_hdf5_{}.h5", NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    let file = File::with_options()
+        .with_fapl(|p| p.core_filebacked(false))
+        .create(name)?;
+    write_group(&file, record)?;
+    file.to_image()
+}
+
+pub fn signature() -> Signature {
+    Signature::build("to hdf5")
+        .description("Convert a record into HDF5 binary")
+        .allow_variants_without_examples(true)
+        .input_output_types(vec![(Type::Any, Type::Binary)])
+        .category(Category::Experimental)
+        .filter()
+}
+
+#[allow(clippy::result_large_err)]
+pub fn run(call: &EvaluatedCall, input: PipelineData) -> Result<PipelineData, LabeledError> {
+    let value = input.into_value(call.head)?;
+    let span = value.span();
+    let Value::Record { val, .. } = &value else {
+        return Err(LabeledError::new(format!(
+            "requires record input, got {}",
+            value.get_type()
+        )));
+    };
+    let bytes = to_hdf5_bytes(val).map_err(|e| LabeledError::new(e.to_string()))?;
+    Ok(PipelineData::Value(
+        Value::Binary {
+            val: bytes,
+            internal_span: span,
+        },
+        None,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_hdf5::tests::convert;
+
+    #[test]
+    fn round_trips_faithful() {
+        let file = File::with_options()
+            .with_fapl(|p| p.core_filebacked(false))
+            .create("round_trips_faithful.h5")
+            .unwrap();
+        file.new_dataset_builder()
+            .with_data(&[1u8, 2, 255])
+            .create("bytes")
+            .unwrap();
+        file.new_dataset_builder()
+            .with_data(&ndarray::arr2(&[[-1i16, 2], [3, 4]]))
+            .create("grid")
+            .unwrap();
+        file.new_dataset_builder()
+            .with_data(&[u64::MAX])
+            .create("big")
+            .unwrap();
+        file.new_dataset::<f32>()
+            .shape([0])
+            .create("empty")
+            .unwrap();
+        let bytes = file.to_image().unwrap();
+        let read = convert(&bytes, &["faithful"]).unwrap();
+        let written = to_hdf5_bytes(read.as_record().unwrap()).unwrap();
+        assert_eq!(convert(&written, &["faithful"]).unwrap(), read);
+    }
+}